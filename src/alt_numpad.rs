@@ -0,0 +1,176 @@
+use super::ScanType;
+
+// Code page 437 glyphs for the control range and the upper half, the rest is ASCII
+const CP437_LOW: &str = "\u{0}☺☻♥♦♣♠•◘○◙♂♀♪♫☼►◄↕‼¶§▬↨↑↓→←∟↔▲▼";
+const CP437_HIGH: &str = concat!(
+    "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»",
+    "░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}",
+);
+
+pub(crate) fn cp437_char(byte: u8) -> char {
+    return match byte {
+        0x00..=0x1f => CP437_LOW.chars().nth(byte as usize).unwrap(),
+        0x7f => '⌂',
+        0x80..=0xff => CP437_HIGH.chars().nth(byte as usize - 0x80).unwrap(),
+        _ => byte as char,
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    // Alt+65, the number is a code page 437 character and wraps at 256 like on DOS
+    Codepage,
+    // Alt+0233, a leading zero makes it a Unicode code point
+    Decimal,
+    // Alt, keypad '+', then hex digits from the keypad or the A-F keys
+    Hex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A character being entered as a number while Alt is held
+pub(crate) struct AltNumpad {
+    entry: Entry,
+    value: u32,
+    digits: u8,
+}
+
+// Past this the number can't be a character anymore, further digits are ignored
+const MAX_VALUE: u32 = 0x10ffff;
+
+impl AltNumpad {
+    pub(crate) const fn new() -> Self {
+        return Self {
+            entry: Entry::Codepage,
+            value: 0,
+            digits: 0,
+        };
+    }
+
+    pub(crate) fn is_hex(&self) -> bool {
+        return self.entry == Entry::Hex;
+    }
+
+    // Keypad '+' switches to hex, but only before the first digit
+    pub(crate) fn start_hex(&mut self) -> bool {
+        if self.digits != 0 {
+            return false;
+        }
+
+        self.entry = Entry::Hex;
+
+        return true;
+    }
+
+    pub(crate) fn push_digit(&mut self, digit: u32) {
+        if self.entry != Entry::Hex && self.digits == 0 && digit == 0 {
+            self.entry = Entry::Decimal;
+        }
+
+        let radix = if self.entry == Entry::Hex { 16 } else { 10 };
+
+        self.digits = self.digits.saturating_add(1);
+
+        if self.entry == Entry::Codepage {
+            self.value = (self.value * radix + digit) % 256;
+            return;
+        }
+
+        self.value = self.value.saturating_mul(radix).saturating_add(digit);
+
+        if self.value > MAX_VALUE {
+            self.value = MAX_VALUE + 1;
+        }
+    }
+
+    // The character once Alt is released, None when nothing valid was entered
+    pub(crate) fn finish(&self) -> Option<char> {
+        if self.digits == 0 || self.value == 0 {
+            return None;
+        }
+
+        return match self.entry {
+            Entry::Codepage => Some(cp437_char(self.value as u8)),
+            Entry::Decimal | Entry::Hex => core::char::from_u32(self.value),
+        };
+    }
+}
+
+// The digit a keypad key enters, letters A-F only count in hex entry
+pub(crate) fn keypad_digit(scan_type: ScanType) -> Option<u32> {
+    return match scan_type {
+        ScanType::Num0 => Some(0),
+        ScanType::Num1 => Some(1),
+        ScanType::Num2 => Some(2),
+        ScanType::Num3 => Some(3),
+        ScanType::Num4 => Some(4),
+        ScanType::Num5 => Some(5),
+        ScanType::Num6 => Some(6),
+        ScanType::Num7 => Some(7),
+        ScanType::Num8 => Some(8),
+        ScanType::Num9 => Some(9),
+        _ => None,
+    };
+}
+
+pub(crate) fn hex_letter_digit(scan_type: ScanType) -> Option<u32> {
+    return match scan_type {
+        ScanType::CharA => Some(0xa),
+        ScanType::CharB => Some(0xb),
+        ScanType::CharC => Some(0xc),
+        ScanType::CharD => Some(0xd),
+        ScanType::CharE => Some(0xe),
+        ScanType::CharF => Some(0xf),
+        _ => None,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enter(digits: &[u32]) -> Option<char> {
+        let mut entry = AltNumpad::new();
+
+        for digit in digits {
+            entry.push_digit(*digit);
+        }
+
+        return entry.finish();
+    }
+
+    #[test]
+    fn test_cp437() {
+        assert_eq!(CP437_LOW.chars().count(), 32);
+        assert_eq!(CP437_HIGH.chars().count(), 128);
+        assert_eq!(cp437_char(1), '☺');
+        assert_eq!(cp437_char(b'A'), 'A');
+        assert_eq!(cp437_char(130), 'é');
+        assert_eq!(cp437_char(219), '█');
+        assert_eq!(cp437_char(255), '\u{a0}');
+    }
+
+    #[test]
+    fn test_entry() {
+        assert_eq!(enter(&[6, 5]), Some('A'));
+        assert_eq!(enter(&[1, 3, 0]), Some('é'));
+        // Code page numbers wrap like they do on DOS
+        assert_eq!(enter(&[3, 2, 1]), Some('A'));
+        assert_eq!(enter(&[9, 9, 9, 9, 9, 9, 9]), Some('⌂'));
+        assert_eq!(enter(&[0, 2, 3, 3]), Some('é'));
+        assert_eq!(enter(&[0, 8, 3, 6, 4]), Some('€'));
+        assert_eq!(enter(&[0]), None);
+        assert_eq!(enter(&[]), None);
+        assert_eq!(enter(&[0, 5, 5, 2, 9, 6]), None);
+        assert_eq!(enter(&[0, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9]), None);
+
+        let mut entry = AltNumpad::new();
+        assert!(entry.start_hex());
+        entry.push_digit(2);
+        entry.push_digit(0);
+        entry.push_digit(0xa);
+        entry.push_digit(0xc);
+        assert!(!entry.start_hex());
+        assert_eq!(entry.finish(), Some('€'));
+    }
+}
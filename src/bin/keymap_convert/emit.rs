@@ -0,0 +1,167 @@
+use super::Conversion;
+use voxeon_os_ps2_scancodes::{
+    keymap_len, write_keymap, Key, KeyState, KeySymbol, KeymapEntry, ScanType, USStandardLayout,
+};
+
+pub fn to_table(conversion: &Conversion) -> Result<Vec<u8>, String> {
+    let entries = conversion.entries()?;
+    let mut bytes = vec![0u8; keymap_len(entries.len())];
+
+    write_keymap(&entries, conversion.has_alt_gr(), &mut bytes)?;
+
+    return Ok(bytes);
+}
+
+fn symbol_source(symbol: KeySymbol) -> String {
+    return match symbol {
+        KeySymbol::Empty => "_".to_string(),
+        KeySymbol::Char(ch) => format!("{:?}", ch),
+        KeySymbol::Dead(dead_key) => format!("{:?}", dead_key),
+    };
+}
+
+// The US rows of the keys the conversion leaves out, which is what a keymap table falls back to.
+// Space and Tab type the same with AltGr held.
+fn us_rows(entries: &[KeymapEntry]) -> Vec<KeymapEntry> {
+    let mut rows = Vec::new();
+
+    for scan_type in ScanType::ALL.iter() {
+        if entries.iter().any(|e| e.scan_type == *scan_type) {
+            continue;
+        }
+
+        let mut row = match USStandardLayout::TABLE.entry(Key::new(*scan_type, KeyState::Pressed)) {
+            Some(row) => *row,
+            None => continue,
+        };
+
+        if matches!(scan_type, ScanType::Space | ScanType::Tab) {
+            row.levels[2] = row.levels[0];
+            row.levels[3] = row.levels[1];
+        }
+
+        rows.push(row);
+    }
+
+    return rows;
+}
+
+// A layout! invocation that builds the converted layout into a StaticLayout, with the keypad of
+// the built in layouts
+pub fn to_rust(conversion: &Conversion, name: &str, source_name: &str) -> Result<String, String> {
+    let mut entries = conversion.entries()?;
+    let mut out = String::new();
+
+    entries.extend(us_rows(&entries));
+    entries.sort_by_key(|e| e.scan_type.as_u8());
+
+    out.push_str(&format!(
+        "// Converted from {}\nvoxeon_os_ps2_scancodes::layout! {{\n    pub struct {}Layout;\n    alt_gr: {};\n    keys {{\n",
+        source_name,
+        name,
+        conversion.has_alt_gr()
+    ));
+
+    for entry in entries.iter() {
+        let levels: Vec<String> = entry.levels.iter().map(|l| symbol_source(*l)).collect();
+
+        out.push_str(&format!(
+            "        {:?} => ({}, {:?}),\n",
+            entry.scan_type,
+            levels.join(", "),
+            entry.caps
+        ));
+    }
+
+    out.push_str("    }\n    keypad: voxeon_os_ps2_scancodes::KEYPAD_ROWS;\n}\n");
+
+    return Ok(out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::kbd;
+    use super::*;
+    use voxeon_os_ps2_scancodes::{KeyModifierState, Layout, TableLayout, KEYPAD_ROWS};
+
+    fn no_include(name: &str) -> Result<String, String> {
+        return Err(format!("{} not found", name));
+    }
+
+    const SOURCE: &str = "\
+keycode 13 = dead_acute dead_grave
+keycode 21 = +z +Z
+altgr keycode 18 = EuroSign
+";
+
+    #[test]
+    fn test_table() {
+        let conversion = kbd::convert(SOURCE, &mut no_include).unwrap();
+        let bytes = to_table(&conversion).unwrap();
+        let layout = TableLayout::parse(&bytes).unwrap();
+
+        assert!(layout.has_alt_gr());
+        assert_eq!(
+            layout.entries().collect::<Vec<_>>(),
+            conversion.entries().unwrap()
+        );
+    }
+
+    // The output of to_rust for SOURCE, regenerate it when the output changes
+    include!("testdata/test_layout.rs");
+
+    #[test]
+    fn test_rust() {
+        let conversion = kbd::convert(SOURCE, &mut no_include).unwrap();
+
+        assert_eq!(
+            to_rust(&conversion, "Test", "test.map").unwrap(),
+            include_str!("testdata/test_layout.rs")
+        );
+    }
+
+    // The generated layout types the same as the table, apart from Shift on the keypad where the
+    // table falls back to the US main keys
+    #[test]
+    fn test_rust_matches_table() {
+        let conversion = kbd::convert(SOURCE, &mut no_include).unwrap();
+        let bytes = to_table(&conversion).unwrap();
+        let table = TableLayout::parse(&bytes).unwrap();
+
+        assert_eq!(TestLayout.has_alt_gr(), table.has_alt_gr());
+
+        for scan_type in ScanType::ALL.iter() {
+            let keypad = KEYPAD_ROWS.iter().any(|row| row.scan_type == *scan_type);
+
+            for bits in 0..8 {
+                let mut modifiers = KeyModifierState::new();
+                modifiers.left_shift = bits & 1 != 0;
+                modifiers.alt_gr = bits & 2 != 0;
+                modifiers.caps_lock = bits & 4 != 0;
+
+                let mut keys = vec![Key::new(*scan_type, KeyState::Pressed)];
+
+                if keypad && !modifiers.left_shift {
+                    keys.push(Key::new_keypad(*scan_type, KeyState::Pressed));
+                }
+
+                for key in keys {
+                    assert_eq!(
+                        TestLayout.key_into_char(&modifiers, key),
+                        table.key_into_char(&modifiers, key),
+                        "{:?} {:?}",
+                        key,
+                        modifiers
+                    );
+                    assert_eq!(
+                        TestLayout.dead_key(&modifiers, key),
+                        table.dead_key(&modifiers, key),
+                        "{:?} {:?}",
+                        key,
+                        modifiers
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,528 @@
+use super::keysym::{is_void, parse_keysym};
+use super::{Conversion, Include, MAX_INCLUDE_DEPTH};
+use std::collections::BTreeSet;
+use voxeon_os_ps2_scancodes::{CapsBehaviour, KeySymbol, ScanType};
+
+// Linux keycodes of the keys a layout table covers. Up to 88 they're the set 1 make codes.
+const KEYCODES: [(u32, ScanType); 51] = {
+    use ScanType::*;
+
+    [
+        (2, Num1),
+        (3, Num2),
+        (4, Num3),
+        (5, Num4),
+        (6, Num5),
+        (7, Num6),
+        (8, Num7),
+        (9, Num8),
+        (10, Num9),
+        (11, Num0),
+        (12, SymbolMinus),
+        (13, SymbolEquals),
+        (16, CharQ),
+        (17, CharW),
+        (18, CharE),
+        (19, CharR),
+        (20, CharT),
+        (21, CharY),
+        (22, CharU),
+        (23, CharI),
+        (24, CharO),
+        (25, CharP),
+        (26, SymbolOpenSquareBracket),
+        (27, SymbolCloseSquareBracket),
+        (30, CharA),
+        (31, CharS),
+        (32, CharD),
+        (33, CharF),
+        (34, CharG),
+        (35, CharH),
+        (36, CharJ),
+        (37, CharK),
+        (38, CharL),
+        (39, SymbolSemicolon),
+        (40, SymbolSingleQuote),
+        (41, SymbolBacktick),
+        (43, SymbolBackslash),
+        (44, CharZ),
+        (45, CharX),
+        (46, CharC),
+        (47, CharV),
+        (48, CharB),
+        (49, CharN),
+        (50, CharM),
+        (51, SymbolComma),
+        (52, SymbolPeriod),
+        (53, SymbolForwardSlash),
+        (57, Space),
+        (86, Iso102nd),
+        (89, Ro),
+        (124, Yen),
+    ]
+};
+
+// The modifier weights of a kbd keymap number. Only the first four keymaps (plain, shift, altgr
+// and shift+altgr) are layout levels, the control and alt maps are generated by the keyboard.
+const MODIFIERS: [(&str, u32); 10] = [
+    ("plain", 0),
+    ("shift", 1),
+    ("altgr", 2),
+    ("control", 4),
+    ("alt", 8),
+    ("shiftl", 16),
+    ("shiftr", 32),
+    ("ctrll", 64),
+    ("ctrlr", 128),
+    ("capsshift", 256),
+];
+
+const LEVEL_COUNT: u32 = 4;
+
+fn scan_type(keycode: u32) -> Option<ScanType> {
+    return KEYCODES
+        .iter()
+        .find(|(k, _)| *k == keycode)
+        .map(|(_, s)| *s);
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    if let Some(hex) = s.strip_prefix("0x") {
+        return u32::from_str_radix(hex, 16).ok();
+    }
+
+    return s.parse().ok();
+}
+
+// "keymaps 0-2,4-6,8" lists which keymap each column of a keycode line fills
+fn parse_keymaps(spec: &str) -> Option<Vec<u32>> {
+    let mut keymaps = Vec::new();
+
+    for part in spec.split(',') {
+        match part.find('-') {
+            Some(i) => {
+                let first = parse_number(&part[..i])?;
+                let last = parse_number(&part[i + 1..])?;
+                keymaps.extend(first..=last);
+            }
+            None => keymaps.push(parse_number(part)?),
+        }
+    }
+
+    return Some(keymaps);
+}
+
+// Drops a trailing # or ! comment that isn't inside a quoted string
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' | '!' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+
+    return line;
+}
+
+struct Parser<'a, 'b> {
+    conversion: Conversion,
+    keymaps: Option<Vec<u32>>,
+    no_scan_type: BTreeSet<u32>,
+    compose_ignored: bool,
+    include: &'a mut Include<'b>,
+    depth: usize,
+}
+
+impl<'a, 'b> Parser<'a, 'b> {
+    fn set(&mut self, line: usize, keycode: u32, keymap: u32, token: &str) {
+        if keymap >= LEVEL_COUNT {
+            return;
+        }
+
+        let (letter, name) = match token.strip_prefix('+') {
+            Some(name) => (true, name),
+            None => (false, token),
+        };
+
+        let symbol = match parse_keysym(name) {
+            Some(symbol) => symbol,
+            None => {
+                if scan_type(keycode).is_some() {
+                    self.conversion.report(format!(
+                        "line {}: keycode {} keymap {}: no character for {}",
+                        line, keycode, keymap, name
+                    ));
+                }
+
+                return;
+            }
+        };
+
+        let scan_type = match scan_type(keycode) {
+            Some(scan_type) => scan_type,
+            None => {
+                if !is_void(name) {
+                    self.no_scan_type.insert(keycode);
+                }
+
+                return;
+            }
+        };
+
+        self.conversion
+            .set_level(scan_type, keymap as usize, symbol);
+
+        // kbd only applies Caps Lock to letter keysyms, marked with + or plain ASCII letters
+        if keymap == 0 {
+            let ascii_letter = matches!(symbol, KeySymbol::Char(c) if c.is_ascii_alphabetic());
+            let caps = if letter || ascii_letter {
+                CapsBehaviour::Auto
+            } else {
+                CapsBehaviour::Ignore
+            };
+
+            self.conversion.set_caps(scan_type, caps);
+        }
+    }
+
+    fn keycode_line(&mut self, line: usize, words: &[&str]) -> Result<(), String> {
+        let keyword = words
+            .iter()
+            .position(|w| *w == "keycode")
+            .ok_or_else(|| format!("line {}: unknown statement {}", line, words[0]))?;
+
+        let mut keymap = 0;
+
+        for word in words[..keyword].iter() {
+            match MODIFIERS.iter().find(|(name, _)| name == word) {
+                Some((_, weight)) => keymap |= weight,
+                None => return Err(format!("line {}: unknown modifier {}", line, word)),
+            }
+        }
+
+        let keycode = words
+            .get(keyword + 1)
+            .and_then(|k| parse_number(k))
+            .ok_or_else(|| format!("line {}: expected a keycode number", line))?;
+
+        if words.get(keyword + 2) != Some(&"=") {
+            return Err(format!("line {}: expected = after the keycode", line));
+        }
+
+        let symbols = &words[keyword + 3..];
+
+        if keyword > 0 {
+            if symbols.len() != 1 {
+                return Err(format!("line {}: expected one keysym", line));
+            }
+
+            self.set(line, keycode, keymap, symbols[0]);
+
+            return Ok(());
+        }
+
+        // A lone keysym applies to every keymap, letters get their capital on the shifted ones
+        if symbols.len() == 1 {
+            let upper = symbols[0]
+                .strip_prefix('+')
+                .filter(|name| name.len() == 1)
+                .map(|name| format!("+{}", name.to_uppercase()));
+
+            for keymap in 0..LEVEL_COUNT {
+                match (&upper, keymap & 1) {
+                    (Some(upper), 1) => self.set(line, keycode, keymap, upper),
+                    _ => self.set(line, keycode, keymap, symbols[0]),
+                }
+            }
+
+            return Ok(());
+        }
+
+        for (column, token) in symbols.iter().enumerate() {
+            let keymap = match &self.keymaps {
+                Some(keymaps) => match keymaps.get(column) {
+                    Some(keymap) => *keymap,
+                    None => return Err(format!("line {}: more keysyms than keymaps", line)),
+                },
+                None => column as u32,
+            };
+
+            self.set(line, keycode, keymap, token);
+        }
+
+        return Ok(());
+    }
+
+    // Parses the included file in place, its notes and errors are prefixed with its name
+    fn include_file(&mut self, line: usize, name: &str) -> Result<(), String> {
+        if self.depth == MAX_INCLUDE_DEPTH {
+            return Err(format!("line {}: includes nested too deeply", line));
+        }
+
+        let source = (self.include)(name).map_err(|e| format!("line {}: {}", line, e))?;
+        let notes = self.conversion.unmapped.len();
+
+        self.depth += 1;
+        self.parse(&source)
+            .map_err(|e| format!("line {}: {}: {}", line, name, e))?;
+        self.depth -= 1;
+
+        for note in self.conversion.unmapped[notes..].iter_mut() {
+            *note = format!("{}: {}", name, note);
+        }
+
+        return Ok(());
+    }
+
+    fn parse(&mut self, source: &str) -> Result<(), String> {
+        let mut statement = String::new();
+        let mut start = 0;
+
+        for (i, line) in source.lines().enumerate() {
+            let line = strip_comment(line);
+
+            if statement.is_empty() {
+                start = i + 1;
+            }
+
+            // A trailing backslash continues the statement on the next line
+            match line.trim_end().strip_suffix('\\') {
+                Some(part) => {
+                    statement.push_str(part);
+                    statement.push(' ');
+                    continue;
+                }
+                None => statement.push_str(line),
+            }
+
+            if !statement.trim().is_empty() {
+                self.statement(start, statement.trim())?;
+            }
+
+            statement.clear();
+        }
+
+        return Ok(());
+    }
+
+    fn statement(&mut self, line: usize, text: &str) -> Result<(), String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+
+        match words[0] {
+            "keymaps" => {
+                let spec = words.get(1).copied().unwrap_or("");
+                self.keymaps = Some(
+                    parse_keymaps(spec)
+                        .ok_or_else(|| format!("line {}: bad keymaps {}", line, spec))?,
+                );
+            }
+            "include" => {
+                let name = words
+                    .get(1)
+                    .map(|w| w.trim_matches('"'))
+                    .ok_or_else(|| format!("line {}: expected a file to include", line))?;
+
+                self.include_file(line, name)?;
+            }
+            "compose" => {
+                if !self.compose_ignored {
+                    self.compose_ignored = true;
+                    self.conversion.report(format!(
+                        "line {}: compose definitions ignored, the built in table is used",
+                        line
+                    ));
+                }
+            }
+            "charset" | "alt_is_meta" | "strings" | "string" | "as" => {}
+            _ => self.keycode_line(line, &words)?,
+        }
+
+        return Ok(());
+    }
+}
+
+pub fn convert(source: &str, include: &mut Include) -> Result<Conversion, String> {
+    let mut parser = Parser {
+        conversion: Conversion::default(),
+        keymaps: None,
+        no_scan_type: BTreeSet::new(),
+        compose_ignored: false,
+        include,
+        depth: 0,
+    };
+
+    parser.parse(source)?;
+
+    for keycode in parser.no_scan_type.iter() {
+        parser
+            .conversion
+            .report(format!("keycode {} has no scan type", keycode));
+    }
+
+    return Ok(parser.conversion);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use voxeon_os_ps2_scancodes::DeadKey;
+    use KeySymbol::{Char, Dead, Empty};
+
+    fn no_include(name: &str) -> Result<String, String> {
+        return Err(format!("{} not found", name));
+    }
+
+    fn include(name: &str) -> Result<String, String> {
+        return match name {
+            "linux-with-alt-and-altgr" => {
+                Ok("include \"linux-keys-bare\"\nkeycode 51 = comma semicolon\n".to_string())
+            }
+            "linux-keys-bare" => Ok("keycode 52 = period colon Greek_koppa\n".to_string()),
+            "loop" => Ok("include \"loop\"\n".to_string()),
+            _ => no_include(name),
+        };
+    }
+
+    const GERMAN: &str = "\
+# German, trimmed down
+keymaps 0-2,4-6,8-9,12
+include \"linux-with-alt-and-altgr\"
+keycode   3 = two              quotedbl         twosuperior      nul
+keycode  12 = ssharp           question         backslash        Control_underscore
+keycode  13 = dead_acute       dead_grave
+keycode  16 = +q               +Q               at               Control_q
+keycode  21 = +z               +Z
+keycode  26 = +udiaeresis      +Udiaeresis
+keycode  39 = +odiaeresis      +Odiaeresis
+keycode  57 = space
+keycode  86 = less             greater          bar
+keycode  99 = Compose
+keycode 120 = adiaeresis
+\tshift\tkeycode  18 = +E
+\taltgr\tkeycode  18 = EuroSign
+keycode  41 = dead_circumflex  degree \\
+              notsign
+compose 'a' 'e' to 'æ'
+";
+
+    #[test]
+    fn test_convert() {
+        let conversion = convert(GERMAN, &mut include).unwrap();
+        let entries = conversion.entries().unwrap();
+        let find = |scan_type: ScanType| {
+            return entries.iter().find(|e| e.scan_type == scan_type).unwrap();
+        };
+
+        assert!(conversion.has_alt_gr());
+        assert_eq!(
+            find(ScanType::Num2).levels,
+            [Char('2'), Char('"'), Char('²'), Empty]
+        );
+        assert_eq!(find(ScanType::Num2).caps, CapsBehaviour::Ignore);
+        assert_eq!(
+            find(ScanType::SymbolEquals).levels,
+            [Dead(DeadKey::Acute), Dead(DeadKey::Grave), Empty, Empty]
+        );
+        assert_eq!(
+            find(ScanType::CharY).levels,
+            [Char('z'), Char('Z'), Empty, Empty]
+        );
+        assert_eq!(find(ScanType::CharY).caps, CapsBehaviour::Auto);
+        // E only has Shift and AltGr here, its base level comes from the fallback
+        assert_eq!(
+            find(ScanType::CharE).levels,
+            [Char('e'), Char('E'), Char('€'), Empty]
+        );
+        assert_eq!(
+            find(ScanType::Space).levels,
+            [Char(' '), Char(' '), Char(' '), Char(' ')]
+        );
+        assert_eq!(
+            find(ScanType::SymbolBacktick).levels,
+            [Dead(DeadKey::Circumflex), Char('°'), Char('¬'), Empty]
+        );
+        assert_eq!(
+            find(ScanType::Iso102nd).levels,
+            [Char('<'), Char('>'), Char('|'), Empty]
+        );
+        assert_eq!(
+            find(ScanType::SymbolComma).levels,
+            [Char(','), Char(';'), Empty, Empty]
+        );
+
+        assert_eq!(
+            conversion.unmapped,
+            [
+                "linux-with-alt-and-altgr: linux-keys-bare: line 1: keycode 52 keymap 2: no \
+                 character for Greek_koppa",
+                "line 19: compose definitions ignored, the built in table is used",
+                "keycode 120 has no scan type",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lone_letter() {
+        let conversion = convert("keycode 16 = +a\n", &mut no_include).unwrap();
+
+        assert_eq!(
+            conversion.entries().unwrap()[0].levels,
+            [Char('a'), Char('A'), Char('a'), Char('A')]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keysym() {
+        let conversion = convert("keycode 16 = q Q Greek_koppa\n", &mut no_include).unwrap();
+
+        assert_eq!(
+            conversion.unmapped,
+            ["line 1: keycode 16 keymap 2: no character for Greek_koppa"]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            convert("\nkeycode 16 q\n", &mut no_include).err().unwrap(),
+            "line 2: expected = after the keycode"
+        );
+        assert_eq!(
+            convert("keycode x = q\n", &mut no_include).err().unwrap(),
+            "line 1: expected a keycode number"
+        );
+        assert_eq!(
+            convert("super keycode 16 = q\n", &mut no_include)
+                .err()
+                .unwrap(),
+            "line 1: unknown modifier super"
+        );
+        assert_eq!(
+            convert("keymaps 0-1\nkeycode 16 = q Q at\n", &mut no_include)
+                .err()
+                .unwrap(),
+            "line 2: more keysyms than keymaps"
+        );
+        assert_eq!(
+            convert("\ninclude \"qwerty\"\n", &mut no_include)
+                .err()
+                .unwrap(),
+            "line 2: qwerty not found"
+        );
+        assert!(convert("include \"loop\"\n", &mut include)
+            .err()
+            .unwrap()
+            .ends_with("line 1: includes nested too deeply"));
+        assert_eq!(
+            convert("altgr keycode 86 = bar\n", &mut no_include)
+                .unwrap()
+                .entries()
+                .err()
+                .unwrap(),
+            "ISO 102nd has no base level and no fallback for it"
+        );
+    }
+}
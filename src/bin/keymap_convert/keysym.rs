@@ -0,0 +1,460 @@
+use voxeon_os_ps2_scancodes::{DeadKey, KeySymbol};
+
+// Named ASCII keysyms. Digits and letters are spelled as themselves in XKB, kbd spells the
+// digits out.
+const ASCII_NAMES: [(&str, char); 45] = [
+    ("space", ' '),
+    ("exclam", '!'),
+    ("quotedbl", '"'),
+    ("numbersign", '#'),
+    ("dollar", '$'),
+    ("percent", '%'),
+    ("ampersand", '&'),
+    ("apostrophe", '\''),
+    ("quoteright", '\''),
+    ("parenleft", '('),
+    ("parenright", ')'),
+    ("asterisk", '*'),
+    ("plus", '+'),
+    ("comma", ','),
+    ("minus", '-'),
+    ("period", '.'),
+    ("slash", '/'),
+    ("zero", '0'),
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+    ("colon", ':'),
+    ("semicolon", ';'),
+    ("less", '<'),
+    ("equal", '='),
+    ("greater", '>'),
+    ("question", '?'),
+    ("at", '@'),
+    ("bracketleft", '['),
+    ("backslash", '\\'),
+    ("bracketright", ']'),
+    ("asciicircum", '^'),
+    ("underscore", '_'),
+    ("grave", '`'),
+    ("quoteleft", '`'),
+    ("braceleft", '{'),
+    ("bar", '|'),
+    ("braceright", '}'),
+    ("asciitilde", '~'),
+];
+
+// The Latin-1 keysyms 0xa0 to 0xff in order
+const LATIN1_NAMES: [&str; 96] = [
+    "nobreakspace",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "currency",
+    "yen",
+    "brokenbar",
+    "section",
+    "diaeresis",
+    "copyright",
+    "ordfeminine",
+    "guillemotleft",
+    "notsign",
+    "hyphen",
+    "registered",
+    "macron",
+    "degree",
+    "plusminus",
+    "twosuperior",
+    "threesuperior",
+    "acute",
+    "mu",
+    "paragraph",
+    "periodcentered",
+    "cedilla",
+    "onesuperior",
+    "masculine",
+    "guillemotright",
+    "onequarter",
+    "onehalf",
+    "threequarters",
+    "questiondown",
+    "Agrave",
+    "Aacute",
+    "Acircumflex",
+    "Atilde",
+    "Adiaeresis",
+    "Aring",
+    "AE",
+    "Ccedilla",
+    "Egrave",
+    "Eacute",
+    "Ecircumflex",
+    "Ediaeresis",
+    "Igrave",
+    "Iacute",
+    "Icircumflex",
+    "Idiaeresis",
+    "ETH",
+    "Ntilde",
+    "Ograve",
+    "Oacute",
+    "Ocircumflex",
+    "Otilde",
+    "Odiaeresis",
+    "multiply",
+    "Oslash",
+    "Ugrave",
+    "Uacute",
+    "Ucircumflex",
+    "Udiaeresis",
+    "Yacute",
+    "THORN",
+    "ssharp",
+    "agrave",
+    "aacute",
+    "acircumflex",
+    "atilde",
+    "adiaeresis",
+    "aring",
+    "ae",
+    "ccedilla",
+    "egrave",
+    "eacute",
+    "ecircumflex",
+    "ediaeresis",
+    "igrave",
+    "iacute",
+    "icircumflex",
+    "idiaeresis",
+    "eth",
+    "ntilde",
+    "ograve",
+    "oacute",
+    "ocircumflex",
+    "otilde",
+    "odiaeresis",
+    "division",
+    "oslash",
+    "ugrave",
+    "uacute",
+    "ucircumflex",
+    "udiaeresis",
+    "yacute",
+    "thorn",
+    "ydiaeresis",
+];
+
+// Other names seen in European keymaps, including the older spellings of Latin-1 names
+const OTHER_NAMES: [(&str, char); 83] = [
+    ("guillemetleft", '«'),
+    ("guillemetright", '»'),
+    ("ordmasculine", 'º'),
+    ("Eth", 'Ð'),
+    ("Thorn", 'Þ'),
+    ("Ooblique", 'Ø'),
+    ("ooblique", 'ø'),
+    ("EuroSign", '€'),
+    ("euro", '€'),
+    ("Aogonek", 'Ą'),
+    ("aogonek", 'ą'),
+    ("Abreve", 'Ă'),
+    ("abreve", 'ă'),
+    ("Cacute", 'Ć'),
+    ("cacute", 'ć'),
+    ("Ccaron", 'Č'),
+    ("ccaron", 'č'),
+    ("Dcaron", 'Ď'),
+    ("dcaron", 'ď'),
+    ("Dstroke", 'Đ'),
+    ("dstroke", 'đ'),
+    ("Eogonek", 'Ę'),
+    ("eogonek", 'ę'),
+    ("Ecaron", 'Ě'),
+    ("ecaron", 'ě'),
+    ("Gbreve", 'Ğ'),
+    ("gbreve", 'ğ'),
+    ("Iabovedot", 'İ'),
+    ("idotless", 'ı'),
+    ("Lacute", 'Ĺ'),
+    ("lacute", 'ĺ'),
+    ("Lcaron", 'Ľ'),
+    ("lcaron", 'ľ'),
+    ("Lstroke", 'Ł'),
+    ("lstroke", 'ł'),
+    ("Nacute", 'Ń'),
+    ("nacute", 'ń'),
+    ("Ncaron", 'Ň'),
+    ("ncaron", 'ň'),
+    ("Odoubleacute", 'Ő'),
+    ("odoubleacute", 'ő'),
+    ("OE", 'Œ'),
+    ("oe", 'œ'),
+    ("Racute", 'Ŕ'),
+    ("racute", 'ŕ'),
+    ("Rcaron", 'Ř'),
+    ("rcaron", 'ř'),
+    ("Sacute", 'Ś'),
+    ("sacute", 'ś'),
+    ("Scaron", 'Š'),
+    ("scaron", 'š'),
+    ("Scedilla", 'Ş'),
+    ("scedilla", 'ş'),
+    ("Tcaron", 'Ť'),
+    ("tcaron", 'ť'),
+    ("Tcedilla", 'Ţ'),
+    ("tcedilla", 'ţ'),
+    ("Udoubleacute", 'Ű'),
+    ("udoubleacute", 'ű'),
+    ("Uring", 'Ů'),
+    ("uring", 'ů'),
+    ("Ydiaeresis", 'Ÿ'),
+    ("Zacute", 'Ź'),
+    ("zacute", 'ź'),
+    ("Zabovedot", 'Ż'),
+    ("zabovedot", 'ż'),
+    ("Zcaron", 'Ž'),
+    ("zcaron", 'ž'),
+    ("breve", '˘'),
+    ("caron", 'ˇ'),
+    ("ogonek", '˛'),
+    ("doubleacute", '˝'),
+    ("abovedot", '˙'),
+    ("endash", '–'),
+    ("emdash", '—'),
+    ("ellipsis", '…'),
+    ("leftsinglequotemark", '‘'),
+    ("rightsinglequotemark", '’'),
+    ("singlelowquotemark", '‚'),
+    ("leftdoublequotemark", '“'),
+    ("rightdoublequotemark", '”'),
+    ("doublelowquotemark", '„'),
+    ("numerosign", '№'),
+];
+
+// Lowercase Cyrillic and Greek keysyms. The uppercase keysym is the same name with the part after
+// the script prefix in capitals.
+const CYRILLIC_NAMES: [(&str, char); 38] = [
+    ("Cyrillic_a", 'а'),
+    ("Cyrillic_be", 'б'),
+    ("Cyrillic_ve", 'в'),
+    ("Cyrillic_ghe", 'г'),
+    ("Cyrillic_de", 'д'),
+    ("Cyrillic_ie", 'е'),
+    ("Cyrillic_io", 'ё'),
+    ("Cyrillic_zhe", 'ж'),
+    ("Cyrillic_ze", 'з'),
+    ("Cyrillic_i", 'и'),
+    ("Cyrillic_shorti", 'й'),
+    ("Cyrillic_ka", 'к'),
+    ("Cyrillic_el", 'л'),
+    ("Cyrillic_em", 'м'),
+    ("Cyrillic_en", 'н'),
+    ("Cyrillic_o", 'о'),
+    ("Cyrillic_pe", 'п'),
+    ("Cyrillic_er", 'р'),
+    ("Cyrillic_es", 'с'),
+    ("Cyrillic_te", 'т'),
+    ("Cyrillic_u", 'у'),
+    ("Cyrillic_ef", 'ф'),
+    ("Cyrillic_ha", 'х'),
+    ("Cyrillic_tse", 'ц'),
+    ("Cyrillic_che", 'ч'),
+    ("Cyrillic_sha", 'ш'),
+    ("Cyrillic_shcha", 'щ'),
+    ("Cyrillic_hardsign", 'ъ'),
+    ("Cyrillic_yeru", 'ы'),
+    ("Cyrillic_softsign", 'ь'),
+    ("Cyrillic_e", 'э'),
+    ("Cyrillic_yu", 'ю'),
+    ("Cyrillic_ya", 'я'),
+    ("Ukrainian_ie", 'є'),
+    ("Ukrainian_i", 'і'),
+    ("Ukrainian_yi", 'ї'),
+    ("Ukrainian_ghe_with_upturn", 'ґ'),
+    ("Byelorussian_shortu", 'ў'),
+];
+
+const GREEK_NAMES: [(&str, char); 35] = [
+    ("Greek_alpha", 'α'),
+    ("Greek_beta", 'β'),
+    ("Greek_gamma", 'γ'),
+    ("Greek_delta", 'δ'),
+    ("Greek_epsilon", 'ε'),
+    ("Greek_zeta", 'ζ'),
+    ("Greek_eta", 'η'),
+    ("Greek_theta", 'θ'),
+    ("Greek_iota", 'ι'),
+    ("Greek_kappa", 'κ'),
+    ("Greek_lamda", 'λ'),
+    ("Greek_lambda", 'λ'),
+    ("Greek_mu", 'μ'),
+    ("Greek_nu", 'ν'),
+    ("Greek_xi", 'ξ'),
+    ("Greek_omicron", 'ο'),
+    ("Greek_pi", 'π'),
+    ("Greek_rho", 'ρ'),
+    ("Greek_sigma", 'σ'),
+    ("Greek_finalsmallsigma", 'ς'),
+    ("Greek_tau", 'τ'),
+    ("Greek_upsilon", 'υ'),
+    ("Greek_phi", 'φ'),
+    ("Greek_chi", 'χ'),
+    ("Greek_psi", 'ψ'),
+    ("Greek_omega", 'ω'),
+    ("Greek_alphaaccent", 'ά'),
+    ("Greek_epsilonaccent", 'έ'),
+    ("Greek_etaaccent", 'ή'),
+    ("Greek_iotaaccent", 'ί'),
+    ("Greek_omicronaccent", 'ό'),
+    ("Greek_upsilonaccent", 'ύ'),
+    ("Greek_omegaaccent", 'ώ'),
+    ("Greek_iotadieresis", 'ϊ'),
+    ("Greek_upsilondieresis", 'ϋ'),
+];
+
+const DEAD_NAMES: [(&str, DeadKey); 14] = [
+    ("dead_grave", DeadKey::Grave),
+    ("dead_acute", DeadKey::Acute),
+    ("dead_circumflex", DeadKey::Circumflex),
+    ("dead_tilde", DeadKey::Tilde),
+    ("dead_perispomeni", DeadKey::Tilde),
+    ("dead_diaeresis", DeadKey::Diaeresis),
+    ("dead_abovering", DeadKey::Ring),
+    ("dead_cedilla", DeadKey::Cedilla),
+    ("dead_caron", DeadKey::Caron),
+    ("dead_macron", DeadKey::Macron),
+    ("dead_breve", DeadKey::Breve),
+    ("dead_doubleacute", DeadKey::DoubleAcute),
+    ("dead_ogonek", DeadKey::Ogonek),
+    ("dead_abovedot", DeadKey::DotAbove),
+];
+
+fn lookup<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    return table.iter().find(|(n, _)| *n == name).map(|(_, v)| *v);
+}
+
+// Cyrillic_SHORTI is the capital of Cyrillic_shorti
+fn lookup_script(table: &[(&str, char)], name: &str) -> Option<char> {
+    if let Some(ch) = lookup(table, name) {
+        return Some(ch);
+    }
+
+    let split = name.find('_')? + 1;
+    let (prefix, rest) = name.split_at(split);
+
+    if !rest.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+        return None;
+    }
+
+    let lower = format!("{}{}", prefix, rest.to_ascii_lowercase());
+    let ch = lookup(table, &lower)?;
+    let mut upper = ch.to_uppercase();
+
+    return match (upper.next(), upper.next()) {
+        (Some(upper), None) if upper != ch => Some(upper),
+        _ => None,
+    };
+}
+
+// Numeric spellings: U+20AC (kbd), U20AC (XKB) and raw keysym values. X11 puts Unicode keysyms
+// at 0x1000000 + code point, kbd puts Latin-1 and letters at 0x00nn and 0x0bnn.
+fn parse_numeric(name: &str) -> Option<char> {
+    if let Some(hex) = name.strip_prefix("U+").or_else(|| name.strip_prefix('U')) {
+        if hex.len() >= 4 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+        }
+
+        return None;
+    }
+
+    let hex = name.strip_prefix("0x")?;
+    let value = u32::from_str_radix(hex, 16).ok()?;
+
+    return match value {
+        0x20..=0xff => char::from_u32(value),
+        0x0b00..=0x0bff => char::from_u32(value & 0xff),
+        0x0100_0000..=0x0110_ffff => char::from_u32(value - 0x0100_0000),
+        _ => None,
+    };
+}
+
+// Whether the keysym explicitly leaves the level empty
+pub fn is_void(name: &str) -> bool {
+    return name == "VoidSymbol" || name == "NoSymbol";
+}
+
+// The symbol a keysym name stands for, None for names that don't produce a character
+pub fn parse_keysym(name: &str) -> Option<KeySymbol> {
+    if is_void(name) {
+        return Some(KeySymbol::Empty);
+    }
+
+    if let Some(dead_key) = lookup(&DEAD_NAMES, name) {
+        return Some(KeySymbol::Dead(dead_key));
+    }
+
+    let mut chars = name.chars();
+
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Some(KeySymbol::Char(ch));
+    }
+
+    let ch = lookup(&ASCII_NAMES, name)
+        .or_else(|| {
+            LATIN1_NAMES
+                .iter()
+                .position(|n| *n == name)
+                .and_then(|i| char::from_u32(0xa0 + i as u32))
+        })
+        .or_else(|| lookup(&OTHER_NAMES, name))
+        .or_else(|| lookup_script(&CYRILLIC_NAMES, name))
+        .or_else(|| lookup_script(&GREEK_NAMES, name))
+        .or_else(|| parse_numeric(name))?;
+
+    return Some(KeySymbol::Char(ch));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(name: &str, ch: char) {
+        assert_eq!(parse_keysym(name), Some(KeySymbol::Char(ch)), "{}", name);
+    }
+
+    #[test]
+    fn test_names() {
+        check("q", 'q');
+        check("7", '7');
+        check("seven", '7');
+        check("bracketleft", '[');
+        check("nobreakspace", '\u{a0}');
+        check("adiaeresis", 'ä');
+        check("ydiaeresis", 'ÿ');
+        check("EuroSign", '€');
+        check("Cyrillic_shorti", 'й');
+        check("Cyrillic_SHORTI", 'Й');
+        check("Ukrainian_GHE_WITH_UPTURN", 'Ґ');
+        check("Greek_OMEGA", 'Ω');
+        check("U+20AC", '€');
+        check("U20AC", '€');
+        check("0x1000451", 'ё');
+        check("0x00e4", 'ä');
+        check("0x0be4", 'ä');
+
+        assert_eq!(
+            parse_keysym("dead_abovering"),
+            Some(KeySymbol::Dead(DeadKey::Ring))
+        );
+        assert_eq!(parse_keysym("VoidSymbol"), Some(KeySymbol::Empty));
+        assert_eq!(parse_keysym("Greek_finalsmallSIGMA"), None);
+        assert_eq!(parse_keysym("Control_a"), None);
+        assert_eq!(parse_keysym("Up"), None);
+    }
+}
@@ -0,0 +1,265 @@
+// Converts Linux console (kbd) keymaps and XKB symbols files into the crate's keymap table
+// format, or into a layout! invocation that builds them into a StaticLayout.
+//
+//   keymap-convert [--kbd | --xkb] [--variant NAME] [--rust NAME] [-o FILE] INPUT
+//
+// Includes are looked up next to the input file, and for kbd keymaps in the include directories
+// beside it. Keys that couldn't be placed on a scan type, keysyms without a character and
+// anything the converter doesn't follow (compose definitions) are listed on stderr.
+
+mod emit;
+mod kbd;
+mod keysym;
+mod xkb;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use voxeon_os_ps2_scancodes::{
+    CapsBehaviour, Key, KeyModifierState, KeyState, KeySymbol, KeymapEntry, Layout, ScanType,
+    USStandardLayout,
+};
+
+// Reads the file an include names, the converters handle what's inside
+pub type Include<'a> = dyn FnMut(&str) -> Result<String, String> + 'a;
+
+// Includes can't nest deeper than this, which also stops include loops
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+
+#[derive(Default)]
+// What a converter produced: the keys it placed and notes on what it left out
+pub struct Conversion {
+    entries: BTreeMap<ScanType, KeymapEntry>,
+    pub unmapped: Vec<String>,
+}
+
+impl Conversion {
+    pub fn set_level(&mut self, scan_type: ScanType, level: usize, symbol: KeySymbol) {
+        let entry = self.entries.entry(scan_type).or_insert_with(|| {
+            KeymapEntry::new(scan_type, CapsBehaviour::Auto, [KeySymbol::Empty; 4])
+        });
+
+        entry.levels[level] = symbol;
+    }
+
+    pub fn set_caps(&mut self, scan_type: ScanType, caps: CapsBehaviour) {
+        if let Some(entry) = self.entries.get_mut(&scan_type) {
+            entry.caps = caps;
+        }
+    }
+
+    pub fn report(&mut self, note: String) {
+        self.unmapped.push(note);
+    }
+
+    // Keys left completely empty are dropped so they keep falling back to the US layout. A key
+    // without a base level takes its base and Shift levels from that fallback instead, or it
+    // would type nothing at all.
+    pub fn entries(&self) -> Result<Vec<KeymapEntry>, String> {
+        let mut entries = Vec::new();
+
+        for entry in self.entries.values() {
+            let mut entry = *entry;
+
+            if entry.levels.iter().all(|l| *l == KeySymbol::Empty) {
+                continue;
+            }
+
+            if entry.levels[0] == KeySymbol::Empty {
+                for (level, shifted) in [(0, false), (1, true)].iter() {
+                    if entry.levels[*level] == KeySymbol::Empty {
+                        entry.levels[*level] = fallback_symbol(entry.scan_type, *shifted);
+                    }
+                }
+            }
+
+            if entry.levels[0] == KeySymbol::Empty {
+                return Err(format!(
+                    "{} has no base level and no fallback for it",
+                    entry.scan_type
+                ));
+            }
+
+            entries.push(entry);
+        }
+
+        return Ok(entries);
+    }
+
+    pub fn has_alt_gr(&self) -> bool {
+        return self
+            .entries
+            .values()
+            .any(|entry| entry.levels[2..].iter().any(|l| *l != KeySymbol::Empty));
+    }
+}
+
+// What a key without a row types on the built in table layouts
+fn fallback_symbol(scan_type: ScanType, shifted: bool) -> KeySymbol {
+    let mut modifiers = KeyModifierState::new();
+    modifiers.left_shift = shifted;
+
+    return match USStandardLayout.key_into_char(&modifiers, Key::new(scan_type, KeyState::Pressed))
+    {
+        Some(ch) => KeySymbol::Char(ch),
+        None => KeySymbol::Empty,
+    };
+}
+
+// Reads an include from the first of the candidate paths that exists
+fn read_include(name: &str, candidates: &[PathBuf]) -> Result<String, String> {
+    for path in candidates.iter() {
+        if path.is_file() {
+            return fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e));
+        }
+    }
+
+    return Err(format!("{} not found", name));
+}
+
+// kbd looks for includes next to the keymap and in the include directories of the keymap tree,
+// with or without the .inc and .map extensions
+fn kbd_include(dir: &Path, name: &str) -> Result<String, String> {
+    let mut candidates = Vec::new();
+
+    for base in [
+        dir.to_path_buf(),
+        dir.join("../include"),
+        dir.join("../../include"),
+    ]
+    .iter()
+    {
+        for extension in ["", ".inc", ".map"].iter() {
+            candidates.push(base.join(format!("{}{}", name, extension)));
+        }
+    }
+
+    return read_include(name, &candidates);
+}
+
+// XKB includes name another file in the same symbols directory
+fn xkb_include(dir: &Path, name: &str) -> Result<String, String> {
+    return read_include(name, &[dir.join(name)]);
+}
+
+enum Format {
+    Kbd,
+    Xkb,
+}
+
+struct Options {
+    format: Option<Format>,
+    variant: Option<String>,
+    rust_name: Option<String>,
+    output: Option<String>,
+    input: String,
+}
+
+const USAGE: &str =
+    "usage: keymap-convert [--kbd | --xkb] [--variant NAME] [--rust NAME] [-o FILE] INPUT";
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut format = None;
+    let mut variant = None;
+    let mut rust_name = None;
+    let mut output = None;
+    let mut input = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--kbd" => format = Some(Format::Kbd),
+            "--xkb" => format = Some(Format::Xkb),
+            "--variant" => variant = Some(args.next().ok_or("--variant needs a name")?),
+            "--rust" => rust_name = Some(args.next().ok_or("--rust needs a name")?),
+            "-o" => output = Some(args.next().ok_or("-o needs a file")?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    return Ok(Options {
+        format,
+        variant,
+        rust_name,
+        output,
+        input: input.ok_or(USAGE)?,
+    });
+}
+
+fn run() -> Result<(), String> {
+    let Options {
+        format,
+        variant,
+        rust_name,
+        output,
+        input,
+    } = parse_args()?;
+    let source = fs::read_to_string(&input).map_err(|e| format!("{}: {}", input, e))?;
+
+    // kbd keymaps are conventionally named *.map, XKB symbols files have no extension
+    let format = format.unwrap_or_else(|| {
+        if input.ends_with(".map") || input.ends_with(".kmap") {
+            Format::Kbd
+        } else {
+            Format::Xkb
+        }
+    });
+
+    let dir = Path::new(&input)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let conversion = match format {
+        Format::Kbd => kbd::convert(&source, &mut |name| kbd_include(&dir, name)),
+        Format::Xkb => xkb::convert(&source, variant.as_deref(), &mut |name| {
+            xkb_include(&dir, name)
+        }),
+    }
+    .map_err(|e| format!("{}: {}", input, e))?;
+    let entries = conversion
+        .entries()
+        .map_err(|e| format!("{}: {}", input, e))?;
+
+    for note in conversion.unmapped.iter() {
+        eprintln!("{}: {}", input, note);
+    }
+
+    eprintln!(
+        "{}: {} keys converted, {} notes",
+        input,
+        entries.len(),
+        conversion.unmapped.len()
+    );
+
+    let bytes = match rust_name {
+        Some(name) => {
+            let file_name = Path::new(&input)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            emit::to_rust(&conversion, &name, &file_name)?.into_bytes()
+        }
+        None => emit::to_table(&conversion)?,
+    };
+
+    return match output {
+        Some(path) => fs::write(&path, bytes).map_err(|e| format!("{}: {}", path, e)),
+        None => io::stdout()
+            .write_all(&bytes)
+            .map_err(|e| format!("stdout: {}", e)),
+    };
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,59 @@
+// Converted from test.map
+voxeon_os_ps2_scancodes::layout! {
+    pub struct TestLayout;
+    alt_gr: true;
+    keys {
+        Num0 => ('0', ')', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '@', _, _, Ignore),
+        Num3 => ('3', '#', _, _, Ignore),
+        Num4 => ('4', '$', _, _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', '^', _, _, Ignore),
+        Num7 => ('7', '&', _, _, Ignore),
+        Num8 => ('8', '*', _, _, Ignore),
+        Num9 => ('9', '(', _, _, Ignore),
+        CharA => ('a', 'A', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharE => ('e', 'E', '€', _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        CharM => ('m', 'M', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharY => ('z', 'Z', _, _, Auto),
+        CharZ => ('z', 'Z', _, _, Auto),
+        SymbolPlus => ('+', '+', _, _, Ignore),
+        SymbolMinus => ('-', '_', _, _, Ignore),
+        SymbolEquals => (Acute, Grave, _, _, Ignore),
+        SymbolOpenSquareBracket => ('[', '{', _, _, Ignore),
+        SymbolCloseSquareBracket => (']', '}', _, _, Ignore),
+        SymbolSemicolon => (';', ':', _, _, Ignore),
+        SymbolSingleQuote => ('\'', '"', _, _, Ignore),
+        SymbolBacktick => ('`', '~', _, _, Ignore),
+        SymbolBackslash => ('\\', '|', _, _, Ignore),
+        SymbolComma => (',', '<', _, _, Ignore),
+        SymbolPeriod => ('.', '>', _, _, Ignore),
+        SymbolForwardSlash => ('/', '?', _, _, Ignore),
+        SymbolAsterisk => ('*', '*', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: voxeon_os_ps2_scancodes::KEYPAD_ROWS;
+}
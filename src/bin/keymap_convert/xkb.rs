@@ -0,0 +1,682 @@
+use super::keysym::{is_void, parse_keysym};
+use super::{Conversion, Include, MAX_INCLUDE_DEPTH};
+use voxeon_os_ps2_scancodes::{KeySymbol, ScanType};
+
+// XKB key names of the keys a layout table covers
+const KEY_NAMES: [(&str, ScanType); 52] = {
+    use ScanType::*;
+
+    [
+        ("TLDE", SymbolBacktick),
+        ("AE01", Num1),
+        ("AE02", Num2),
+        ("AE03", Num3),
+        ("AE04", Num4),
+        ("AE05", Num5),
+        ("AE06", Num6),
+        ("AE07", Num7),
+        ("AE08", Num8),
+        ("AE09", Num9),
+        ("AE10", Num0),
+        ("AE11", SymbolMinus),
+        ("AE12", SymbolEquals),
+        ("AE13", Yen),
+        ("AD01", CharQ),
+        ("AD02", CharW),
+        ("AD03", CharE),
+        ("AD04", CharR),
+        ("AD05", CharT),
+        ("AD06", CharY),
+        ("AD07", CharU),
+        ("AD08", CharI),
+        ("AD09", CharO),
+        ("AD10", CharP),
+        ("AD11", SymbolOpenSquareBracket),
+        ("AD12", SymbolCloseSquareBracket),
+        ("AC01", CharA),
+        ("AC02", CharS),
+        ("AC03", CharD),
+        ("AC04", CharF),
+        ("AC05", CharG),
+        ("AC06", CharH),
+        ("AC07", CharJ),
+        ("AC08", CharK),
+        ("AC09", CharL),
+        ("AC10", SymbolSemicolon),
+        ("AC11", SymbolSingleQuote),
+        ("AC12", SymbolBackslash),
+        ("BKSL", SymbolBackslash),
+        ("LSGT", Iso102nd),
+        ("AB01", CharZ),
+        ("AB02", CharX),
+        ("AB03", CharC),
+        ("AB04", CharV),
+        ("AB05", CharB),
+        ("AB06", CharN),
+        ("AB07", CharM),
+        ("AB08", SymbolComma),
+        ("AB09", SymbolPeriod),
+        ("AB10", SymbolForwardSlash),
+        ("AB11", Ro),
+        ("SPCE", Space),
+    ]
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Str(String),
+    KeyName(String),
+    Punct(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            _ if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            '"' | '<' => {
+                let end = if c == '"' { '"' } else { '>' };
+                let mut text = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some(c) if c == end => break,
+                        Some('\n') | None => {
+                            return Err(format!("line {}: unterminated {}", line, c))
+                        }
+                        Some(c) => text.push(c),
+                    }
+                }
+
+                tokens.push((
+                    line,
+                    if c == '"' {
+                        Token::Str(text)
+                    } else {
+                        Token::KeyName(text)
+                    },
+                ));
+            }
+            _ if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+
+                while let Some(c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                    word.push(*c);
+                    chars.next();
+                }
+
+                tokens.push((line, Token::Word(word)));
+            }
+            _ => tokens.push((line, Token::Punct(c))),
+        }
+    }
+
+    return Ok(tokens);
+}
+
+fn is_open(token: &Token) -> bool {
+    return matches!(
+        token,
+        Token::Punct('{') | Token::Punct('[') | Token::Punct('(')
+    );
+}
+
+fn is_close(token: &Token) -> bool {
+    return matches!(
+        token,
+        Token::Punct('}') | Token::Punct(']') | Token::Punct(')')
+    );
+}
+
+// Splits tokens on a separator that isn't nested inside brackets
+fn split(tokens: &[(usize, Token)], separator: char) -> Vec<&[(usize, Token)]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, (_, token)) in tokens.iter().enumerate() {
+        if is_open(token) {
+            depth += 1;
+        } else if is_close(token) {
+            depth -= 1;
+        } else if depth == 0 && *token == Token::Punct(separator) {
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+
+    return parts;
+}
+
+// The index of the bracket closing the one at start
+fn matching(tokens: &[(usize, Token)], start: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, (_, token)) in tokens.iter().enumerate().skip(start) {
+        if is_open(token) {
+            depth += 1;
+        } else if is_close(token) {
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+
+    return None;
+}
+
+fn is_merge_mode(word: &str) -> bool {
+    return word == "augment" || word == "override" || word == "replace";
+}
+
+struct Section<'a> {
+    name: String,
+    default: bool,
+    body: &'a [(usize, Token)],
+}
+
+fn sections(tokens: &[(usize, Token)]) -> Result<Vec<Section<'_>>, String> {
+    let mut sections = Vec::new();
+    let mut default = false;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let (line, token) = &tokens[i];
+
+        match token {
+            Token::Word(word) if word == "xkb_symbols" => {
+                let name = match tokens.get(i + 1) {
+                    Some((_, Token::Str(name))) => name.clone(),
+                    _ => return Err(format!("line {}: expected a section name", line)),
+                };
+
+                if tokens.get(i + 2).map(|(_, t)| t) != Some(&Token::Punct('{')) {
+                    return Err(format!("line {}: expected {{ after {:?}", line, name));
+                }
+
+                let end = matching(tokens, i + 2)
+                    .ok_or_else(|| format!("line {}: section {:?} is not closed", line, name))?;
+
+                sections.push(Section {
+                    name,
+                    default,
+                    body: &tokens[i + 3..end],
+                });
+
+                default = false;
+                i = end + 1;
+            }
+            Token::Word(word) if word == "default" => {
+                default = true;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+
+    return Ok(sections);
+}
+
+// The keysyms of a key's first group, either a bare [ ... ] or symbols[Group1] = [ ... ]
+fn group1(body: &[(usize, Token)]) -> Option<Vec<String>> {
+    for field in split(body, ',') {
+        let list = match field.first().map(|(_, t)| t) {
+            Some(Token::Punct('[')) => field,
+            Some(Token::Word(word)) if word == "symbols" => {
+                let group = field.get(2).map(|(_, t)| t);
+
+                if group != Some(&Token::Word("Group1".to_string())) {
+                    continue;
+                }
+
+                let equals = field.iter().position(|(_, t)| *t == Token::Punct('='))?;
+
+                &field[equals + 1..]
+            }
+            _ => continue,
+        };
+
+        let end = matching(list, 0)?;
+
+        return Some(
+            split(&list[1..end], ',')
+                .iter()
+                .map(|item| {
+                    return item
+                        .iter()
+                        .map(|(_, t)| match t {
+                            Token::Word(w) | Token::Str(w) | Token::KeyName(w) => w.clone(),
+                            Token::Punct(c) => c.to_string(),
+                        })
+                        .collect::<String>();
+                })
+                .collect(),
+        );
+    }
+
+    return None;
+}
+
+fn key_statement(
+    conversion: &mut Conversion,
+    line: usize,
+    statement: &[(usize, Token)],
+) -> Result<(), String> {
+    let name = match statement.get(1).map(|(_, t)| t) {
+        Some(Token::KeyName(name)) => name.clone(),
+        _ => return Err(format!("line {}: expected a key name", line)),
+    };
+
+    if statement.get(2).map(|(_, t)| t) != Some(&Token::Punct('{')) {
+        return Err(format!("line {}: expected {{ after <{}>", line, name));
+    }
+
+    let end = matching(statement, 2)
+        .ok_or_else(|| format!("line {}: key <{}> is not closed", line, name))?;
+
+    let keysyms = match group1(&statement[3..end]) {
+        Some(keysyms) => keysyms,
+        None => return Ok(()),
+    };
+
+    let scan_type = KEY_NAMES.iter().find(|(n, _)| *n == name).map(|(_, s)| *s);
+
+    for (level, keysym) in keysyms.iter().enumerate().take(4) {
+        let symbol = parse_keysym(keysym);
+
+        match (scan_type, symbol) {
+            (Some(scan_type), Some(symbol)) => conversion.set_level(scan_type, level, symbol),
+            (Some(_), None) => conversion.report(format!(
+                "line {}: <{}> level {}: no character for {}",
+                line,
+                name,
+                level + 1,
+                keysym
+            )),
+            (None, Some(KeySymbol::Empty)) | (None, None) => {}
+            (None, Some(_)) => {
+                if !is_void(keysym) {
+                    conversion.report(format!("line {}: <{}> has no scan type", line, name));
+                }
+
+                return Ok(());
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+fn find_section<'a>(
+    sections: &'a [Section<'a>],
+    name: Option<&str>,
+) -> Result<&'a Section<'a>, String> {
+    let section = match name {
+        Some(name) => sections.iter().find(|s| s.name == name),
+        None => sections
+            .iter()
+            .find(|s| s.default)
+            .or_else(|| sections.first()),
+    };
+
+    return section.ok_or_else(|| match name {
+        Some(name) => format!("no xkb_symbols section named {:?}", name),
+        None => "no xkb_symbols section".to_string(),
+    });
+}
+
+// Converts a section of an included file, or its default section when none is named
+fn included_section(
+    conversion: &mut Conversion,
+    source: &str,
+    name: Option<&str>,
+    include: &mut Include,
+    depth: usize,
+) -> Result<(), String> {
+    let tokens = tokenize(source)?;
+    let sections = sections(&tokens)?;
+    let section = find_section(&sections, name)?;
+
+    return section_body(conversion, section.body, include, depth);
+}
+
+// An include names one or more files joined with + or |, e.g. "pc+de(nodeadkeys)", each with
+// an optional section and group. Only the first group is converted.
+fn include_statement(
+    conversion: &mut Conversion,
+    line: usize,
+    spec: &str,
+    include: &mut Include,
+    depth: usize,
+) -> Result<(), String> {
+    if depth == MAX_INCLUDE_DEPTH {
+        return Err(format!("line {}: includes nested too deeply", line));
+    }
+
+    for part in spec.split(['+', '|']).filter(|p| !p.is_empty()) {
+        let (part, group) = match part.find(':') {
+            Some(i) => (&part[..i], &part[i + 1..]),
+            None => (part, "1"),
+        };
+
+        if group != "1" {
+            conversion.report(format!(
+                "line {}: include {:?} is for group {}, only group 1 is converted",
+                line, part, group
+            ));
+            continue;
+        }
+
+        let (file, section) = match part.find('(') {
+            Some(i) => (&part[..i], Some(part[i + 1..].trim_end_matches(')'))),
+            None => (part, None),
+        };
+
+        let source = include(file).map_err(|e| format!("line {}: {}", line, e))?;
+        let notes = conversion.unmapped.len();
+
+        included_section(conversion, &source, section, include, depth + 1)
+            .map_err(|e| format!("line {}: {}: {}", line, part, e))?;
+
+        for note in conversion.unmapped[notes..].iter_mut() {
+            *note = format!("{}: {}", part, note);
+        }
+    }
+
+    return Ok(());
+}
+
+fn section_body(
+    conversion: &mut Conversion,
+    body: &[(usize, Token)],
+    include: &mut Include,
+    depth: usize,
+) -> Result<(), String> {
+    for statement in split(body, ';') {
+        let mut statement = statement;
+
+        // Includes don't need a semicolon so one can run into the next statement
+        while let [(line, Token::Word(word)), (_, Token::Str(name)), ..] = statement {
+            if word != "include" && !is_merge_mode(word) {
+                break;
+            }
+
+            include_statement(conversion, *line, name, include, depth)?;
+            statement = &statement[2..];
+        }
+
+        // replace key, override key and augment key all just define the key here
+        while let [(_, Token::Word(word)), ..] = statement {
+            if !is_merge_mode(word) {
+                break;
+            }
+
+            statement = &statement[1..];
+        }
+
+        // key.type[Group1] = "KEYPAD"; and other key.<field> statements set defaults for the
+        // keys after them, they don't define a key
+        if let [(line, Token::Word(word)), rest @ ..] = statement {
+            if word == "key" && rest.first().map(|(_, t)| t) != Some(&Token::Punct('.')) {
+                key_statement(conversion, *line, statement)?;
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+pub fn convert(
+    source: &str,
+    variant: Option<&str>,
+    include: &mut Include,
+) -> Result<Conversion, String> {
+    let tokens = tokenize(source)?;
+    let sections = sections(&tokens)?;
+    let section = find_section(&sections, variant)?;
+    let mut conversion = Conversion::default();
+
+    section_body(&mut conversion, section.body, include, 0)?;
+
+    return Ok(conversion);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use voxeon_os_ps2_scancodes::DeadKey;
+    use KeySymbol::{Char, Dead, Empty};
+
+    const GERMAN: &str = r#"
+default partial alphanumeric_keys
+xkb_symbols "basic" {
+
+    include "latin(type4)"
+
+    name[Group1]="German";
+
+    key <AE02>	{ [         2,   quotedbl,  twosuperior,   onequarter ]	};
+    key <AE12>	{ [dead_acute, dead_grave, dead_cedilla,  dead_ogonek ]	};
+    key <AD03>	{ [         e,          E,     EuroSign,     EuroSign ]	};
+    key <AD06>	{ [         z,          Z,    leftarrow,          yen ]	};
+    key <AC10>	{ type[Group1] = "FOUR_LEVEL_SEMIALPHABETIC",
+                  symbols[Group1] = [ odiaeresis, Odiaeresis, dead_doubleacute, dead_belowdot ] };
+    key <TLDE>	{ [dead_circumflex, degree, U2032, U2033 ] };
+    key <I120>	{ [ adiaeresis ] };
+    key <KPDL>	{ [ KP_Delete, KP_Separator ] };
+
+    include "kpdl(comma)"
+};
+
+partial alphanumeric_keys
+xkb_symbols "nodeadkeys" {
+    include "de(basic)"
+    key <AE12>	{ [     acute,      grave,      cedilla,     ogonek ]	};
+};
+"#;
+
+    const LATIN: &str = r#"
+default partial alphanumeric_keys
+xkb_symbols "basic" {
+    key <AE01>	{ [         1,     exclam,  onesuperior,   exclamdown ]	};
+    key <AD01>	{ [         q,          Q,           at,  Greek_OMEGA ]	};
+};
+
+partial alphanumeric_keys
+xkb_symbols "type4" {
+    include "latin(basic)"
+    key <AD01>	{ [         q,          Q,           at,  Greek_koppa ]	};
+};
+"#;
+
+    const KPDL: &str = r#"
+default partial keypad_keys
+xkb_symbols "dot" {
+    key.type[Group1]="KEYPAD" ;
+    key <KPDL> { [ KP_Delete, period ] };
+};
+
+partial keypad_keys
+xkb_symbols "comma" {
+    key.type[Group1]="KEYPAD" ;
+    key <KPDL> { [ KP_Delete, comma ] };
+};
+"#;
+
+    fn include(name: &str) -> Result<String, String> {
+        return match name {
+            "latin" => Ok(LATIN.to_string()),
+            "kpdl" => Ok(KPDL.to_string()),
+            "de" => Ok(GERMAN.to_string()),
+            _ => Err(format!("{} not found", name)),
+        };
+    }
+
+    fn entry(conversion: &Conversion, scan_type: ScanType) -> [KeySymbol; 4] {
+        return conversion
+            .entries()
+            .unwrap()
+            .iter()
+            .find(|e| e.scan_type == scan_type)
+            .unwrap()
+            .levels;
+    }
+
+    #[test]
+    fn test_convert() {
+        let conversion = convert(GERMAN, None, &mut include).unwrap();
+
+        assert!(conversion.has_alt_gr());
+        assert_eq!(
+            entry(&conversion, ScanType::Num1),
+            [Char('1'), Char('!'), Char('¹'), Char('¡')]
+        );
+        assert_eq!(
+            entry(&conversion, ScanType::CharQ),
+            [Char('q'), Char('Q'), Char('@'), Char('Ω')]
+        );
+        assert_eq!(
+            entry(&conversion, ScanType::Num2),
+            [Char('2'), Char('"'), Char('²'), Char('¼')]
+        );
+        assert_eq!(
+            entry(&conversion, ScanType::SymbolEquals),
+            [
+                Dead(DeadKey::Acute),
+                Dead(DeadKey::Grave),
+                Dead(DeadKey::Cedilla),
+                Dead(DeadKey::Ogonek)
+            ]
+        );
+        assert_eq!(
+            entry(&conversion, ScanType::CharE),
+            [Char('e'), Char('E'), Char('€'), Char('€')]
+        );
+        assert_eq!(
+            entry(&conversion, ScanType::SymbolSemicolon),
+            [Char('ö'), Char('Ö'), Dead(DeadKey::DoubleAcute), Empty]
+        );
+        assert_eq!(
+            entry(&conversion, ScanType::SymbolBacktick),
+            [Dead(DeadKey::Circumflex), Char('°'), Char('′'), Char('″')]
+        );
+
+        assert_eq!(
+            conversion.unmapped,
+            [
+                "latin(type4): line 11: <AD01> level 4: no character for Greek_koppa",
+                "line 12: <AD06> level 3: no character for leftarrow",
+                "line 13: <AC10> level 4: no character for dead_belowdot",
+                "line 16: <I120> has no scan type",
+                "kpdl(comma): line 11: <KPDL> has no scan type",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_defaults() {
+        let conversion = convert(
+            "xkb_symbols \"basic\" {\n include \"kpdl(comma)\"\n key.type = \"ALPHABETIC\";\n key <AE01> { [ 1, exclam ] };\n};",
+            None,
+            &mut include,
+        )
+        .unwrap();
+
+        assert_eq!(
+            entry(&conversion, ScanType::Num1),
+            [Char('1'), Char('!'), Empty, Empty]
+        );
+        assert_eq!(
+            conversion.unmapped,
+            ["kpdl(comma): line 11: <KPDL> has no scan type"]
+        );
+    }
+
+    #[test]
+    fn test_variant() {
+        let conversion = convert(GERMAN, Some("nodeadkeys"), &mut include).unwrap();
+
+        assert_eq!(
+            entry(&conversion, ScanType::SymbolEquals),
+            [Char('´'), Char('`'), Char('¸'), Char('˛')]
+        );
+        assert_eq!(
+            entry(&conversion, ScanType::CharE),
+            [Char('e'), Char('E'), Char('€'), Char('€')]
+        );
+        assert_eq!(
+            convert(GERMAN, Some("neo"), &mut include).err().unwrap(),
+            "no xkb_symbols section named \"neo\""
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            convert(
+                "xkb_symbols \"basic\" {\n key <AE01> { [ 1 ] };\n",
+                None,
+                &mut include
+            )
+            .err()
+            .unwrap(),
+            "line 1: section \"basic\" is not closed"
+        );
+        assert_eq!(
+            convert(
+                "xkb_symbols \"basic\" {\n key AE01 { [ 1 ] };\n};",
+                None,
+                &mut include
+            )
+            .err()
+            .unwrap(),
+            "line 2: expected a key name"
+        );
+        assert_eq!(
+            convert(
+                "xkb_symbols \"basic\" {\n include \"latin\n};",
+                None,
+                &mut include
+            )
+            .err()
+            .unwrap(),
+            "line 2: unterminated \""
+        );
+        assert_eq!(
+            convert(
+                "xkb_symbols \"basic\" {\n include \"us\"\n};",
+                None,
+                &mut include
+            )
+            .err()
+            .unwrap(),
+            "line 2: us not found"
+        );
+        assert_eq!(
+            convert(
+                "xkb_symbols \"basic\" {\n include \"de(neo)\"\n};",
+                None,
+                &mut include
+            )
+            .err()
+            .unwrap(),
+            "line 2: de(neo): no xkb_symbols section named \"neo\""
+        );
+    }
+}
@@ -0,0 +1,295 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum DeadKey {
+    Grave = 0,
+    Acute,
+    Circumflex,
+    Tilde,
+    Diaeresis,
+    Ring,
+    Cedilla,
+    Caron,
+    Macron,
+    Breve,
+    DoubleAcute,
+    Ogonek,
+    DotAbove,
+}
+
+impl DeadKey {
+    pub const ALL: [DeadKey; 13] = [
+        DeadKey::Grave,
+        DeadKey::Acute,
+        DeadKey::Circumflex,
+        DeadKey::Tilde,
+        DeadKey::Diaeresis,
+        DeadKey::Ring,
+        DeadKey::Cedilla,
+        DeadKey::Caron,
+        DeadKey::Macron,
+        DeadKey::Breve,
+        DeadKey::DoubleAcute,
+        DeadKey::Ogonek,
+        DeadKey::DotAbove,
+    ];
+
+    #[inline]
+    pub fn as_u8(self) -> u8 {
+        return self as u8;
+    }
+
+    pub fn from_u8(value: u8) -> Option<DeadKey> {
+        return DeadKey::ALL.get(value as usize).copied();
+    }
+
+    // The character produced when the dead key is followed by space or pressed twice
+    pub fn spacing_char(self) -> char {
+        return match self {
+            DeadKey::Grave => '`',
+            DeadKey::Acute => '´',
+            DeadKey::Circumflex => '^',
+            DeadKey::Tilde => '~',
+            DeadKey::Diaeresis => '¨',
+            DeadKey::Ring => '°',
+            DeadKey::Cedilla => '¸',
+            DeadKey::Caron => 'ˇ',
+            DeadKey::Macron => '¯',
+            DeadKey::Breve => '˘',
+            DeadKey::DoubleAcute => '˝',
+            DeadKey::Ogonek => '˛',
+            DeadKey::DotAbove => '˙',
+        };
+    }
+
+    // Looks the pair up in the built in compose table
+    pub fn compose(self, base: char) -> Option<char> {
+        return ComposeTable::DEFAULT.lookup(self, base);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A list of (dead key, base character, result) entries
+pub struct ComposeTable<'a> {
+    entries: &'a [(DeadKey, char, char)],
+}
+
+impl<'a> ComposeTable<'a> {
+    pub const DEFAULT: ComposeTable<'static> = ComposeTable::new(&DEFAULT_COMPOSE_ENTRIES);
+
+    pub const fn new(entries: &'a [(DeadKey, char, char)]) -> Self {
+        return Self { entries };
+    }
+
+    pub fn lookup(&self, dead_key: DeadKey, base: char) -> Option<char> {
+        for (d, b, result) in self.entries.iter() {
+            if *d == dead_key && *b == base {
+                return Some(*result);
+            }
+        }
+
+        return None;
+    }
+}
+
+const DEFAULT_COMPOSE_ENTRIES: [(DeadKey, char, char); 171] = [
+    (DeadKey::Grave, 'a', 'à'),
+    (DeadKey::Grave, 'e', 'è'),
+    (DeadKey::Grave, 'i', 'ì'),
+    (DeadKey::Grave, 'o', 'ò'),
+    (DeadKey::Grave, 'u', 'ù'),
+    (DeadKey::Grave, 'A', 'À'),
+    (DeadKey::Grave, 'E', 'È'),
+    (DeadKey::Grave, 'I', 'Ì'),
+    (DeadKey::Grave, 'O', 'Ò'),
+    (DeadKey::Grave, 'U', 'Ù'),
+    (DeadKey::Acute, 'a', 'á'),
+    (DeadKey::Acute, 'c', 'ć'),
+    (DeadKey::Acute, 'e', 'é'),
+    (DeadKey::Acute, 'i', 'í'),
+    (DeadKey::Acute, 'l', 'ĺ'),
+    (DeadKey::Acute, 'n', 'ń'),
+    (DeadKey::Acute, 'o', 'ó'),
+    (DeadKey::Acute, 'r', 'ŕ'),
+    (DeadKey::Acute, 's', 'ś'),
+    (DeadKey::Acute, 'u', 'ú'),
+    (DeadKey::Acute, 'y', 'ý'),
+    (DeadKey::Acute, 'z', 'ź'),
+    (DeadKey::Acute, 'A', 'Á'),
+    (DeadKey::Acute, 'C', 'Ć'),
+    (DeadKey::Acute, 'E', 'É'),
+    (DeadKey::Acute, 'I', 'Í'),
+    (DeadKey::Acute, 'L', 'Ĺ'),
+    (DeadKey::Acute, 'N', 'Ń'),
+    (DeadKey::Acute, 'O', 'Ó'),
+    (DeadKey::Acute, 'R', 'Ŕ'),
+    (DeadKey::Acute, 'S', 'Ś'),
+    (DeadKey::Acute, 'U', 'Ú'),
+    (DeadKey::Acute, 'Y', 'Ý'),
+    (DeadKey::Acute, 'Z', 'Ź'),
+    // Greek tonos uses the acute dead key
+    (DeadKey::Acute, 'α', 'ά'),
+    (DeadKey::Acute, 'ε', 'έ'),
+    (DeadKey::Acute, 'η', 'ή'),
+    (DeadKey::Acute, 'ι', 'ί'),
+    (DeadKey::Acute, 'ο', 'ό'),
+    (DeadKey::Acute, 'υ', 'ύ'),
+    (DeadKey::Acute, 'ω', 'ώ'),
+    (DeadKey::Acute, 'Α', 'Ά'),
+    (DeadKey::Acute, 'Ε', 'Έ'),
+    (DeadKey::Acute, 'Η', 'Ή'),
+    (DeadKey::Acute, 'Ι', 'Ί'),
+    (DeadKey::Acute, 'Ο', 'Ό'),
+    (DeadKey::Acute, 'Υ', 'Ύ'),
+    (DeadKey::Acute, 'Ω', 'Ώ'),
+    (DeadKey::Circumflex, 'a', 'â'),
+    (DeadKey::Circumflex, 'c', 'ĉ'),
+    (DeadKey::Circumflex, 'e', 'ê'),
+    (DeadKey::Circumflex, 'g', 'ĝ'),
+    (DeadKey::Circumflex, 'h', 'ĥ'),
+    (DeadKey::Circumflex, 'i', 'î'),
+    (DeadKey::Circumflex, 'j', 'ĵ'),
+    (DeadKey::Circumflex, 'o', 'ô'),
+    (DeadKey::Circumflex, 's', 'ŝ'),
+    (DeadKey::Circumflex, 'u', 'û'),
+    (DeadKey::Circumflex, 'w', 'ŵ'),
+    (DeadKey::Circumflex, 'y', 'ŷ'),
+    (DeadKey::Circumflex, 'A', 'Â'),
+    (DeadKey::Circumflex, 'C', 'Ĉ'),
+    (DeadKey::Circumflex, 'E', 'Ê'),
+    (DeadKey::Circumflex, 'G', 'Ĝ'),
+    (DeadKey::Circumflex, 'H', 'Ĥ'),
+    (DeadKey::Circumflex, 'I', 'Î'),
+    (DeadKey::Circumflex, 'J', 'Ĵ'),
+    (DeadKey::Circumflex, 'O', 'Ô'),
+    (DeadKey::Circumflex, 'S', 'Ŝ'),
+    (DeadKey::Circumflex, 'U', 'Û'),
+    (DeadKey::Circumflex, 'W', 'Ŵ'),
+    (DeadKey::Circumflex, 'Y', 'Ŷ'),
+    (DeadKey::Tilde, 'a', 'ã'),
+    (DeadKey::Tilde, 'i', 'ĩ'),
+    (DeadKey::Tilde, 'n', 'ñ'),
+    (DeadKey::Tilde, 'o', 'õ'),
+    (DeadKey::Tilde, 'u', 'ũ'),
+    (DeadKey::Tilde, 'A', 'Ã'),
+    (DeadKey::Tilde, 'I', 'Ĩ'),
+    (DeadKey::Tilde, 'N', 'Ñ'),
+    (DeadKey::Tilde, 'O', 'Õ'),
+    (DeadKey::Tilde, 'U', 'Ũ'),
+    (DeadKey::Diaeresis, 'a', 'ä'),
+    (DeadKey::Diaeresis, 'e', 'ë'),
+    (DeadKey::Diaeresis, 'i', 'ï'),
+    (DeadKey::Diaeresis, 'o', 'ö'),
+    (DeadKey::Diaeresis, 'u', 'ü'),
+    (DeadKey::Diaeresis, 'y', 'ÿ'),
+    (DeadKey::Diaeresis, 'A', 'Ä'),
+    (DeadKey::Diaeresis, 'E', 'Ë'),
+    (DeadKey::Diaeresis, 'I', 'Ï'),
+    (DeadKey::Diaeresis, 'O', 'Ö'),
+    (DeadKey::Diaeresis, 'U', 'Ü'),
+    (DeadKey::Diaeresis, 'Y', 'Ÿ'),
+    (DeadKey::Diaeresis, 'ι', 'ϊ'),
+    (DeadKey::Diaeresis, 'υ', 'ϋ'),
+    (DeadKey::Diaeresis, 'Ι', 'Ϊ'),
+    (DeadKey::Diaeresis, 'Υ', 'Ϋ'),
+    (DeadKey::Ring, 'a', 'å'),
+    (DeadKey::Ring, 'u', 'ů'),
+    (DeadKey::Ring, 'A', 'Å'),
+    (DeadKey::Ring, 'U', 'Ů'),
+    (DeadKey::Cedilla, 'c', 'ç'),
+    (DeadKey::Cedilla, 'g', 'ģ'),
+    (DeadKey::Cedilla, 'k', 'ķ'),
+    (DeadKey::Cedilla, 'l', 'ļ'),
+    (DeadKey::Cedilla, 'n', 'ņ'),
+    (DeadKey::Cedilla, 'r', 'ŗ'),
+    (DeadKey::Cedilla, 's', 'ş'),
+    (DeadKey::Cedilla, 't', 'ţ'),
+    (DeadKey::Cedilla, 'C', 'Ç'),
+    (DeadKey::Cedilla, 'G', 'Ģ'),
+    (DeadKey::Cedilla, 'K', 'Ķ'),
+    (DeadKey::Cedilla, 'L', 'Ļ'),
+    (DeadKey::Cedilla, 'N', 'Ņ'),
+    (DeadKey::Cedilla, 'R', 'Ŗ'),
+    (DeadKey::Cedilla, 'S', 'Ş'),
+    (DeadKey::Cedilla, 'T', 'Ţ'),
+    (DeadKey::Caron, 'c', 'č'),
+    (DeadKey::Caron, 'd', 'ď'),
+    (DeadKey::Caron, 'e', 'ě'),
+    (DeadKey::Caron, 'n', 'ň'),
+    (DeadKey::Caron, 'r', 'ř'),
+    (DeadKey::Caron, 's', 'š'),
+    (DeadKey::Caron, 't', 'ť'),
+    (DeadKey::Caron, 'z', 'ž'),
+    (DeadKey::Caron, 'C', 'Č'),
+    (DeadKey::Caron, 'D', 'Ď'),
+    (DeadKey::Caron, 'E', 'Ě'),
+    (DeadKey::Caron, 'N', 'Ň'),
+    (DeadKey::Caron, 'R', 'Ř'),
+    (DeadKey::Caron, 'S', 'Š'),
+    (DeadKey::Caron, 'T', 'Ť'),
+    (DeadKey::Caron, 'Z', 'Ž'),
+    (DeadKey::Macron, 'a', 'ā'),
+    (DeadKey::Macron, 'e', 'ē'),
+    (DeadKey::Macron, 'i', 'ī'),
+    (DeadKey::Macron, 'o', 'ō'),
+    (DeadKey::Macron, 'u', 'ū'),
+    (DeadKey::Macron, 'A', 'Ā'),
+    (DeadKey::Macron, 'E', 'Ē'),
+    (DeadKey::Macron, 'I', 'Ī'),
+    (DeadKey::Macron, 'O', 'Ō'),
+    (DeadKey::Macron, 'U', 'Ū'),
+    (DeadKey::Breve, 'a', 'ă'),
+    (DeadKey::Breve, 'g', 'ğ'),
+    (DeadKey::Breve, 'u', 'ŭ'),
+    (DeadKey::Breve, 'A', 'Ă'),
+    (DeadKey::Breve, 'G', 'Ğ'),
+    (DeadKey::Breve, 'U', 'Ŭ'),
+    (DeadKey::DoubleAcute, 'o', 'ő'),
+    (DeadKey::DoubleAcute, 'u', 'ű'),
+    (DeadKey::DoubleAcute, 'O', 'Ő'),
+    (DeadKey::DoubleAcute, 'U', 'Ű'),
+    (DeadKey::Ogonek, 'a', 'ą'),
+    (DeadKey::Ogonek, 'e', 'ę'),
+    (DeadKey::Ogonek, 'i', 'į'),
+    (DeadKey::Ogonek, 'u', 'ų'),
+    (DeadKey::Ogonek, 'A', 'Ą'),
+    (DeadKey::Ogonek, 'E', 'Ę'),
+    (DeadKey::Ogonek, 'I', 'Į'),
+    (DeadKey::Ogonek, 'U', 'Ų'),
+    (DeadKey::DotAbove, 'c', 'ċ'),
+    (DeadKey::DotAbove, 'e', 'ė'),
+    (DeadKey::DotAbove, 'g', 'ġ'),
+    (DeadKey::DotAbove, 'z', 'ż'),
+    (DeadKey::DotAbove, 'C', 'Ċ'),
+    (DeadKey::DotAbove, 'E', 'Ė'),
+    (DeadKey::DotAbove, 'G', 'Ġ'),
+    (DeadKey::DotAbove, 'I', 'İ'),
+    (DeadKey::DotAbove, 'Z', 'Ż'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose() {
+        assert_eq!(DeadKey::Acute.compose('e'), Some('é'));
+        assert_eq!(DeadKey::Circumflex.compose('o'), Some('ô'));
+        assert_eq!(DeadKey::Diaeresis.compose('U'), Some('Ü'));
+        assert_eq!(DeadKey::Acute.compose('x'), None);
+    }
+
+    #[test]
+    fn test_custom_table() {
+        let table = ComposeTable::new(&[(DeadKey::Tilde, 'x', 'χ')]);
+
+        assert_eq!(table.lookup(DeadKey::Tilde, 'x'), Some('χ'));
+        assert_eq!(table.lookup(DeadKey::Tilde, 'n'), None);
+    }
+
+    #[test]
+    fn test_from_u8() {
+        for dead_key in DeadKey::ALL.iter() {
+            assert_eq!(DeadKey::from_u8(dead_key.as_u8()), Some(*dead_key));
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use super::key_stroke::KeyStrokes;
+use super::layout::Layout;
+use super::reader::Reader;
+use super::{Key, KeyState, ReaderMode, ScanType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The bytes that make up a single key event, the longest sequence (pause) is 6 bytes
+pub struct ScanCodeSequence {
+    bytes: [u8; 6],
+    len: usize,
+}
+
+impl ScanCodeSequence {
+    fn new() -> Self {
+        return Self {
+            bytes: [0u8; 6],
+            len: 0,
+        };
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.bytes[self.len] = byte;
+        self.len += 1;
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        return &self.bytes[..self.len];
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The encoder is the inverse of the reader, it turns keys back into scan codes
+pub struct Encoder {
+    mode: ReaderMode,
+}
+
+impl Encoder {
+    pub fn new(mode: ReaderMode) -> Encoder {
+        return Encoder { mode };
+    }
+
+    pub fn switch_scan_mode(&mut self, mode: ReaderMode) {
+        self.mode = mode;
+    }
+
+    pub fn encode(&self, key: Key) -> Result<ScanCodeSequence, &'static str> {
+        // The reader currently interprets every mode as set 1 so we do the same
+        return match self.mode {
+            ReaderMode::Set1 => Self::encode_s1(key),
+            ReaderMode::Set2 => Self::encode_s1(key),
+            ReaderMode::Set3 => Self::encode_s1(key),
+        };
+    }
+
+    fn encode_s1(key: Key) -> Result<ScanCodeSequence, &'static str> {
+        let mut sequence = ScanCodeSequence::new();
+
+        match key.scan_type() {
+            ScanType::PrintScreen if !key.keypad() => {
+                let codes: [u8; 4] = if key.is_pressed() {
+                    [0xe0, 0x2a, 0xe0, 0x37]
+                } else {
+                    [0xe0, 0xb7, 0xe0, 0xaa]
+                };
+
+                for code in codes.iter() {
+                    sequence.push(*code);
+                }
+
+                return Ok(sequence);
+            }
+            ScanType::Pause if !key.keypad() => {
+                if !key.is_pressed() {
+                    return Err("Pause has no release scan code.");
+                }
+
+                for code in [0xe1, 0x1d, 0x45, 0xe1, 0x9d, 0xc5].iter() {
+                    sequence.push(*code);
+                }
+
+                return Ok(sequence);
+            }
+            _ => (),
+        }
+
+        let (extended, code) = match Self::base_code_s1(key.scan_type(), key.keypad()) {
+            Some(v) => v,
+            None => return Err("No scan code exists for this key."),
+        };
+
+        if extended {
+            sequence.push(0xe0);
+        }
+
+        if key.is_pressed() {
+            sequence.push(code);
+        } else {
+            sequence.push(code + 0x80);
+        }
+
+        return Ok(sequence);
+    }
+
+    fn encode_into(
+        &self,
+        key: Key,
+        buffer: &mut [u8],
+        len: &mut usize,
+    ) -> Result<(), &'static str> {
+        let sequence = self.encode(key)?;
+        let end = *len + sequence.len();
+
+        if end > buffer.len() {
+            return Err("Buffer too small for scan codes.");
+        }
+
+        buffer[*len..end].copy_from_slice(sequence.as_slice());
+        *len = end;
+
+        return Ok(());
+    }
+
+    // Writes the presses and releases that type the strokes, each one wrapped in the Shift and
+    // AltGr presses its level needs. Returns the number of bytes written.
+    pub fn encode_strokes(
+        &self,
+        strokes: &KeyStrokes,
+        buffer: &mut [u8],
+    ) -> Result<usize, &'static str> {
+        let mut len = 0;
+
+        for stroke in strokes.as_slice() {
+            let shift = Key::new(ScanType::LeftShift, KeyState::Pressed);
+            let alt_gr = Key::new(ScanType::RightAlt, KeyState::Pressed);
+            let key = if stroke.key.is_pressed() {
+                stroke.key
+            } else {
+                stroke.key.inverted_state()
+            };
+
+            if stroke.level.is_shifted() {
+                self.encode_into(shift, buffer, &mut len)?;
+            }
+
+            if stroke.level.is_alt_gr() {
+                self.encode_into(alt_gr, buffer, &mut len)?;
+            }
+
+            self.encode_into(key, buffer, &mut len)?;
+            self.encode_into(key.inverted_state(), buffer, &mut len)?;
+
+            if stroke.level.is_alt_gr() {
+                self.encode_into(alt_gr.inverted_state(), buffer, &mut len)?;
+            }
+
+            if stroke.level.is_shifted() {
+                self.encode_into(shift.inverted_state(), buffer, &mut len)?;
+            }
+        }
+
+        return Ok(len);
+    }
+
+    // Writes the scan codes that type the text on the layout, assuming no modifier is held and
+    // every lock is off. Returns the number of bytes written.
+    pub fn encode_text<L: Layout + ?Sized>(
+        &self,
+        layout: &L,
+        text: &str,
+        buffer: &mut [u8],
+    ) -> Result<usize, &'static str> {
+        let mut len = 0;
+
+        for ch in text.chars() {
+            let strokes = match layout.char_to_keys(ch) {
+                Some(strokes) => strokes,
+                None => return Err("No key on the layout types this character."),
+            };
+
+            len += self.encode_strokes(&strokes, &mut buffer[len..])?;
+        }
+
+        return Ok(len);
+    }
+
+    // Returns whether the key is prefixed with 0xe0 and the pressed code following it.
+    // We search the reader's own tables so the two can never disagree.
+    pub(crate) fn base_code_s1(scan_type: ScanType, keypad: bool) -> Option<(bool, u8)> {
+        let key = if keypad {
+            Key::new_keypad(scan_type, KeyState::Pressed)
+        } else {
+            Key::new(scan_type, KeyState::Pressed)
+        };
+
+        for code in 0x01..0x80u8 {
+            if Reader::map_simple_scan_code_s1(code) == Some(key) {
+                return Some((false, code));
+            }
+        }
+
+        for code in 0x01..0x80u8 {
+            if Reader::map_media_scan_code_s1(code) == Some(key) {
+                return Some((true, code));
+            }
+        }
+
+        return None;
+    }
+
+    // Whether the key is an "extended" key, i.e. its set 1 code is prefixed with 0xe0
+    pub fn is_extended(scan_type: ScanType, keypad: bool) -> bool {
+        if scan_type == ScanType::PrintScreen {
+            return true;
+        }
+
+        return match Self::base_code_s1(scan_type, keypad) {
+            Some((extended, _)) => extended,
+            None => false,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod set_1 {
+        use super::super::super::{
+            FrenchAzertyLayout, GermanQwertzLayout, Keyboard, USStandardLayout,
+        };
+        use super::*;
+
+        fn read_back(bytes: &[u8]) -> Option<Key> {
+            let mut reader = Reader::new(ReaderMode::Set1);
+            let mut result = None;
+
+            for b in bytes {
+                result = reader.input_scan_code(*b).unwrap();
+            }
+
+            return result;
+        }
+
+        #[test]
+        fn test_encode_simple() {
+            let encoder = Encoder::new(ReaderMode::Set1);
+
+            assert_eq!(
+                encoder
+                    .encode(Key::new(ScanType::CharG, KeyState::Pressed))
+                    .unwrap()
+                    .as_slice(),
+                &[0x22]
+            );
+            assert_eq!(
+                encoder
+                    .encode(Key::new(ScanType::CharG, KeyState::Released))
+                    .unwrap()
+                    .as_slice(),
+                &[0xa2]
+            );
+        }
+
+        #[test]
+        fn test_encode_extended() {
+            let encoder = Encoder::new(ReaderMode::Set1);
+
+            assert_eq!(
+                encoder
+                    .encode(Key::new(ScanType::PageUp, KeyState::Released))
+                    .unwrap()
+                    .as_slice(),
+                &[0xe0, 0xc9]
+            );
+            assert_eq!(
+                encoder
+                    .encode(Key::new_keypad(ScanType::Enter, KeyState::Pressed))
+                    .unwrap()
+                    .as_slice(),
+                &[0xe0, 0x1c]
+            );
+        }
+
+        #[test]
+        fn test_encode_special() {
+            let encoder = Encoder::new(ReaderMode::Set1);
+
+            assert_eq!(
+                encoder
+                    .encode(Key::new(ScanType::PrintScreen, KeyState::Pressed))
+                    .unwrap()
+                    .as_slice(),
+                &[0xe0, 0x2a, 0xe0, 0x37]
+            );
+            assert_eq!(
+                encoder
+                    .encode(Key::new(ScanType::Pause, KeyState::Pressed))
+                    .unwrap()
+                    .as_slice(),
+                &[0xe1, 0x1d, 0x45, 0xe1, 0x9d, 0xc5]
+            );
+            assert!(encoder
+                .encode(Key::new(ScanType::Pause, KeyState::Released))
+                .is_err());
+            assert!(encoder
+                .encode(Key::new(ScanType::Unknown, KeyState::Pressed))
+                .is_err());
+        }
+
+        #[test]
+        fn test_round_trip() {
+            let encoder = Encoder::new(ReaderMode::Set1);
+
+            for scan_type in ScanType::ALL.iter() {
+                for keypad in [false, true].iter() {
+                    for state in [KeyState::Pressed, KeyState::Released].iter() {
+                        let key = if *keypad {
+                            Key::new_keypad(*scan_type, *state)
+                        } else {
+                            Key::new(*scan_type, *state)
+                        };
+
+                        if let Ok(bytes) = encoder.encode(key) {
+                            assert_eq!(read_back(bytes.as_slice()), Some(key));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Types the text through a keyboard and checks it comes out unchanged
+        fn assert_round_trip<T: Layout>(layout: T, text: &str) {
+            let encoder = Encoder::new(ReaderMode::Set1);
+            let mut buffer = [0u8; 256];
+            let len = encoder.encode_text(&layout, text, &mut buffer).unwrap();
+            let mut key_board = Keyboard::new(ReaderMode::Set1, layout);
+            let mut expected = text.chars();
+
+            key_board.set_translation_state(Some(KeyState::Pressed));
+
+            for byte in buffer[..len].iter() {
+                if let Some(ch) = key_board.input_byte(*byte) {
+                    assert_eq!(Some(ch), expected.next());
+                }
+            }
+
+            assert_eq!(expected.next(), None);
+            assert!(!key_board.current_state().shift_down());
+            assert!(!key_board.current_state().alt_gr);
+        }
+
+        #[test]
+        fn test_encode_text() {
+            let encoder = Encoder::new(ReaderMode::Set1);
+            let mut buffer = [0u8; 16];
+
+            assert_eq!(
+                encoder.encode_text(&USStandardLayout, "aA", &mut buffer),
+                Ok(6)
+            );
+            assert_eq!(&buffer[..6], &[0x1e, 0x9e, 0x2a, 0x1e, 0x9e, 0xaa]);
+            assert_eq!(
+                encoder.encode_text(&GermanQwertzLayout, "€", &mut buffer),
+                Ok(6)
+            );
+            assert_eq!(&buffer[..6], &[0xe0, 0x38, 0x12, 0x92, 0xe0, 0xb8]);
+
+            assert!(encoder
+                .encode_text(&USStandardLayout, "é", &mut buffer)
+                .is_err());
+            assert!(encoder
+                .encode_text(&USStandardLayout, "AAAAA", &mut buffer)
+                .is_err());
+        }
+
+        #[test]
+        fn test_text_round_trip() {
+            assert_round_trip(USStandardLayout, "Hello, World! 1+1=2");
+            assert_round_trip(GermanQwertzLayout, "Grüße, café à 5€ ^2");
+            assert_round_trip(FrenchAzertyLayout, "ça coûte ñ @");
+        }
+    }
+}
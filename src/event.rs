@@ -0,0 +1,146 @@
+use super::layout::KeyModifierState;
+use super::{Key, ScanType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecureAttention {
+    CtrlAltDelete,
+    // Alt+SysRq held while the given command key was pressed
+    SysRq(ScanType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    // A key changed state, the modifiers are those in effect after the key was applied and
+    // ch is the character the layout produced for it (if any).
+    Key {
+        key: Key,
+        modifiers: KeyModifierState,
+        ch: Option<char>,
+    },
+    // A registered hotkey was pressed. When it was not consumed the key follows as a normal event.
+    Hotkey {
+        id: u16,
+        key: Key,
+        consumed: bool,
+    },
+    // A registered key sequence completed, its keys don't appear as events
+    Sequence(u16),
+    // A character that doesn't belong to a single key, e.g. a dead key that didn't combine
+    Char(char),
+    // Detected from the physical keys before any layout or hotkey sees them, the key that
+    // completed the sequence is swallowed.
+    SecureAttention(SecureAttention),
+}
+
+impl KeyEvent {
+    pub fn key(&self) -> Option<Key> {
+        return match self {
+            KeyEvent::Key { key, .. } => Some(*key),
+            KeyEvent::Hotkey { key, .. } => Some(*key),
+            KeyEvent::Sequence(_) => None,
+            KeyEvent::Char(_) => None,
+            KeyEvent::SecureAttention(_) => None,
+        };
+    }
+
+    pub fn char(&self) -> Option<char> {
+        return match self {
+            KeyEvent::Key { ch, .. } => *ch,
+            KeyEvent::Char(ch) => Some(*ch),
+            _ => None,
+        };
+    }
+}
+
+pub(crate) const EVENT_QUEUE_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+// A fixed size ring of pending events, the oldest event is dropped when it overflows.
+pub(crate) struct EventQueue<T = KeyEvent> {
+    events: [Option<T>; EVENT_QUEUE_CAPACITY],
+    head: usize,
+    len: usize,
+    // Every event ever pushed, wrapping, so a caller can tell how many a call added
+    pushed: usize,
+}
+
+impl<T: Copy> EventQueue<T> {
+    pub(crate) const fn new() -> Self {
+        return Self {
+            events: [None; EVENT_QUEUE_CAPACITY],
+            head: 0,
+            len: 0,
+            pushed: 0,
+        };
+    }
+
+    pub(crate) fn push(&mut self, event: T) {
+        let tail = (self.head + self.len) % EVENT_QUEUE_CAPACITY;
+        self.events[tail] = Some(event);
+        self.pushed = self.pushed.wrapping_add(1);
+
+        if self.len == EVENT_QUEUE_CAPACITY {
+            self.head = (self.head + 1) % EVENT_QUEUE_CAPACITY;
+        } else {
+            self.len += 1;
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub(crate) fn pushed(&self) -> usize {
+        return self.pushed;
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let event = self.events[self.head].take();
+        self.head = (self.head + 1) % EVENT_QUEUE_CAPACITY;
+        self.len -= 1;
+
+        return event;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::KeyState;
+    use super::*;
+
+    fn event(scan_type: ScanType) -> KeyEvent {
+        return KeyEvent::Key {
+            key: Key::new(scan_type, KeyState::Pressed),
+            modifiers: KeyModifierState::new(),
+            ch: None,
+        };
+    }
+
+    #[test]
+    fn test_queue_order() {
+        let mut queue = EventQueue::new();
+
+        queue.push(event(ScanType::CharA));
+        queue.push(event(ScanType::CharB));
+
+        assert_eq!(queue.pop(), Some(event(ScanType::CharA)));
+        assert_eq!(queue.pop(), Some(event(ScanType::CharB)));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_queue_overflow() {
+        let mut queue = EventQueue::new();
+
+        for scan_type in ScanType::ALL.iter().take(EVENT_QUEUE_CAPACITY + 1) {
+            queue.push(event(*scan_type));
+        }
+
+        // The first event was dropped
+        assert_eq!(queue.pop(), Some(event(ScanType::ALL[1])));
+    }
+}
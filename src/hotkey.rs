@@ -0,0 +1,520 @@
+use super::layout::KeyModifierState;
+use super::names::parse_key_name;
+use super::{Key, ScanType};
+use core::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModifierSide {
+    Either,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The modifiers a hotkey requires, None means the modifier is not part of the hotkey.
+pub struct HotkeyModifiers {
+    pub ctrl: Option<ModifierSide>,
+    pub shift: Option<ModifierSide>,
+    pub alt: Option<ModifierSide>,
+    pub gui: Option<ModifierSide>,
+}
+
+impl HotkeyModifiers {
+    pub const fn new() -> Self {
+        return Self {
+            ctrl: None,
+            shift: None,
+            alt: None,
+            gui: None,
+        };
+    }
+
+    fn side(&self, kind: ModifierKind) -> Option<ModifierSide> {
+        return match kind {
+            ModifierKind::Ctrl => self.ctrl,
+            ModifierKind::Shift => self.shift,
+            ModifierKind::Alt => self.alt,
+            ModifierKind::Gui => self.gui,
+        };
+    }
+
+    fn slot(&mut self, kind: ModifierKind) -> &mut Option<ModifierSide> {
+        return match kind {
+            ModifierKind::Ctrl => &mut self.ctrl,
+            ModifierKind::Shift => &mut self.shift,
+            ModifierKind::Alt => &mut self.alt,
+            ModifierKind::Gui => &mut self.gui,
+        };
+    }
+}
+
+impl Default for HotkeyModifiers {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyMatch {
+    // Exactly the required modifiers must be held
+    Exact,
+    // The required modifiers must be held, others are ignored
+    Subset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    modifiers: HotkeyModifiers,
+    scan_type: ScanType,
+    keypad: bool,
+    mode: HotkeyMatch,
+    // The key is a modifier named without a side, e.g. the Shift of "Alt+Shift"
+    either_side: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModifierKind {
+    Ctrl,
+    Shift,
+    Alt,
+    Gui,
+}
+
+fn modifier_kind(scan_type: ScanType) -> Option<(ModifierKind, ModifierSide)> {
+    return match scan_type {
+        ScanType::LeftCtrl => Some((ModifierKind::Ctrl, ModifierSide::Left)),
+        ScanType::RightCtrl => Some((ModifierKind::Ctrl, ModifierSide::Right)),
+        ScanType::LeftShift => Some((ModifierKind::Shift, ModifierSide::Left)),
+        ScanType::RightShift => Some((ModifierKind::Shift, ModifierSide::Right)),
+        ScanType::LeftAlt => Some((ModifierKind::Alt, ModifierSide::Left)),
+        ScanType::RightAlt => Some((ModifierKind::Alt, ModifierSide::Right)),
+        ScanType::LeftGUI => Some((ModifierKind::Gui, ModifierSide::Left)),
+        ScanType::RightGUI => Some((ModifierKind::Gui, ModifierSide::Right)),
+        _ => None,
+    };
+}
+
+// Names that refer to a modifier without choosing a side
+const UNSIDED_MODIFIERS: [(&str, ModifierKind); 10] = [
+    ("ctrl", ModifierKind::Ctrl),
+    ("control", ModifierKind::Ctrl),
+    ("shift", ModifierKind::Shift),
+    ("alt", ModifierKind::Alt),
+    ("option", ModifierKind::Alt),
+    ("gui", ModifierKind::Gui),
+    ("win", ModifierKind::Gui),
+    ("super", ModifierKind::Gui),
+    ("meta", ModifierKind::Gui),
+    ("cmd", ModifierKind::Gui),
+];
+
+fn parse_modifier(name: &str) -> Option<(ModifierKind, ModifierSide)> {
+    let name = name.trim();
+
+    for (unsided, kind) in UNSIDED_MODIFIERS.iter() {
+        if name.eq_ignore_ascii_case(unsided) {
+            return Some((*kind, ModifierSide::Either));
+        }
+    }
+
+    let (scan_type, _) = parse_key_name(name)?;
+
+    return modifier_kind(scan_type);
+}
+
+fn side_allows(required: Option<ModifierSide>, side: ModifierSide) -> bool {
+    return match required {
+        Some(ModifierSide::Either) => true,
+        Some(required) => required == side,
+        None => false,
+    };
+}
+
+fn side_matches(
+    required: Option<ModifierSide>,
+    left: bool,
+    right: bool,
+    mode: HotkeyMatch,
+) -> bool {
+    return match (required, mode) {
+        (None, HotkeyMatch::Exact) => !left && !right,
+        (None, HotkeyMatch::Subset) => true,
+        (Some(ModifierSide::Either), _) => left || right,
+        (Some(ModifierSide::Left), HotkeyMatch::Exact) => left && !right,
+        (Some(ModifierSide::Left), HotkeyMatch::Subset) => left,
+        (Some(ModifierSide::Right), HotkeyMatch::Exact) => right && !left,
+        (Some(ModifierSide::Right), HotkeyMatch::Subset) => right,
+    };
+}
+
+impl Hotkey {
+    pub const fn new(modifiers: HotkeyModifiers, scan_type: ScanType) -> Self {
+        return Self {
+            modifiers,
+            scan_type,
+            keypad: false,
+            mode: HotkeyMatch::Exact,
+            either_side: false,
+        };
+    }
+
+    pub const fn new_keypad(modifiers: HotkeyModifiers, scan_type: ScanType) -> Self {
+        return Self {
+            modifiers,
+            scan_type,
+            keypad: true,
+            mode: HotkeyMatch::Exact,
+            either_side: false,
+        };
+    }
+
+    pub const fn with_match(mut self, mode: HotkeyMatch) -> Self {
+        self.mode = mode;
+        return self;
+    }
+
+    #[inline]
+    pub fn modifiers(&self) -> HotkeyModifiers {
+        return self.modifiers;
+    }
+
+    #[inline]
+    pub fn scan_type(&self) -> ScanType {
+        return self.scan_type;
+    }
+
+    #[inline]
+    pub fn keypad(&self) -> bool {
+        return self.keypad;
+    }
+
+    #[inline]
+    pub fn match_mode(&self) -> HotkeyMatch {
+        return self.mode;
+    }
+
+    // Parses strings such as "Ctrl+Shift+Esc", "RightAlt+F4" or "Ctrl++".
+    pub fn parse(s: &str) -> Result<Hotkey, &'static str> {
+        let s = s.trim();
+
+        // A trailing "++" means the key itself is the plus key
+        let (modifier_part, key_name) = if s.ends_with("++") || s == "+" {
+            (&s[..s.len() - 1], "+")
+        } else {
+            match s.rfind('+') {
+                Some(i) => (&s[..i + 1], &s[i + 1..]),
+                None => ("", s),
+            }
+        };
+
+        let (scan_type, keypad) = match parse_key_name(key_name) {
+            Some(v) => v,
+            None => return Err("Unknown key name in hotkey."),
+        };
+        let either_side =
+            parse_modifier(key_name).map(|(_, side)| side) == Some(ModifierSide::Either);
+
+        let mut modifiers = HotkeyModifiers::new();
+
+        for name in modifier_part.split('+').filter(|n| !n.trim().is_empty()) {
+            let (kind, side) = match parse_modifier(name) {
+                Some(v) => v,
+                None => return Err("Unknown modifier in hotkey."),
+            };
+
+            let slot = modifiers.slot(kind);
+
+            if slot.is_some() {
+                return Err("Modifier repeated in hotkey.");
+            }
+
+            *slot = Some(side);
+        }
+
+        return Ok(Hotkey {
+            modifiers,
+            scan_type,
+            keypad,
+            mode: HotkeyMatch::Exact,
+            either_side,
+        });
+    }
+
+    // The modifiers including the hotkey's own key when that is a modifier too
+    fn chord(&self) -> HotkeyModifiers {
+        let mut chord = self.modifiers;
+
+        if let Some((kind, side)) = modifier_kind(self.scan_type) {
+            let side = if self.either_side {
+                ModifierSide::Either
+            } else {
+                side
+            };
+
+            *chord.slot(kind) = Some(side);
+        }
+
+        return chord;
+    }
+
+    // A hotkey made only of modifiers, e.g. "Alt+Shift", fires on whichever of them is pressed
+    // last. The pressed key's own state is not checked.
+    pub fn matches(&self, key: &Key, modifiers: &KeyModifierState) -> bool {
+        if !key.is_pressed() || key.keypad() != self.keypad {
+            return false;
+        }
+
+        let chord = self.chord();
+        let pressed_kind = match modifier_kind(key.scan_type()) {
+            Some((kind, side)) if side_allows(chord.side(kind), side) => Some(kind),
+            _ if key.scan_type() == self.scan_type => None,
+            _ => return false,
+        };
+
+        // Other hotkeys only fire on their own key, not on one of their modifiers
+        if pressed_kind.is_some() && modifier_kind(self.scan_type).is_none() {
+            return false;
+        }
+
+        let checks = [
+            (
+                ModifierKind::Ctrl,
+                chord.ctrl,
+                modifiers.left_ctrl,
+                modifiers.right_ctrl,
+            ),
+            (
+                ModifierKind::Shift,
+                chord.shift,
+                modifiers.left_shift,
+                modifiers.right_shift,
+            ),
+            (
+                ModifierKind::Alt,
+                chord.alt,
+                modifiers.left_alt,
+                modifiers.right_alt,
+            ),
+            (
+                ModifierKind::Gui,
+                chord.gui,
+                modifiers.left_gui,
+                modifiers.right_gui,
+            ),
+        ];
+
+        for (kind, required, left, right) in checks.iter() {
+            if pressed_kind == Some(*kind) {
+                continue;
+            }
+
+            if !side_matches(*required, *left, *right, self.mode) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Hotkey::parse(s);
+    }
+}
+
+pub const HOTKEY_TABLE_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotkeyBinding {
+    pub id: u16,
+    pub hotkey: Hotkey,
+    // Whether the key press should be swallowed instead of being reported as a key
+    pub consume: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotkeyTable {
+    bindings: [Option<HotkeyBinding>; HOTKEY_TABLE_CAPACITY],
+}
+
+impl HotkeyTable {
+    pub const fn new() -> Self {
+        return Self {
+            bindings: [None; HOTKEY_TABLE_CAPACITY],
+        };
+    }
+
+    pub fn register(&mut self, id: u16, hotkey: Hotkey, consume: bool) -> Result<(), &'static str> {
+        for slot in self.bindings.iter_mut() {
+            if slot.is_none() {
+                *slot = Some(HotkeyBinding {
+                    id,
+                    hotkey,
+                    consume,
+                });
+
+                return Ok(());
+            }
+        }
+
+        return Err("Hotkey table full.");
+    }
+
+    // Removes every binding with the id, returns whether any were removed
+    pub fn unregister(&mut self, id: u16) -> bool {
+        let mut removed = false;
+
+        for slot in self.bindings.iter_mut() {
+            if let Some(binding) = slot {
+                if binding.id == id {
+                    *slot = None;
+                    removed = true;
+                }
+            }
+        }
+
+        return removed;
+    }
+
+    pub fn clear(&mut self) {
+        self.bindings = [None; HOTKEY_TABLE_CAPACITY];
+    }
+
+    // Returns the first binding that matches the key
+    pub fn find(&self, key: &Key, modifiers: &KeyModifierState) -> Option<HotkeyBinding> {
+        for binding in self.bindings.iter().flatten() {
+            if binding.hotkey.matches(key, modifiers) {
+                return Some(*binding);
+            }
+        }
+
+        return None;
+    }
+}
+
+impl Default for HotkeyTable {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::KeyState;
+    use super::*;
+
+    fn pressed(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Pressed);
+    }
+
+    #[test]
+    fn test_parse() {
+        let hotkey = Hotkey::parse("Ctrl+Shift+Esc").unwrap();
+
+        assert_eq!(hotkey.scan_type(), ScanType::Escape);
+        assert_eq!(hotkey.modifiers().ctrl, Some(ModifierSide::Either));
+        assert_eq!(hotkey.modifiers().shift, Some(ModifierSide::Either));
+        assert_eq!(hotkey.modifiers().alt, None);
+
+        let hotkey: Hotkey = "RightAlt+F4".parse().unwrap();
+
+        assert_eq!(hotkey.scan_type(), ScanType::F4);
+        assert_eq!(hotkey.modifiers().alt, Some(ModifierSide::Right));
+
+        let hotkey = Hotkey::parse("ctrl++").unwrap();
+
+        assert_eq!(hotkey.scan_type(), ScanType::SymbolPlus);
+        assert!(hotkey.keypad());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Hotkey::parse("Ctrl+Nope").is_err());
+        assert!(Hotkey::parse("Q+A").is_err());
+        assert!(Hotkey::parse("Ctrl+LCtrl+A").is_err());
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let hotkey = Hotkey::parse("Ctrl+Alt+Delete").unwrap();
+        let mut modifiers = KeyModifierState::new();
+        modifiers.right_ctrl = true;
+        modifiers.left_alt = true;
+
+        assert!(hotkey.matches(&pressed(ScanType::Delete), &modifiers));
+        assert!(!hotkey.matches(&Key::new(ScanType::Delete, KeyState::Released), &modifiers));
+
+        modifiers.left_shift = true;
+        assert!(!hotkey.matches(&pressed(ScanType::Delete), &modifiers));
+    }
+
+    #[test]
+    fn test_subset_match() {
+        let hotkey = Hotkey::parse("LCtrl+C")
+            .unwrap()
+            .with_match(HotkeyMatch::Subset);
+        let mut modifiers = KeyModifierState::new();
+        modifiers.left_ctrl = true;
+        modifiers.left_shift = true;
+
+        assert!(hotkey.matches(&pressed(ScanType::CharC), &modifiers));
+
+        modifiers.left_ctrl = false;
+        modifiers.right_ctrl = true;
+        assert!(!hotkey.matches(&pressed(ScanType::CharC), &modifiers));
+    }
+
+    #[test]
+    fn test_modifier_as_key() {
+        let hotkey = Hotkey::parse("Alt+Shift").unwrap();
+        let mut modifiers = KeyModifierState::new();
+        modifiers.left_alt = true;
+        modifiers.left_shift = true;
+
+        assert!(hotkey.matches(&pressed(ScanType::LeftShift), &modifiers));
+
+        // Either Shift and either order
+        modifiers.left_shift = false;
+        modifiers.right_shift = true;
+        assert!(hotkey.matches(&pressed(ScanType::RightShift), &modifiers));
+        assert!(hotkey.matches(&pressed(ScanType::LeftAlt), &modifiers));
+
+        let hotkey = Hotkey::parse("Alt+LeftShift").unwrap();
+        assert!(!hotkey.matches(&pressed(ScanType::RightShift), &modifiers));
+        assert!(!hotkey.matches(&pressed(ScanType::LeftAlt), &modifiers));
+
+        // Modifiers aren't the key of other hotkeys
+        let hotkey = Hotkey::parse("Ctrl+C").unwrap();
+        modifiers.right_shift = false;
+        modifiers.left_alt = false;
+        modifiers.left_ctrl = true;
+        assert!(!hotkey.matches(&pressed(ScanType::LeftCtrl), &modifiers));
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = HotkeyTable::new();
+        table
+            .register(1, Hotkey::parse("Ctrl+Q").unwrap(), true)
+            .unwrap();
+        table
+            .register(2, Hotkey::parse("Ctrl+W").unwrap(), false)
+            .unwrap();
+
+        let mut modifiers = KeyModifierState::new();
+        modifiers.left_ctrl = true;
+
+        assert_eq!(
+            table
+                .find(&pressed(ScanType::CharW), &modifiers)
+                .unwrap()
+                .id,
+            2
+        );
+        assert!(table.unregister(2));
+        assert!(table.find(&pressed(ScanType::CharW), &modifiers).is_none());
+    }
+}
@@ -0,0 +1,250 @@
+use super::scan_code_set::{KeySet, KEY_SLOT_COUNT};
+use super::{Key, KeyState};
+
+#[derive(Debug, Clone, Copy)]
+// The keys a filter let through for one input, at most a press it accepted late and the key itself
+pub(crate) struct FilteredKeys {
+    keys: [Option<Key>; 2],
+}
+
+impl FilteredKeys {
+    const fn new() -> Self {
+        return Self { keys: [None; 2] };
+    }
+
+    fn push(&mut self, key: Key) {
+        let slot = if self.keys[0].is_none() { 0 } else { 1 };
+        self.keys[slot] = Some(key);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Key> + '_ {
+        return self.keys.iter().flatten().copied();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+// Slow Keys and Bounce Keys. Times are milliseconds from any monotonic clock the caller has,
+// the filter only ever compares them.
+//
+// Slow Keys holds a press back until the key has been down for the delay. It is accepted by the
+// first input or tick after that, a key released sooner is dropped together with its release.
+// Bounce Keys drops a press of the key that was released less than the delay ago, along with
+// its repeats and release.
+pub(crate) struct KeyFilter {
+    slow_keys: Option<u32>,
+    bounce_keys: Option<u32>,
+    pending: Option<(Key, u64)>,
+    // When each key was last released
+    released_at: [Option<u64>; KEY_SLOT_COUNT],
+    held: KeySet,
+    rejected: KeySet,
+}
+
+impl KeyFilter {
+    pub(crate) const fn new() -> Self {
+        return Self {
+            slow_keys: None,
+            bounce_keys: None,
+            pending: None,
+            released_at: [None; KEY_SLOT_COUNT],
+            held: KeySet::new(),
+            rejected: KeySet::new(),
+        };
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        return self.slow_keys.is_some() || self.bounce_keys.is_some() || self.pending.is_some();
+    }
+
+    pub(crate) fn set_slow_keys(&mut self, delay: Option<u32>) {
+        self.slow_keys = delay;
+    }
+
+    pub(crate) fn set_bounce_keys(&mut self, delay: Option<u32>) {
+        self.bounce_keys = delay;
+    }
+
+    // Accepts the pending press once it has been held long enough
+    pub(crate) fn tick(&mut self, now: u64) -> Option<Key> {
+        let (key, pressed_at) = self.pending?;
+        let delay = self.slow_keys.unwrap_or(0) as u64;
+
+        if now.saturating_sub(pressed_at) < delay {
+            return None;
+        }
+
+        self.pending = None;
+        self.held.insert(&key);
+
+        return Some(key);
+    }
+
+    pub(crate) fn input(&mut self, key: Key, now: u64) -> FilteredKeys {
+        let mut keys = FilteredKeys::new();
+
+        if let Some(accepted) = self.tick(now) {
+            keys.push(accepted);
+        }
+
+        if self.accept(key, now) {
+            keys.push(key);
+        }
+
+        return keys;
+    }
+
+    fn is_pending(&self, key: Key) -> bool {
+        return match self.pending {
+            Some((pending, _)) => {
+                pending.scan_type() == key.scan_type() && pending.keypad() == key.keypad()
+            }
+            None => false,
+        };
+    }
+
+    fn bounced(&self, key: Key, now: u64) -> bool {
+        let (delay, index) = match (self.bounce_keys, key.slot()) {
+            (Some(delay), Some(index)) => (delay, index),
+            _ => return false,
+        };
+
+        return match self.released_at[index] {
+            Some(released_at) => now.saturating_sub(released_at) < delay as u64,
+            None => false,
+        };
+    }
+
+    fn accept(&mut self, key: Key, now: u64) -> bool {
+        if key.state() == KeyState::Released {
+            if self.is_pending(key) {
+                self.pending = None;
+                return false;
+            }
+
+            if self.rejected.remove(&key) {
+                return false;
+            }
+
+            self.held.remove(&key);
+
+            if let Some(index) = key.slot() {
+                self.released_at[index] = Some(now);
+            }
+
+            return true;
+        }
+
+        // Repeats follow whatever happened to the first press
+        if self.held.contains(&key) {
+            return true;
+        }
+
+        if self.rejected.contains(&key) || self.is_pending(key) {
+            return false;
+        }
+
+        if self.bounced(key, now) {
+            self.rejected.insert(&key);
+            return false;
+        }
+
+        if self.slow_keys.is_some() {
+            // Only one key can wait at a time, starting another gives up on the first
+            if let Some((pending, _)) = self.pending.replace((key, now)) {
+                self.rejected.insert(&pending);
+            }
+
+            return false;
+        }
+
+        self.held.insert(&key);
+
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ScanType;
+    use super::*;
+
+    fn press(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Pressed);
+    }
+
+    fn release(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Released);
+    }
+
+    fn count(keys: FilteredKeys) -> usize {
+        return keys.iter().count();
+    }
+
+    #[test]
+    fn test_slow_keys() {
+        let mut filter = KeyFilter::new();
+        filter.set_slow_keys(Some(300));
+
+        // Released too soon
+        assert_eq!(count(filter.input(press(ScanType::CharA), 0)), 0);
+        assert_eq!(count(filter.input(release(ScanType::CharA), 200)), 0);
+
+        // Held long enough, accepted by a repeat or a tick
+        assert_eq!(count(filter.input(press(ScanType::CharA), 1000)), 0);
+        assert_eq!(filter.tick(1100), None);
+        assert_eq!(filter.tick(1300), Some(press(ScanType::CharA)));
+        assert_eq!(count(filter.input(press(ScanType::CharA), 1330)), 1);
+        assert_eq!(count(filter.input(release(ScanType::CharA), 1400)), 1);
+
+        // A second key gives up on the first
+        filter.input(press(ScanType::CharA), 2000);
+        filter.input(press(ScanType::CharB), 2100);
+        assert_eq!(count(filter.input(release(ScanType::CharA), 2350)), 0);
+        let keys = filter.input(release(ScanType::CharB), 2450);
+        assert!(keys
+            .iter()
+            .eq([press(ScanType::CharB), release(ScanType::CharB)]
+                .iter()
+                .copied()));
+    }
+
+    #[test]
+    fn test_bounce_keys() {
+        let mut filter = KeyFilter::new();
+        filter.set_bounce_keys(Some(200));
+
+        assert_eq!(count(filter.input(press(ScanType::CharA), 0)), 1);
+        assert_eq!(count(filter.input(release(ScanType::CharA), 50)), 1);
+
+        // The bounce and everything until its release are dropped
+        assert_eq!(count(filter.input(press(ScanType::CharA), 100)), 0);
+        assert_eq!(count(filter.input(press(ScanType::CharA), 600)), 0);
+        assert_eq!(count(filter.input(release(ScanType::CharA), 650)), 0);
+
+        // Other keys aren't affected
+        assert_eq!(count(filter.input(press(ScanType::CharB), 660)), 1);
+        assert_eq!(count(filter.input(release(ScanType::CharB), 670)), 1);
+        assert_eq!(count(filter.input(press(ScanType::CharA), 680)), 1);
+    }
+
+    #[test]
+    fn test_bounce_after_other_release() {
+        let mut filter = KeyFilter::new();
+        filter.set_bounce_keys(Some(200));
+
+        filter.input(press(ScanType::CharA), 0);
+        filter.input(press(ScanType::CharB), 10);
+        assert_eq!(count(filter.input(release(ScanType::CharA), 50)), 1);
+        assert_eq!(count(filter.input(release(ScanType::CharB), 60)), 1);
+
+        // A's release is still remembered after B's
+        assert_eq!(count(filter.input(press(ScanType::CharA), 100)), 0);
+        assert_eq!(count(filter.input(press(ScanType::CharB), 100)), 0);
+
+        // The keypad 7 is a different key from the top-row 7
+        filter.input(press(ScanType::Num7), 1000);
+        filter.input(release(ScanType::Num7), 1010);
+        let keypad = Key::new_keypad(ScanType::Num7, KeyState::Pressed);
+        assert_eq!(count(filter.input(keypad, 1020)), 1);
+    }
+}
@@ -0,0 +1,236 @@
+use super::layout::{KeyLevel, KeyModifierState, Layout};
+use super::reader::Reader;
+use super::Key;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A key pressed on a level, e.g. Shift+2 for '@' on the US layout
+pub struct KeyStroke {
+    pub key: Key,
+    pub level: KeyLevel,
+}
+
+impl KeyStroke {
+    pub const fn new(key: Key, level: KeyLevel) -> Self {
+        return Self { key, level };
+    }
+
+    // The modifier state the layout sees while the stroke is held, with every lock off
+    pub fn modifiers(&self) -> KeyModifierState {
+        let mut modifiers = KeyModifierState::new();
+
+        modifiers.left_shift = self.level.is_shifted();
+        modifiers.right_alt = self.level.is_alt_gr();
+        modifiers.alt_gr = self.level.is_alt_gr();
+
+        return modifiers;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The strokes that type one character: a single key, or a dead key and the key it combines with
+pub struct KeyStrokes {
+    strokes: [KeyStroke; 2],
+    len: usize,
+}
+
+impl KeyStrokes {
+    fn single(stroke: KeyStroke) -> Self {
+        return Self {
+            strokes: [stroke; 2],
+            len: 1,
+        };
+    }
+
+    fn pair(dead_key: KeyStroke, stroke: KeyStroke) -> Self {
+        return Self {
+            strokes: [dead_key, stroke],
+            len: 2,
+        };
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[KeyStroke] {
+        return &self.strokes[..self.len];
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    // Whether the character needs a dead key first
+    pub fn is_dead_key_sequence(&self) -> bool {
+        return self.len == 2;
+    }
+}
+
+// Every key the reader can produce with a single scan code
+fn encodable_key(index: usize) -> Option<Key> {
+    let code = (index % 0x7f) as u8 + 1;
+
+    if index < 0x7f {
+        return Reader::map_simple_scan_code_s1(code);
+    }
+
+    return Reader::map_media_scan_code_s1(code);
+}
+
+// Main block keys are tried before the keypad so digits don't depend on Num Lock, and lower
+// levels before higher ones so '1' isn't typed as AltGr+something.
+fn find_stroke<L, F>(layout: &L, mut matches: F) -> Option<KeyStroke>
+where
+    L: Layout + ?Sized,
+    F: FnMut(&KeyModifierState, Key) -> bool,
+{
+    let levels: &[KeyLevel] = if layout.has_alt_gr() {
+        &[
+            KeyLevel::Base,
+            KeyLevel::Shift,
+            KeyLevel::AltGr,
+            KeyLevel::ShiftAltGr,
+        ]
+    } else {
+        &[KeyLevel::Base, KeyLevel::Shift]
+    };
+
+    for keypad in [false, true].iter() {
+        for level in levels.iter() {
+            for index in 0..0x7f * 2 {
+                let key = match encodable_key(index) {
+                    Some(key) if key.keypad() == *keypad => key,
+                    _ => continue,
+                };
+
+                let stroke = KeyStroke::new(key, *level);
+
+                if matches(&stroke.modifiers(), key) {
+                    return Some(stroke);
+                }
+            }
+        }
+    }
+
+    return None;
+}
+
+fn direct_stroke<L: Layout + ?Sized>(layout: &L, ch: char) -> Option<KeyStroke> {
+    return find_stroke(layout, |modifiers, key| {
+        return layout.key_into_char(modifiers, key) == Some(ch);
+    });
+}
+
+// How to type a character on the layout's first group with every lock off. Characters that
+// aren't on any key are typed through a dead key when one composes them.
+pub(crate) fn find_key_strokes<L: Layout + ?Sized>(layout: &L, ch: char) -> Option<KeyStrokes> {
+    if let Some(stroke) = direct_stroke(layout, ch) {
+        return Some(KeyStrokes::single(stroke));
+    }
+
+    let mut result = None;
+
+    find_stroke(layout, |modifiers, key| {
+        let dead_key = match layout.dead_key(modifiers, key) {
+            Some(dead_key) => dead_key,
+            None => return false,
+        };
+
+        let base = if dead_key.spacing_char() == ch {
+            direct_stroke(layout, ' ')
+        } else {
+            find_stroke(layout, |modifiers, key| {
+                return match layout.key_into_char(modifiers, key) {
+                    Some(base) => layout.compose(modifiers, dead_key, base) == Some(ch),
+                    None => false,
+                };
+            })
+        };
+
+        let dead_stroke = KeyStroke::new(key, modifiers.level());
+
+        result = base.map(|base| KeyStrokes::pair(dead_stroke, base));
+
+        return result.is_some();
+    });
+
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        FrenchAzertyLayout, GermanQwertzLayout, KeyState, ScanType, USStandardLayout,
+    };
+    use super::*;
+
+    fn stroke(scan_type: ScanType, level: KeyLevel) -> KeyStroke {
+        return KeyStroke::new(Key::new(scan_type, KeyState::Pressed), level);
+    }
+
+    #[test]
+    fn test_direct() {
+        let layout = USStandardLayout;
+
+        assert_eq!(
+            layout.char_to_keys('a').unwrap().as_slice(),
+            [stroke(ScanType::CharA, KeyLevel::Base)]
+        );
+        assert_eq!(
+            layout.char_to_keys('@').unwrap().as_slice(),
+            [stroke(ScanType::Num2, KeyLevel::Shift)]
+        );
+        assert_eq!(
+            layout.char_to_keys('+').unwrap().as_slice(),
+            [stroke(ScanType::SymbolEquals, KeyLevel::Shift)]
+        );
+        assert_eq!(
+            layout.char_to_keys('*').unwrap().as_slice(),
+            [stroke(ScanType::Num8, KeyLevel::Shift)]
+        );
+        assert_eq!(layout.char_to_keys('é'), None);
+    }
+
+    #[test]
+    fn test_alt_gr() {
+        assert_eq!(
+            GermanQwertzLayout.char_to_keys('€').unwrap().as_slice(),
+            [stroke(ScanType::CharE, KeyLevel::AltGr)]
+        );
+    }
+
+    #[test]
+    fn test_dead_key_sequence() {
+        let strokes = GermanQwertzLayout.char_to_keys('é').unwrap();
+
+        assert!(strokes.is_dead_key_sequence());
+        assert_eq!(
+            strokes.as_slice(),
+            [
+                stroke(ScanType::SymbolEquals, KeyLevel::Base),
+                stroke(ScanType::CharE, KeyLevel::Base)
+            ]
+        );
+
+        // The circumflex is typed on its own by following the dead key with space
+        assert_eq!(
+            GermanQwertzLayout.char_to_keys('^').unwrap().as_slice(),
+            [
+                stroke(ScanType::SymbolBacktick, KeyLevel::Base),
+                stroke(ScanType::Space, KeyLevel::Base)
+            ]
+        );
+
+        // Dead keys on the AltGr level are found as well
+        assert_eq!(
+            FrenchAzertyLayout.char_to_keys('ñ').unwrap().as_slice(),
+            [
+                stroke(ScanType::Num2, KeyLevel::AltGr),
+                stroke(ScanType::CharN, KeyLevel::Base)
+            ]
+        );
+    }
+}
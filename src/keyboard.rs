@@ -1,4 +1,18 @@
+use super::alt_numpad::{hex_letter_digit, keypad_digit, AltNumpad};
+use super::dead_key::DeadKey;
+use super::event::{EventQueue, KeyEvent, SecureAttention};
+use super::hotkey::{Hotkey, HotkeyTable};
+use super::key_filter::KeyFilter;
+use super::keypad::{keypad_char, navigation_key, num_lock_active};
+use super::layers::Layers;
 use super::layout::{KeyModifierState, Layout};
+use super::mouse::MouseEvent;
+use super::mouse_keys::MouseKeys;
+use super::remap::{KeyRemap, PressedKeys};
+use super::scan_code_set::{KeySet, ScanTypeSet};
+use super::sequence::{SequenceMatch, SequenceMatcher};
+use super::sticky_keys::{is_modifier, StickyKeys};
+use super::tap_hold::TapHold;
 use super::{Key, KeyState, Reader, ReaderMode, ScanType};
 
 pub struct Keyboard<T>
@@ -7,8 +21,33 @@ where
 {
     reader: Reader,
     modifiers: KeyModifierState,
+    // The modifiers according to the physical keys, before any remapping
+    physical_modifiers: KeyModifierState,
     layout: T,
     translation_state: Option<KeyState>,
+    hotkeys: HotkeyTable,
+    // Keys whose press was swallowed, so their release is swallowed too
+    consumed: KeySet,
+    events: EventQueue,
+    sysrq_enabled: bool,
+    sysrq_down: bool,
+    layers: Layers,
+    remap: KeyRemap,
+    pressed: PressedKeys,
+    secure_consumed: KeySet,
+    // Keypad keys pressed while NumLock was off, released as the navigation key they stood for
+    keypad_navigation: ScanTypeSet,
+    alt_numpad_enabled: bool,
+    alt_numpad: Option<AltNumpad>,
+    sticky_keys: Option<StickyKeys>,
+    filter: KeyFilter,
+    mouse_keys: Option<MouseKeys>,
+    tap_hold: TapHold,
+    sequences: SequenceMatcher,
+    now: u64,
+    dead_key: Option<DeadKey>,
+    alt_gr_emulation: bool,
+    group_switch: Option<Hotkey>,
 }
 
 impl<T: Layout> Keyboard<T> {
@@ -16,8 +55,30 @@ impl<T: Layout> Keyboard<T> {
         return Self {
             reader: Reader::new(mode),
             modifiers: KeyModifierState::new(),
+            physical_modifiers: KeyModifierState::new(),
             layout,
             translation_state: None,
+            hotkeys: HotkeyTable::new(),
+            consumed: KeySet::new(),
+            events: EventQueue::new(),
+            sysrq_enabled: false,
+            sysrq_down: false,
+            layers: Layers::new(),
+            remap: KeyRemap::identity(),
+            pressed: PressedKeys::new(),
+            secure_consumed: KeySet::new(),
+            keypad_navigation: ScanTypeSet::new(),
+            alt_numpad_enabled: false,
+            alt_numpad: None,
+            sticky_keys: None,
+            filter: KeyFilter::new(),
+            mouse_keys: None,
+            tap_hold: TapHold::new(),
+            sequences: SequenceMatcher::new(),
+            now: 0,
+            dead_key: None,
+            alt_gr_emulation: true,
+            group_switch: None,
         };
     }
 
@@ -30,25 +91,543 @@ impl<T: Layout> Keyboard<T> {
         return self.modifiers;
     }
 
+    // The modifier state of the physical keys, ignoring any remapping
+    pub fn physical_state(&self) -> KeyModifierState {
+        return self.physical_modifiers;
+    }
+
+    // Replaces the remap table, keys that are currently held still release as they were pressed
+    pub fn set_remap(&mut self, remap: KeyRemap) {
+        self.remap = remap;
+    }
+
+    pub fn remap(&self) -> &KeyRemap {
+        return &self.remap;
+    }
+
+    // The layer stack is applied before the remap table. Use layers_mut to change layers while
+    // keys are held, a new stack doesn't know what they were pressed as.
+    pub fn set_layers(&mut self, layers: Layers) {
+        self.layers = layers;
+    }
+
+    pub fn layers(&self) -> &Layers {
+        return &self.layers;
+    }
+
+    pub fn layers_mut(&mut self) -> &mut Layers {
+        return &mut self.layers;
+    }
+
+    pub fn set_hotkeys(&mut self, hotkeys: HotkeyTable) {
+        self.hotkeys = hotkeys;
+    }
+
+    pub fn hotkeys(&self) -> &HotkeyTable {
+        return &self.hotkeys;
+    }
+
+    pub fn hotkeys_mut(&mut self) -> &mut HotkeyTable {
+        return &mut self.hotkeys;
+    }
+
+    // The dead key waiting for the next character
+    pub fn pending_dead_key(&self) -> Option<DeadKey> {
+        return self.dead_key;
+    }
+
+    pub fn clear_dead_key(&mut self) {
+        self.dead_key = None;
+    }
+
+    // Whether Ctrl+LeftAlt acts as AltGr on layouts that have one, as it does on Windows
+    pub fn set_alt_gr_emulation(&mut self, enabled: bool) {
+        self.alt_gr_emulation = enabled;
+        self.update_alt_gr();
+    }
+
+    // The hotkey that cycles through the layout's groups, e.g. "Alt+Shift". It fires when its
+    // key is pressed while the modifiers are held and the key is still reported as usual.
+    pub fn set_group_switch(&mut self, hotkey: Option<Hotkey>) {
+        self.group_switch = hotkey;
+    }
+
+    pub fn group(&self) -> u8 {
+        return self.modifiers.group;
+    }
+
+    pub fn set_group(&mut self, group: u8) {
+        if group < self.layout.group_count() {
+            self.modifiers.group = group;
+            self.dead_key = None;
+            self.update_alt_gr();
+        }
+    }
+
+    // Whether Alt+SysRq+key is reported as a secure attention event
+    pub fn set_sysrq_enabled(&mut self, enabled: bool) {
+        self.sysrq_enabled = enabled;
+    }
+
+    // Whether holding Alt and typing a number on the keypad enters a character when Alt is
+    // released: Alt+65 is code page 437, Alt+0233 decimal Unicode and Alt, keypad '+', 20AC hex
+    // Unicode. The digits are swallowed while they are typed.
+    pub fn set_alt_numpad(&mut self, enabled: bool) {
+        self.alt_numpad_enabled = enabled;
+        self.alt_numpad = None;
+    }
+
+    // Sticky Keys for users who can't hold chords, see current_state() for what is latched or
+    // locked. Turning it off drops every latch and lock.
+    pub fn set_sticky_keys(&mut self, enabled: bool) {
+        if let Some(sticky) = self.sticky_keys.take() {
+            sticky.clear(&mut self.modifiers);
+        }
+
+        if enabled {
+            self.sticky_keys = Some(StickyKeys::new(&self.modifiers));
+        }
+
+        self.update_alt_gr();
+    }
+
+    // Slow Keys: a key only counts once it has been held for the delay in milliseconds. Needs
+    // the timestamps from input_byte_at or tick, a key is accepted by the first one after the
+    // delay has passed.
+    pub fn set_slow_keys(&mut self, delay: Option<u32>) {
+        self.filter.set_slow_keys(delay);
+    }
+
+    // Bounce Keys: a press of the key released less than the delay in milliseconds ago is ignored
+    pub fn set_bounce_keys(&mut self, delay: Option<u32>) {
+        self.filter.set_bounce_keys(delay);
+    }
+
+    // Like input_byte with the time in milliseconds from a monotonic clock, for the Slow Keys and
+    // Bounce Keys filters
+    pub fn input_byte_at(&mut self, byte: u8, now: u64) -> Option<char> {
+        self.now = now;
+
+        return self.input_byte(byte);
+    }
+
+    pub fn input_event_at(&mut self, byte: u8, now: u64) -> Option<KeyEvent> {
+        self.now = now;
+
+        return self.input_event(byte);
+    }
+
+    // Lets time pass without input so a key held for Slow Keys can be accepted and Mouse Keys
+    // can move the pointer. Their events are queued for next_event and next_mouse_event.
+    pub fn tick(&mut self, now: u64) {
+        self.now = now;
+
+        if let Some(mouse_keys) = self.mouse_keys.as_mut() {
+            mouse_keys.tick(now);
+        }
+
+        if let Some(key) = self.filter.tick(now) {
+            self.process_timed_key(key);
+        }
+
+        self.tap_hold.tick(now);
+        self.process_tap_hold_output();
+
+        if let Some(result) = self.sequences.tick(now) {
+            self.process_sequence_result(result);
+        }
+    }
+
+    // Dual-role keys are decided straight after the reader and the secure attention check,
+    // before the keyboard sees any modifier. Their terms need the timestamps from input_byte_at
+    // or tick.
+    pub fn set_tap_hold(&mut self, tap_hold: TapHold) {
+        self.tap_hold = tap_hold;
+    }
+
+    pub fn tap_hold(&self) -> &TapHold {
+        return &self.tap_hold;
+    }
+
+    pub fn tap_hold_mut(&mut self) -> &mut TapHold {
+        return &mut self.tap_hold;
+    }
+
+    // Key sequences are matched after remapping and Mouse Keys, ahead of hotkeys. A completed
+    // sequence is queued as KeyEvent::Sequence, the keys of one that broke off are replayed.
+    pub fn set_sequences(&mut self, sequences: SequenceMatcher) {
+        self.sequences = sequences;
+    }
+
+    pub fn sequences(&self) -> &SequenceMatcher {
+        return &self.sequences;
+    }
+
+    pub fn sequences_mut(&mut self) -> &mut SequenceMatcher {
+        return &mut self.sequences;
+    }
+
+    // Mouse Keys takes the keypad keys it uses before anything else sees them, its pointer
+    // events are read with next_mouse_event.
+    pub fn set_mouse_keys(&mut self, mouse_keys: Option<MouseKeys>) {
+        self.mouse_keys = mouse_keys;
+    }
+
+    pub fn mouse_keys_mut(&mut self) -> Option<&mut MouseKeys> {
+        return self.mouse_keys.as_mut();
+    }
+
+    pub fn next_mouse_event(&mut self) -> Option<MouseEvent> {
+        return self.mouse_keys.as_mut()?.next_event();
+    }
+
+    // Processes the byte and returns the character it typed. The key and character events of the
+    // byte are used up here, only hotkeys, sequences and secure attention stay queued for
+    // next_event. A dead key that doesn't combine types two characters, only the first is
+    // returned, input_event and next_event give both.
     pub fn input_byte(&mut self, byte: u8) -> Option<char> {
-        let k = self.raw_input_byte(byte)?;
+        let pushed = self.events.pushed();
+        self.process_byte(byte);
 
-        match self.translation_state {
-            Some(KeyState::Pressed) => {
-                if k.is_pressed() {
-                    return self.layout.key_into_char(&self.modifiers, k);
-                } else {
-                    return None;
+        let count = self.events.len();
+        let added = self.events.pushed().wrapping_sub(pushed).min(count);
+        let mut ch = None;
+
+        // Goes round the queue once so the events queued before this byte keep their place
+        for i in 0..count {
+            let event = match self.events.pop() {
+                Some(event) => event,
+                None => break,
+            };
+
+            match event {
+                KeyEvent::Key { .. } | KeyEvent::Char(_) if i >= count - added => {
+                    if ch.is_none() {
+                        ch = event.char();
+                    }
+                }
+                _ => self.events.push(event),
+            }
+        }
+
+        return ch;
+    }
+
+    // Processes the byte and returns the first pending event
+    pub fn input_event(&mut self, byte: u8) -> Option<KeyEvent> {
+        self.process_byte(byte);
+
+        return self.events.pop();
+    }
+
+    // A single byte can produce several events, this returns the ones still pending
+    pub fn next_event(&mut self) -> Option<KeyEvent> {
+        return self.events.pop();
+    }
+
+    // Processes a key as if the reader had just decoded it, e.g. when replaying a macro. The key
+    // isn't physical, it skips the Slow Keys and Bounce Keys filters and can never be part of a
+    // secure attention sequence.
+    pub fn input_key(&mut self, key: Key) -> Option<KeyEvent> {
+        self.process_tap_hold_key(key);
+
+        return self.events.pop();
+    }
+
+    fn process_byte(&mut self, byte: u8) {
+        if let Ok(Some(key)) = self.reader.input_scan_code(byte) {
+            self.process_decoded_key(key);
+        }
+    }
+
+    fn process_decoded_key(&mut self, key: Key) {
+        if !self.filter.is_active() {
+            self.process_timed_key(key);
+            return;
+        }
+
+        for key in self.filter.input(key, self.now).iter() {
+            self.process_timed_key(key);
+        }
+    }
+
+    // Secure attention is checked on the keys as they were typed, before tap-hold turns them
+    // into other keys
+    fn process_timed_key(&mut self, key: Key) {
+        self.physical_modifiers.apply_key(&key);
+
+        if let Some(sequence) = self.check_secure_attention(&key) {
+            self.secure_consumed.insert(&key);
+            self.events.push(KeyEvent::SecureAttention(sequence));
+            return;
+        } else if !key.is_pressed() && self.secure_consumed.remove(&key) {
+            return;
+        }
+
+        self.process_tap_hold_key(key);
+    }
+
+    fn process_tap_hold_key(&mut self, key: Key) {
+        if self.tap_hold.is_idle() {
+            self.process_physical_key(key);
+            return;
+        }
+
+        self.tap_hold.input(key, self.now);
+        self.process_tap_hold_output();
+    }
+
+    fn process_tap_hold_output(&mut self) {
+        while let Some(key) = self.tap_hold.next_key() {
+            self.process_physical_key(key);
+        }
+    }
+
+    fn process_physical_key(&mut self, physical: Key) {
+        if let Some(key) = self.remap_key(physical) {
+            self.process_key(key);
+
+            if let Some(sticky) = self.sticky_keys {
+                if key.is_pressed() && !is_modifier(key.scan_type()) {
+                    sticky.release_latched(&mut self.modifiers);
+                    self.update_alt_gr();
+                }
+            }
+        }
+    }
+
+    fn process_key(&mut self, key: Key) {
+        if let Some(mouse_keys) = self.mouse_keys.as_mut() {
+            if mouse_keys.input(key, &self.modifiers, self.now) {
+                return;
+            }
+        }
+
+        if self.alt_numpad_enabled && self.alt_numpad_key(key) {
+            self.consumed.insert(&key);
+            return;
+        }
+
+        let key = self.keypad_navigation(key);
+
+        if let Some(hotkey) = self.group_switch {
+            if hotkey.matches(&key, &self.modifiers) {
+                self.set_group((self.modifiers.group + 1) % self.layout.group_count());
+            }
+        }
+
+        match self.sequences.input(key, &self.modifiers, self.now) {
+            SequenceMatch::Unmatched => self.process_unsequenced_key(key),
+            result => self.process_sequence_result(result),
+        }
+    }
+
+    // Replayed keys are translated and matched against the hotkeys with the modifiers they were
+    // typed with, the modifiers may have changed while they were held back
+    fn process_sequence_result(&mut self, result: SequenceMatch) {
+        if let SequenceMatch::Matched(id) = result {
+            self.events.push(KeyEvent::Sequence(id));
+        }
+
+        while let Some((key, modifiers)) = self.sequences.next_replay() {
+            let current = self.modifiers;
+            self.modifiers = modifiers;
+            self.process_unsequenced_key(key);
+            self.modifiers = current;
+        }
+    }
+
+    fn process_unsequenced_key(&mut self, key: Key) {
+        if key.is_pressed() {
+            if let Some(binding) = self.hotkeys.find(&key, &self.modifiers) {
+                self.events.push(KeyEvent::Hotkey {
+                    id: binding.id,
+                    key,
+                    consumed: binding.consume,
+                });
+
+                if binding.consume {
+                    self.consumed.insert(&key);
+                    return;
                 }
             }
-            Some(KeyState::Released) => {
-                if k.is_pressed() {
-                    return None;
-                } else {
-                    return self.layout.key_into_char(&self.modifiers, k);
+        } else if self.consumed.remove(&key) {
+            return;
+        }
+
+        let ch = if key.is_pressed() {
+            self.translate_pressed(key)
+        } else if self.layout.dead_key(&self.modifiers, key).is_some() {
+            // Dead keys don't produce a character on release either
+            None
+        } else {
+            self.translate(key)
+        };
+
+        self.events.push(KeyEvent::Key {
+            key,
+            modifiers: self.modifiers,
+            ch,
+        });
+    }
+
+    // Collects the digits of an Alt+numpad character, true when the key was one of them. The
+    // character is queued just before the Alt release that completes it.
+    fn alt_numpad_key(&mut self, key: Key) -> bool {
+        let modifiers = &self.modifiers;
+
+        if !modifiers.alt_down() || modifiers.alt_gr || modifiers.ctrl_down() {
+            if let Some(ch) = self.alt_numpad.take().and_then(|entry| entry.finish()) {
+                self.events.push(KeyEvent::Char(ch));
+            }
+
+            return false;
+        }
+
+        if !key.is_pressed() {
+            return false;
+        }
+
+        let entry = self.alt_numpad.get_or_insert(AltNumpad::new());
+
+        let digit = if key.keypad() {
+            if key.scan_type() == ScanType::SymbolPlus {
+                return entry.start_hex();
+            }
+
+            keypad_digit(key.scan_type())
+        } else if entry.is_hex() {
+            hex_letter_digit(key.scan_type())
+        } else {
+            None
+        };
+
+        return match digit {
+            Some(digit) => {
+                entry.push_digit(digit);
+                true
+            }
+            None => false,
+        };
+    }
+
+    // With NumLock off the keypad digits and period become the navigation keys printed below them
+    fn keypad_navigation(&mut self, key: Key) -> Key {
+        if !key.keypad() {
+            return key;
+        }
+
+        let navigation = match navigation_key(key.scan_type()) {
+            Some(navigation) => navigation,
+            None => return key,
+        };
+
+        if key.is_pressed() {
+            if num_lock_active(&self.modifiers) {
+                return key;
+            }
+
+            self.keypad_navigation.insert(key.scan_type());
+        } else if !self.keypad_navigation.remove(key.scan_type()) {
+            return key;
+        }
+
+        return Key::new(navigation, key.state());
+    }
+
+    // Translates a pressed key, handling dead keys
+    fn translate_pressed(&mut self, key: Key) -> Option<char> {
+        if key.scan_type() == ScanType::Escape {
+            self.dead_key = None;
+            return self.translate(key);
+        }
+
+        if let Some(dead_key) = self.layout.dead_key(&self.modifiers, key) {
+            match self.dead_key.take() {
+                // Pressing the same dead key twice produces it on its own
+                Some(pending) if pending == dead_key => return Some(dead_key.spacing_char()),
+                Some(pending) => {
+                    self.events.push(KeyEvent::Char(pending.spacing_char()));
+                    self.dead_key = Some(dead_key);
                 }
+                None => self.dead_key = Some(dead_key),
             }
-            None => return self.layout.key_into_char(&self.modifiers, k),
+
+            return None;
+        }
+
+        let ch = self.translate(key);
+
+        let (pending, c) = match (self.dead_key, ch) {
+            (Some(pending), Some(c)) => (pending, c),
+            // Keys without a character (modifiers, arrows) leave the dead key pending
+            _ => return ch,
+        };
+
+        self.dead_key = None;
+
+        if c == ' ' {
+            return Some(pending.spacing_char());
+        }
+
+        if let Some(composed) = self.layout.compose(&self.modifiers, pending, c) {
+            return Some(composed);
+        }
+
+        // They don't combine so both characters are produced
+        self.events.push(KeyEvent::Char(pending.spacing_char()));
+
+        return Some(c);
+    }
+
+    // This only looks at the decoded key and the physical modifier state so remapping,
+    // hotkeys and layouts can neither fake nor intercept the sequence.
+    fn check_secure_attention(&mut self, key: &Key) -> Option<SecureAttention> {
+        let modifiers = &self.physical_modifiers;
+
+        if key.scan_type() == ScanType::PrintScreen {
+            self.sysrq_down = key.is_pressed();
+            return None;
+        }
+
+        if !key.is_pressed() || !modifiers.alt_down() {
+            return None;
+        }
+
+        // The modifiers held with SysRq aren't its command key
+        if self.sysrq_enabled && self.sysrq_down && !is_modifier(key.scan_type()) {
+            return Some(SecureAttention::SysRq(key.scan_type()));
+        }
+
+        let delete = if key.keypad() {
+            // The keypad period is delete when NumLock is off
+            key.scan_type() == ScanType::SymbolPeriod && !modifiers.num_lock
+        } else {
+            key.scan_type() == ScanType::Delete
+        };
+
+        if delete && modifiers.ctrl_down() {
+            return Some(SecureAttention::CtrlAltDelete);
+        }
+
+        return None;
+    }
+
+    // Applies the remap table, modifiers follow the remapped key
+    fn remap_key(&mut self, physical: Key) -> Option<Key> {
+        let key = self.layers.map(physical)?;
+        let key = self.pressed.map(&self.remap, key)?;
+        self.check_apply_modifiers(&key);
+
+        return Some(key);
+    }
+
+    fn translate(&self, key: Key) -> Option<char> {
+        match self.translation_state {
+            Some(KeyState::Pressed) if !key.is_pressed() => return None,
+            Some(KeyState::Released) if key.is_pressed() => return None,
+            _ if key.keypad() => return keypad_char(&self.layout, &self.modifiers, key),
+            _ => return self.layout.key_into_char(&self.modifiers, key),
         }
     }
 
@@ -59,60 +638,115 @@ impl<T: Layout> Keyboard<T> {
         };
     }
 
+    // Decodes the byte and applies remapping and modifiers, skipping hotkeys and layouts
     pub fn try_raw_input_byte(&mut self, byte: u8) -> Result<Option<Key>, &str> {
         let res = self.reader.input_scan_code(byte);
 
         match res {
             Ok(Some(k)) => {
-                self.check_apply_modifiers(&k);
+                self.physical_modifiers.apply_key(&k);
 
-                return Ok(Some(k));
+                return Ok(self.remap_key(k));
             }
             _ => return res,
         }
     }
 
     fn check_apply_modifiers(&mut self, key: &Key) {
-        match key.scan_type() {
-            ScanType::LeftGUI => self.modifiers.left_gui = key.is_pressed(),
-            ScanType::RightGUI => self.modifiers.right_gui = key.is_pressed(),
-            ScanType::LeftAlt => self.modifiers.left_alt = key.is_pressed(),
-            ScanType::RightAlt => self.modifiers.right_alt = key.is_pressed(),
-            ScanType::LeftShift => self.modifiers.left_shift = key.is_pressed(),
-            ScanType::RightShift => self.modifiers.right_shift = key.is_pressed(),
-            ScanType::LeftCtrl => self.modifiers.left_ctrl = key.is_pressed(),
-            ScanType::RightCtrl => self.modifiers.right_ctrl = key.is_pressed(),
-            ScanType::NumLock => {
-                // Toggle only when pressed
-                if key.is_pressed() {
-                    self.modifiers.num_lock = !self.modifiers.num_lock;
-                }
-            }
-            ScanType::CapsLock => {
-                // Toggle only when pressed
-                if key.is_pressed() {
-                    self.modifiers.caps_lock = !self.modifiers.caps_lock;
-                }
-            }
-            ScanType::ScrollLock => {
-                // Toggle only when pressed
-                if key.is_pressed() {
-                    self.modifiers.scroll_lock = !self.modifiers.scroll_lock;
-                }
-            }
-            _ => (),
+        self.modifiers.apply_key(key);
+
+        if let Some(sticky) = self.sticky_keys.as_mut() {
+            sticky.apply_key(&mut self.modifiers, key);
         }
+
+        self.update_alt_gr();
+    }
+
+    fn update_alt_gr(&mut self) {
+        let modifiers = &self.modifiers;
+        let emulated = self.alt_gr_emulation && modifiers.left_alt && modifiers.ctrl_down();
+
+        self.modifiers.alt_gr =
+            self.layout.group_has_alt_gr(modifiers.group) && (modifiers.right_alt || emulated);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::layout::USStandardLayout;
+    use super::super::hotkey::Hotkey;
+    use super::super::layers::Layer;
+    use super::super::layout::{KeyLevel, LayoutPair, USStandardLayout};
+    use super::super::layouts::{GermanQwertzLayout, RussianLayout};
+    use super::super::macro_recorder::{MacroPlayer, MacroStep};
+    use super::super::mouse::MouseButtons;
+    use super::super::mouse_keys::MouseKeysConfig;
+    use super::super::tap_hold::DualRole;
     use super::*;
 
+    // The US layout with dead keys on backtick (grave) and shift + 6 (circumflex)
+    struct DeadKeyLayout;
+
+    impl Layout for DeadKeyLayout {
+        fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+            return USStandardLayout.key_into_char(modifiers, key);
+        }
+
+        fn dead_key(&self, modifiers: &KeyModifierState, key: Key) -> Option<DeadKey> {
+            return match (key.scan_type(), modifiers.shift_down()) {
+                (ScanType::SymbolBacktick, false) => Some(DeadKey::Grave),
+                (ScanType::Num6, true) => Some(DeadKey::Circumflex),
+                _ => None,
+            };
+        }
+    }
+
+    // The dead keys of DeadKeyLayout without any compositions
+    struct NoComposeLayout;
+
+    impl Layout for NoComposeLayout {
+        fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+            return USStandardLayout.key_into_char(modifiers, key);
+        }
+
+        fn dead_key(&self, modifiers: &KeyModifierState, key: Key) -> Option<DeadKey> {
+            return DeadKeyLayout.dead_key(modifiers, key);
+        }
+
+        fn compose(&self, _: &KeyModifierState, _: DeadKey, _: char) -> Option<char> {
+            return None;
+        }
+    }
+
+    // The US layout with a few characters on AltGr
+    struct AltGrLayout;
+
+    impl Layout for AltGrLayout {
+        fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+            return match (key.scan_type(), modifiers.level()) {
+                (ScanType::CharE, KeyLevel::AltGr) => Some('€'),
+                (ScanType::CharQ, KeyLevel::AltGr) => Some('@'),
+                (ScanType::Num7, KeyLevel::AltGr) => Some('{'),
+                (ScanType::Num7, KeyLevel::ShiftAltGr) => Some('⅞'),
+                (_, level) if level.is_alt_gr() => None,
+                _ => USStandardLayout.key_into_char(modifiers, key),
+            };
+        }
+
+        fn has_alt_gr(&self) -> bool {
+            return true;
+        }
+    }
+
     mod set1 {
         use super::*;
 
+        fn feed<T: Layout>(key_board: &mut Keyboard<T>, bytes: &[u8]) {
+            for b in bytes {
+                key_board.input_byte(*b);
+                while key_board.next_event().is_some() {}
+            }
+        }
+
         #[test]
         fn test_single_character() {
             let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
@@ -151,5 +785,908 @@ mod tests {
             assert!(key_board.input_byte(0xba).is_none()); // CapsLock released
             assert_eq!(key_board.input_byte(0x1e).unwrap(), 'a');
         }
+
+        #[test]
+        fn test_input_event() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+
+            assert_eq!(
+                key_board.input_event(0x1e),
+                Some(KeyEvent::Key {
+                    key: Key::new(ScanType::CharA, KeyState::Pressed),
+                    modifiers: KeyModifierState::new(),
+                    ch: Some('a'),
+                })
+            );
+            assert!(key_board.next_event().is_none());
+        }
+
+        #[test]
+        fn test_consumed_hotkey() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board
+                .hotkeys_mut()
+                .register(7, Hotkey::parse("Ctrl+Q").unwrap(), true)
+                .unwrap();
+
+            assert!(key_board.input_byte(0x1d).is_none()); // Left ctrl
+
+            assert_eq!(
+                key_board.input_event(0x10), // Q pressed
+                Some(KeyEvent::Hotkey {
+                    id: 7,
+                    key: Key::new(ScanType::CharQ, KeyState::Pressed),
+                    consumed: true,
+                })
+            );
+            assert!(key_board.next_event().is_none());
+
+            // The release is swallowed with the press
+            assert!(key_board.input_event(0x90).is_none());
+        }
+
+        #[test]
+        fn test_unconsumed_hotkey() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board
+                .hotkeys_mut()
+                .register(3, Hotkey::parse("Shift+F1").unwrap(), false)
+                .unwrap();
+
+            assert!(key_board.input_byte(0x2a).is_none()); // Left shift
+
+            assert!(matches!(
+                key_board.input_event(0x3b),
+                Some(KeyEvent::Hotkey {
+                    id: 3,
+                    consumed: false,
+                    ..
+                })
+            ));
+            assert_eq!(
+                key_board.next_event().unwrap().key(),
+                Some(Key::new(ScanType::F1, KeyState::Pressed))
+            );
+        }
+
+        #[test]
+        fn test_ctrl_alt_delete() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            feed(&mut key_board, &[0xe0, 0x1d, 0x38]); // Right ctrl, left alt
+
+            assert!(key_board.input_event(0xe0).is_none());
+            assert_eq!(
+                key_board.input_event(0x53),
+                Some(KeyEvent::SecureAttention(SecureAttention::CtrlAltDelete))
+            );
+            assert!(key_board.next_event().is_none());
+
+            // The release is swallowed as well
+            assert!(key_board.input_event(0xe0).is_none());
+            assert!(key_board.input_event(0xd3).is_none());
+        }
+
+        #[test]
+        fn test_ctrl_alt_keypad_delete() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            feed(&mut key_board, &[0x1d, 0xe0, 0x38]); // Left ctrl, right alt
+
+            assert_eq!(
+                key_board.input_event(0x53),
+                Some(KeyEvent::SecureAttention(SecureAttention::CtrlAltDelete))
+            );
+            feed(&mut key_board, &[0xd3, 0x45, 0xc5]); // NumLock on
+
+            assert!(matches!(
+                key_board.input_event(0x53),
+                Some(KeyEvent::Key { .. })
+            ));
+        }
+
+        #[test]
+        fn test_keypad_num_lock() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+
+            assert_eq!(
+                key_board.input_event(0x47),
+                Some(KeyEvent::Key {
+                    key: Key::new(ScanType::Home, KeyState::Pressed),
+                    modifiers: KeyModifierState::new(),
+                    ch: None,
+                })
+            );
+            assert_eq!(
+                key_board.input_event(0xc7).unwrap().key(),
+                Some(Key::new(ScanType::Home, KeyState::Released))
+            );
+            assert_eq!(key_board.input_byte(0x4c), None);
+
+            feed(&mut key_board, &[0x45, 0xc5]); // NumLock on
+            assert_eq!(key_board.input_byte(0x47), Some('7'));
+            assert_eq!(
+                key_board.input_event(0xc7).unwrap().key(),
+                Some(Key::new_keypad(ScanType::Num7, KeyState::Released))
+            );
+            assert_eq!(key_board.input_byte(0x4c), Some('5'));
+        }
+
+        #[test]
+        fn test_keypad_shift_inverts_num_lock() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+
+            key_board.input_byte(0x2a);
+            assert_eq!(key_board.input_byte(0x4f), Some('1'));
+            key_board.input_byte(0xcf);
+
+            feed(&mut key_board, &[0xaa, 0x45, 0xc5]);
+            assert_eq!(key_board.input_byte(0x53), Some('.'));
+
+            // The release follows the press even after Shift changed in between
+            key_board.input_byte(0x2a);
+            assert_eq!(
+                key_board.input_event(0x53).unwrap().key(),
+                Some(Key::new(ScanType::Delete, KeyState::Pressed))
+            );
+            key_board.input_byte(0xaa);
+            assert_eq!(
+                key_board.input_event(0xd3).unwrap().key(),
+                Some(Key::new(ScanType::Delete, KeyState::Released))
+            );
+        }
+
+        #[test]
+        fn test_keypad_operators() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, GermanQwertzLayout);
+            key_board.set_translation_state(Some(KeyState::Pressed));
+
+            feed(&mut key_board, &[0xe0]);
+            assert_eq!(key_board.input_byte(0x1c), Some('\n'));
+            feed(&mut key_board, &[0xe0]);
+            assert_eq!(key_board.input_byte(0x35), Some('/'));
+
+            key_board.input_byte(0x2a);
+            assert_eq!(key_board.input_byte(0x37), Some('*'));
+            assert_eq!(key_board.input_byte(0x4a), Some('-'));
+            assert_eq!(key_board.input_byte(0x4e), Some('+'));
+            feed(&mut key_board, &[0xe0]);
+            assert_eq!(key_board.input_byte(0x35), Some('/'));
+
+            // The main Enter key is left to the layout
+            assert_eq!(key_board.input_byte(0x1c), None);
+        }
+
+        #[test]
+        fn test_alt_numpad() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+
+            // Off by default, the keypad acts as usual
+            feed(&mut key_board, &[0x38, 0x4d, 0xcd, 0x4c, 0xcc]);
+            assert_eq!(key_board.input_byte(0xb8), None);
+
+            key_board.set_alt_numpad(true);
+
+            // Alt+65 through code page 437, NumLock doesn't matter
+            feed(&mut key_board, &[0x38, 0x4d, 0xcd]);
+            assert_eq!(key_board.input_event(0x4c), None);
+            assert_eq!(key_board.input_event(0xcc), None);
+            assert_eq!(key_board.input_event(0xb8), Some(KeyEvent::Char('A')));
+            assert_eq!(
+                key_board.next_event().unwrap().key(),
+                Some(Key::new(ScanType::LeftAlt, KeyState::Released))
+            );
+
+            // Alt+0233 as decimal Unicode
+            feed(
+                &mut key_board,
+                &[0x45, 0xc5, 0x38, 0x52, 0xd2, 0x50, 0xd0, 0x51, 0xd1],
+            );
+            feed(&mut key_board, &[0x51, 0xd1]);
+            assert_eq!(key_board.input_byte(0xb8), Some('é'));
+
+            // Alt, keypad '+', 20ac as hex Unicode
+            feed(&mut key_board, &[0x38, 0x4e, 0xce, 0x50, 0xd0, 0x52, 0xd2]);
+            feed(&mut key_board, &[0x1e, 0x9e, 0x2e, 0xae]);
+            assert_eq!(key_board.input_byte(0xb8), Some('€'));
+
+            // Letters only count as digits in hex entry
+            feed(&mut key_board, &[0x38, 0x4f]);
+            assert!(matches!(
+                key_board.input_event(0x1e),
+                Some(KeyEvent::Key { ch: Some('a'), .. })
+            ));
+            feed(&mut key_board, &[0x9e, 0xcf]);
+            assert_eq!(key_board.input_byte(0xb8), Some('☺'));
+
+            // Nothing is entered when Alt is released without digits
+            feed(&mut key_board, &[0x38]);
+            assert!(matches!(
+                key_board.input_event(0xb8),
+                Some(KeyEvent::Key { ch: None, .. })
+            ));
+        }
+
+        #[test]
+        fn test_sticky_keys() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_sticky_keys(true);
+            key_board
+                .hotkeys_mut()
+                .register(1, Hotkey::parse("Ctrl+C").unwrap(), true)
+                .unwrap();
+
+            // A tap latches Shift for one key
+            feed(&mut key_board, &[0x2a, 0xaa]);
+            assert!(key_board.current_state().latched.shift());
+            assert_eq!(key_board.input_byte(0x1e), Some('A'));
+            assert!(key_board.current_state().latched.is_empty());
+            key_board.input_byte(0x9e);
+            assert_eq!(key_board.input_byte(0x1e), Some('a'));
+            key_board.input_byte(0x9e);
+
+            // Latched modifiers reach hotkeys too
+            feed(&mut key_board, &[0x1d, 0x9d]);
+            assert!(matches!(
+                key_board.input_event(0x2e),
+                Some(KeyEvent::Hotkey { id: 1, .. })
+            ));
+            key_board.input_byte(0xae);
+
+            // Two taps lock it until the third
+            feed(&mut key_board, &[0x36, 0xb6, 0x36, 0xb6]);
+            assert!(key_board
+                .current_state()
+                .locked
+                .contains(ScanType::RightShift));
+            assert_eq!(key_board.input_byte(0x1e), Some('A'));
+            key_board.input_byte(0x9e);
+            assert_eq!(key_board.input_byte(0x1e), Some('A'));
+            key_board.input_byte(0x9e);
+            feed(&mut key_board, &[0x36, 0xb6]);
+            assert!(key_board.current_state().locked.is_empty());
+            assert_eq!(key_board.input_byte(0x1e), Some('a'));
+            key_board.input_byte(0x9e);
+
+            // Held through a chord the modifier isn't latched
+            feed(&mut key_board, &[0x2a]);
+            assert_eq!(key_board.input_byte(0x1e), Some('A'));
+            feed(&mut key_board, &[0x9e, 0xaa]);
+            assert!(key_board.current_state().latched.is_empty());
+            assert_eq!(key_board.input_byte(0x1e), Some('a'));
+            key_board.input_byte(0x9e);
+
+            // Turning it off drops the lock
+            feed(&mut key_board, &[0x2a, 0xaa, 0x2a, 0xaa]);
+            key_board.set_sticky_keys(false);
+            assert!(!key_board.current_state().shift_down());
+            assert!(key_board.current_state().locked.is_empty());
+        }
+
+        #[test]
+        fn test_slow_keys() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_slow_keys(Some(250));
+
+            assert_eq!(key_board.input_byte_at(0x1e, 1000), None);
+            assert_eq!(key_board.input_byte_at(0x9e, 1100), None);
+
+            assert_eq!(key_board.input_byte_at(0x1e, 2000), None);
+            key_board.tick(2300);
+            assert!(matches!(
+                key_board.next_event(),
+                Some(KeyEvent::Key { ch: Some('a'), .. })
+            ));
+            assert!(key_board.input_event_at(0x9e, 2400).is_some());
+
+            // Modifiers have to be held as well
+            key_board.set_translation_state(Some(KeyState::Pressed));
+            assert_eq!(key_board.input_byte_at(0x2a, 3000), None);
+            assert_eq!(key_board.input_byte_at(0x2a, 3300), None);
+            assert_eq!(key_board.input_byte_at(0x1e, 3310), None);
+            assert_eq!(key_board.input_byte_at(0x1e, 3600), Some('A'));
+        }
+
+        #[test]
+        fn test_bounce_keys() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_bounce_keys(Some(100));
+            key_board.set_translation_state(Some(KeyState::Pressed));
+
+            assert_eq!(key_board.input_byte_at(0x1e, 0), Some('a'));
+            assert_eq!(key_board.input_byte_at(0x9e, 30), None);
+            assert_eq!(key_board.input_byte_at(0x1e, 60), None);
+            assert_eq!(key_board.input_byte_at(0x9e, 80), None);
+            assert_eq!(key_board.input_byte_at(0x30, 90), Some('b'));
+            assert_eq!(key_board.input_byte_at(0x1e, 200), Some('a'));
+        }
+
+        #[test]
+        fn test_mouse_keys() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            let mut mouse_keys = MouseKeys::new(MouseKeysConfig::new());
+            mouse_keys.set_toggle(Some(Hotkey::parse("LeftAlt+LeftShift+NumLock").unwrap()));
+            key_board.set_mouse_keys(Some(mouse_keys));
+            feed(&mut key_board, &[0x45, 0xc5]); // NumLock on
+
+            assert_eq!(key_board.input_byte(0x48), Some('8'));
+            key_board.input_byte(0xc8);
+            assert_eq!(key_board.next_mouse_event(), None);
+
+            feed(&mut key_board, &[0x38, 0x2a, 0x45, 0xc5, 0xaa, 0xb8]);
+            assert!(key_board.mouse_keys_mut().unwrap().is_active());
+
+            assert_eq!(key_board.input_event_at(0x48, 100), None);
+            assert_eq!(
+                key_board.next_mouse_event(),
+                Some(MouseEvent::new(0, 1, MouseButtons::new()))
+            );
+            key_board.tick(140);
+            assert!(key_board.next_mouse_event().unwrap().dy > 1);
+            assert_eq!(key_board.input_event_at(0xc8, 150), None);
+
+            // Keys off the keypad still type
+            assert_eq!(key_board.input_byte(0x09), Some('8'));
+        }
+
+        #[test]
+        fn test_tap_hold() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board
+                .tap_hold_mut()
+                .register(DualRole::new(
+                    ScanType::CapsLock,
+                    ScanType::Escape,
+                    ScanType::LeftCtrl,
+                ))
+                .unwrap();
+
+            // Tapped it's Escape and Caps Lock never toggles
+            assert_eq!(key_board.input_event_at(0x3a, 0), None);
+            assert_eq!(
+                key_board.input_event_at(0xba, 50).unwrap().key(),
+                Some(Key::new(ScanType::Escape, KeyState::Pressed))
+            );
+            assert_eq!(
+                key_board.next_event().unwrap().key(),
+                Some(Key::new(ScanType::Escape, KeyState::Released))
+            );
+            assert!(!key_board.current_state().caps_lock);
+
+            // Held it's Ctrl for the keys typed meanwhile
+            key_board.input_byte_at(0x3a, 1000);
+            assert_eq!(key_board.input_event_at(0x2e, 1020), None);
+            assert_eq!(
+                key_board.input_event_at(0xae, 1040).unwrap().key(),
+                Some(Key::new(ScanType::LeftCtrl, KeyState::Pressed))
+            );
+            assert!(matches!(
+                key_board.next_event(),
+                Some(KeyEvent::Key { key, modifiers, .. })
+                    if key.scan_type() == ScanType::CharC && modifiers.ctrl_down()
+            ));
+            key_board.input_byte_at(0xba, 1100);
+            assert!(!key_board.current_state().ctrl_down());
+
+            // Or once the term has passed
+            key_board.input_byte_at(0x3a, 2000);
+            key_board.tick(2300);
+            assert!(key_board.current_state().ctrl_down());
+        }
+
+        #[test]
+        fn test_tap_hold_secure_attention() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board
+                .tap_hold_mut()
+                .register(DualRole::new(
+                    ScanType::CapsLock,
+                    ScanType::Delete,
+                    ScanType::LeftCtrl,
+                ))
+                .unwrap();
+
+            // The Ctrl held through Caps Lock isn't a physical Ctrl
+            let mut secure = false;
+            for (byte, now) in [(0x3a, 0), (0x38, 300), (0xe0, 310), (0x53, 310)] {
+                let first = key_board.input_event_at(byte, now);
+                key_board.tick(now);
+                for event in first
+                    .into_iter()
+                    .chain(core::iter::from_fn(|| key_board.next_event()))
+                {
+                    secure |= matches!(event, KeyEvent::SecureAttention(_));
+                }
+            }
+            assert!(!secure);
+
+            for (byte, now) in [(0xe0, 400), (0xd3, 400), (0xb8, 410), (0xba, 420)] {
+                key_board.input_byte_at(byte, now);
+                while key_board.next_event().is_some() {}
+            }
+
+            // A real Ctrl still counts
+            key_board.input_byte_at(0x1d, 1000);
+            key_board.input_byte_at(0x38, 1010);
+            key_board.input_byte_at(0xe0, 1020);
+            assert_eq!(
+                key_board.input_event_at(0x53, 1020),
+                Some(KeyEvent::SecureAttention(SecureAttention::CtrlAltDelete))
+            );
+        }
+
+        #[test]
+        fn test_layers() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_layers(
+                Layers::new()
+                    .with_layer(0, Layer::transparent().momentary(ScanType::CapsLock, 1))
+                    .with_layer(
+                        1,
+                        Layer::transparent().map(ScanType::CharH, ScanType::CursorLeft),
+                    ),
+            );
+
+            assert_eq!(key_board.input_event(0x3a), None);
+            assert!(!key_board.current_state().caps_lock);
+            assert!(key_board.layers().is_active(1));
+            assert_eq!(
+                key_board.input_event(0x23).unwrap().key(),
+                Some(Key::new(ScanType::CursorLeft, KeyState::Pressed))
+            );
+            assert_eq!(key_board.input_event(0xba), None);
+            assert_eq!(
+                key_board.input_event(0xa3).unwrap().key(),
+                Some(Key::new(ScanType::CursorLeft, KeyState::Released))
+            );
+            assert_eq!(key_board.input_byte(0x23), Some('h'));
+        }
+
+        #[test]
+        fn test_replay_macro() {
+            let steps = [
+                MacroStep::new(Key::new(ScanType::LeftShift, KeyState::Pressed), 0),
+                MacroStep::new(Key::new(ScanType::CharA, KeyState::Pressed), 20),
+                MacroStep::new(Key::new(ScanType::CharA, KeyState::Released), 20),
+                MacroStep::new(Key::new(ScanType::LeftShift, KeyState::Released), 20),
+            ];
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_translation_state(Some(KeyState::Pressed));
+            let mut player = MacroPlayer::new(&steps, 0);
+            let mut typed = None;
+
+            for now in 0..100 {
+                while let Some(key) = player.next_key(now) {
+                    if let Some(ch) = key_board.input_key(key).and_then(|event| event.char()) {
+                        typed = Some(ch);
+                    }
+                }
+            }
+
+            assert!(player.is_finished());
+            assert_eq!(typed, Some('A'));
+            assert!(!key_board.current_state().shift_down());
+        }
+
+        #[test]
+        fn test_input_key_never_secure() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            let mut secure = false;
+
+            for scan_type in [ScanType::LeftCtrl, ScanType::LeftAlt, ScanType::Delete] {
+                let first = key_board.input_key(Key::new(scan_type, KeyState::Pressed));
+                for event in first
+                    .into_iter()
+                    .chain(core::iter::from_fn(|| key_board.next_event()))
+                {
+                    secure |= matches!(event, KeyEvent::SecureAttention(_));
+                }
+            }
+            assert!(!secure);
+
+            // Nor do injected modifiers count for a physical Delete
+            key_board.input_byte(0xe0);
+            assert!(!matches!(
+                key_board.input_event(0x53),
+                Some(KeyEvent::SecureAttention(_))
+            ));
+        }
+
+        #[test]
+        fn test_sequences() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_translation_state(Some(KeyState::Pressed));
+            let ctrl_x = Hotkey::parse("Ctrl+X").unwrap();
+            let ctrl_s = Hotkey::parse("Ctrl+S").unwrap();
+            key_board
+                .sequences_mut()
+                .register(1, &[ctrl_x, ctrl_s])
+                .unwrap();
+
+            assert!(key_board.input_event_at(0x1d, 0).is_some());
+            assert_eq!(key_board.input_event_at(0x2d, 10), None);
+            assert_eq!(key_board.input_event_at(0xad, 20), None);
+            assert_eq!(
+                key_board.input_event_at(0x1f, 30),
+                Some(KeyEvent::Sequence(1))
+            );
+            assert_eq!(key_board.input_event_at(0x9f, 40), None);
+            assert!(key_board.input_event_at(0x9d, 50).is_some());
+
+            // A sequence that breaks off replays its keys, here after the timeout
+            assert_eq!(key_board.input_byte_at(0x1d, 110), None);
+            assert_eq!(key_board.input_byte_at(0x2d, 120), None);
+            key_board.input_byte_at(0xad, 130);
+            key_board.input_byte_at(0x9d, 140);
+            key_board.tick(1200);
+            assert_eq!(
+                key_board.next_event().unwrap().key(),
+                Some(Key::new(ScanType::CharX, KeyState::Pressed))
+            );
+            assert_eq!(
+                key_board.next_event().unwrap().key(),
+                Some(Key::new(ScanType::CharX, KeyState::Released))
+            );
+            assert_eq!(key_board.next_event(), None);
+        }
+
+        #[test]
+        fn test_sequence_replay_modifiers() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_translation_state(Some(KeyState::Pressed));
+            let ctrl_x = Hotkey::parse("Ctrl+X").unwrap();
+            let ctrl_s = Hotkey::parse("Ctrl+S").unwrap();
+            key_board
+                .sequences_mut()
+                .register(1, &[ctrl_x, ctrl_s])
+                .unwrap();
+            key_board.hotkeys_mut().register(2, ctrl_x, true).unwrap();
+
+            // Ctrl is let go before the key that breaks the sequence off
+            feed(&mut key_board, &[0x1d, 0x2d, 0xad, 0x9d]);
+            assert_eq!(
+                key_board.input_event(0x2e),
+                Some(KeyEvent::Hotkey {
+                    id: 2,
+                    key: Key::new(ScanType::CharX, KeyState::Pressed),
+                    consumed: true
+                })
+            );
+            assert!(matches!(
+                key_board.next_event(),
+                Some(KeyEvent::Key { key, modifiers, ch: Some('c') })
+                    if key.scan_type() == ScanType::CharC && !modifiers.ctrl_down()
+            ));
+            assert_eq!(key_board.next_event(), None);
+        }
+
+        #[test]
+        fn test_secure_attention_beats_hotkeys() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board
+                .hotkeys_mut()
+                .register(1, Hotkey::parse("Ctrl+Alt+Delete").unwrap(), true)
+                .unwrap();
+            feed(&mut key_board, &[0x1d, 0x38, 0xe0]);
+
+            assert_eq!(
+                key_board.input_event(0x53),
+                Some(KeyEvent::SecureAttention(SecureAttention::CtrlAltDelete))
+            );
+        }
+
+        #[test]
+        fn test_remap_caps_to_ctrl() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_remap(KeyRemap::identity().remap(ScanType::CapsLock, ScanType::LeftCtrl));
+
+            assert_eq!(
+                key_board.input_event(0x3a).unwrap().key(),
+                Some(Key::new(ScanType::LeftCtrl, KeyState::Pressed))
+            );
+            assert!(key_board.current_state().left_ctrl);
+            assert!(!key_board.current_state().caps_lock);
+            assert!(key_board.physical_state().caps_lock);
+
+            assert!(key_board.input_byte(0xba).is_none());
+            assert!(!key_board.current_state().left_ctrl);
+        }
+
+        #[test]
+        fn test_remap_swap_at_runtime() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_remap(KeyRemap::identity().remap(ScanType::CapsLock, ScanType::Escape));
+
+            assert_eq!(
+                key_board.input_event(0x3a).unwrap().key(),
+                Some(Key::new(ScanType::Escape, KeyState::Pressed))
+            );
+
+            key_board.set_remap(KeyRemap::identity());
+
+            // The release matches the press
+            assert_eq!(
+                key_board.input_event(0xba).unwrap().key(),
+                Some(Key::new(ScanType::Escape, KeyState::Released))
+            );
+            assert_eq!(
+                key_board.input_event(0x3a).unwrap().key(),
+                Some(Key::new(ScanType::CapsLock, KeyState::Pressed))
+            );
+        }
+
+        #[test]
+        fn test_remap_disable() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_remap(KeyRemap::identity().disable(ScanType::CharA));
+
+            assert!(key_board.input_event(0x1e).is_none());
+            assert!(key_board.input_event(0x9e).is_none());
+            assert_eq!(key_board.input_byte(0x30).unwrap(), 'b');
+        }
+
+        #[test]
+        fn test_remap_cannot_fake_secure_attention() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_remap(KeyRemap::identity().remap(ScanType::CharQ, ScanType::Delete));
+            feed(&mut key_board, &[0x1d, 0x38]);
+
+            assert_eq!(
+                key_board.input_event(0x10).unwrap().key(),
+                Some(Key::new(ScanType::Delete, KeyState::Pressed))
+            );
+        }
+
+        #[test]
+        fn test_dead_key_compose() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, DeadKeyLayout);
+
+            assert!(key_board.input_byte(0x29).is_none()); // Grave
+            assert_eq!(key_board.pending_dead_key(), Some(DeadKey::Grave));
+            assert!(key_board.input_byte(0xa9).is_none());
+            assert_eq!(key_board.input_byte(0x12).unwrap(), 'è');
+            assert_eq!(key_board.pending_dead_key(), None);
+        }
+
+        #[test]
+        fn test_dead_key_with_shift() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, DeadKeyLayout);
+
+            assert!(key_board.input_byte(0x2a).is_none()); // Left shift
+            assert!(key_board.input_byte(0x07).is_none()); // Circumflex
+            assert!(key_board.input_byte(0x87).is_none());
+            assert!(key_board.input_byte(0xaa).is_none());
+            assert_eq!(key_board.input_byte(0x18).unwrap(), 'ô');
+        }
+
+        #[test]
+        fn test_dead_key_no_compose() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, DeadKeyLayout);
+
+            assert!(key_board.input_event(0x29).is_some());
+            assert!(key_board.input_event(0xa9).is_some());
+
+            assert_eq!(key_board.input_event(0x2d), Some(KeyEvent::Char('`')));
+            assert_eq!(key_board.next_event().unwrap().char(), Some('x'));
+        }
+
+        #[test]
+        fn test_input_byte_stays_in_step() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, DeadKeyLayout);
+            key_board.set_translation_state(Some(KeyState::Pressed));
+            key_board
+                .hotkeys_mut()
+                .register(2, Hotkey::parse("X").unwrap(), false)
+                .unwrap();
+
+            // A dead key that doesn't combine, only its character comes back
+            assert_eq!(key_board.input_byte(0x29), None);
+            assert_eq!(key_board.input_byte(0xa9), None);
+            assert_eq!(key_board.input_byte(0x10), Some('`'));
+            assert_eq!(key_board.input_byte(0x90), None);
+
+            // An unconsumed hotkey still types its key, the hotkey waits in next_event
+            assert_eq!(key_board.input_byte(0x2d), Some('x'));
+            assert_eq!(key_board.input_byte(0xad), None);
+            assert_eq!(key_board.input_byte(0x1e), Some('a'));
+            assert_eq!(key_board.input_byte(0x9e), None);
+
+            // Both at once
+            key_board.input_byte(0x29);
+            key_board.input_byte(0xa9);
+            assert_eq!(key_board.input_byte(0x2d), Some('`'));
+            assert_eq!(key_board.input_byte(0xad), None);
+            assert_eq!(key_board.input_byte(0x1e), Some('a'));
+
+            let hotkey = KeyEvent::Hotkey {
+                id: 2,
+                key: Key::new(ScanType::CharX, KeyState::Pressed),
+                consumed: false,
+            };
+            assert_eq!(key_board.next_event(), Some(hotkey));
+            assert_eq!(key_board.next_event(), Some(hotkey));
+            assert_eq!(key_board.next_event(), None);
+        }
+
+        #[test]
+        fn test_dead_key_twice_and_space() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, DeadKeyLayout);
+
+            feed(&mut key_board, &[0x29, 0xa9]);
+            assert_eq!(key_board.input_byte(0x29).unwrap(), '`');
+
+            feed(&mut key_board, &[0xa9, 0x29, 0xa9]);
+            assert_eq!(key_board.input_byte(0x39).unwrap(), '`'); // Space
+        }
+
+        #[test]
+        fn test_dead_key_cleared_by_escape() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, DeadKeyLayout);
+
+            feed(&mut key_board, &[0x29, 0xa9, 0x01, 0x81]);
+            assert_eq!(key_board.pending_dead_key(), None);
+            assert_eq!(key_board.input_byte(0x12).unwrap(), 'e');
+        }
+
+        #[test]
+        fn test_alt_gr_levels() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, AltGrLayout);
+
+            feed(&mut key_board, &[0xe0, 0x38]); // Right alt
+            assert!(key_board.current_state().alt_gr);
+            assert_eq!(key_board.input_byte(0x12).unwrap(), '€');
+
+            feed(&mut key_board, &[0x92, 0x2a]); // Left shift
+            assert_eq!(key_board.input_byte(0x08).unwrap(), '⅞');
+
+            feed(&mut key_board, &[0x88, 0xaa, 0xe0, 0xb8]);
+            assert!(!key_board.current_state().alt_gr);
+            assert_eq!(key_board.input_byte(0x12).unwrap(), 'e');
+        }
+
+        #[test]
+        fn test_alt_gr_emulation() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, AltGrLayout);
+
+            feed(&mut key_board, &[0x1d, 0x38]); // Left ctrl, left alt
+            assert_eq!(key_board.input_byte(0x10).unwrap(), '@');
+
+            key_board.set_alt_gr_emulation(false);
+            assert!(!key_board.current_state().alt_gr);
+            assert_eq!(key_board.input_byte(0x10).unwrap(), 'q');
+        }
+
+        #[test]
+        fn test_right_alt_without_alt_gr() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+
+            feed(&mut key_board, &[0xe0, 0x38]);
+            assert!(!key_board.current_state().alt_gr);
+            assert!(key_board.current_state().alt_down());
+            assert_eq!(key_board.input_byte(0x12).unwrap(), 'e');
+        }
+
+        #[test]
+        fn test_group_switch() {
+            let layouts = LayoutPair::new(USStandardLayout, RussianLayout);
+            let mut key_board = Keyboard::new(ReaderMode::Set1, layouts);
+            key_board.set_group_switch(Some(Hotkey::parse("Alt+Shift").unwrap()));
+
+            assert_eq!(key_board.input_byte(0x10).unwrap(), 'q');
+
+            feed(&mut key_board, &[0x90, 0x38, 0x2a, 0xaa, 0xb8]); // Alt, left shift
+            assert_eq!(key_board.group(), 1);
+            assert_eq!(key_board.current_state().group, 1);
+            assert_eq!(key_board.input_byte(0x10).unwrap(), 'й');
+
+            feed(&mut key_board, &[0x90, 0x38, 0x2a, 0xaa, 0xb8]);
+            assert_eq!(key_board.group(), 0);
+            assert_eq!(key_board.input_byte(0x10).unwrap(), 'q');
+
+            // Right shift counts too, and so does pressing Shift first
+            feed(&mut key_board, &[0x90, 0x38, 0x36, 0xb6, 0xb8]);
+            assert_eq!(key_board.group(), 1);
+            feed(&mut key_board, &[0x2a, 0x38, 0xb8, 0xaa]);
+            assert_eq!(key_board.group(), 0);
+        }
+
+        #[test]
+        fn test_nested_groups() {
+            let layouts = LayoutPair::new(
+                USStandardLayout,
+                LayoutPair::new(GermanQwertzLayout, RussianLayout),
+            );
+            let mut key_board = Keyboard::new(ReaderMode::Set1, layouts);
+            assert_eq!(layouts.group_count(), 3);
+
+            assert_eq!(key_board.input_byte(0x15), Some('y'));
+            key_board.set_group(1);
+            assert_eq!(key_board.input_byte(0x15), Some('z'));
+            key_board.set_group(2);
+            assert_eq!(key_board.input_byte(0x15), Some('н'));
+
+            // AltGr follows the group's own layout
+            feed(&mut key_board, &[0x95, 0xe0, 0x38]);
+            assert!(key_board.current_state().alt_gr);
+            key_board.set_group(0);
+            assert!(!key_board.current_state().alt_gr);
+        }
+
+        #[test]
+        fn test_group_compose() {
+            let layouts = LayoutPair::new(DeadKeyLayout, NoComposeLayout);
+            let mut key_board = Keyboard::new(ReaderMode::Set1, layouts);
+
+            feed(&mut key_board, &[0x29, 0xa9]);
+            assert_eq!(key_board.input_byte(0x12).unwrap(), 'è');
+
+            // The second group's layout decides what composes
+            key_board.set_group(1);
+            feed(&mut key_board, &[0x92, 0x29, 0xa9]);
+            assert_eq!(key_board.input_event(0x12), Some(KeyEvent::Char('`')));
+            assert_eq!(key_board.next_event().unwrap().char(), Some('e'));
+        }
+
+        #[test]
+        fn test_group_alt_gr() {
+            let layouts = LayoutPair::new(USStandardLayout, RussianLayout);
+            let mut key_board = Keyboard::new(ReaderMode::Set1, layouts);
+
+            // Right alt is only AltGr while the Russian layout is active
+            feed(&mut key_board, &[0xe0, 0x38]);
+            assert!(!key_board.current_state().alt_gr);
+
+            key_board.set_group(1);
+            assert!(key_board.current_state().alt_gr);
+            assert_eq!(key_board.input_byte(0x09).unwrap(), '₽');
+
+            // Groups the layout doesn't have are ignored
+            key_board.set_group(2);
+            assert_eq!(key_board.group(), 1);
+        }
+
+        #[test]
+        fn test_sysrq() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            feed(&mut key_board, &[0x38, 0x54]); // Alt, SysRq
+
+            // Disabled by default
+            assert!(matches!(
+                key_board.input_event(0x13),
+                Some(KeyEvent::Key { .. })
+            ));
+
+            key_board.set_sysrq_enabled(true);
+            assert_eq!(
+                key_board.input_event(0x13),
+                Some(KeyEvent::SecureAttention(SecureAttention::SysRq(
+                    ScanType::CharR
+                )))
+            );
+        }
+
+        #[test]
+        fn test_sysrq_after_print_screen() {
+            let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+            key_board.set_sysrq_enabled(true);
+
+            // Alt pressed while print screen is down is not the command key
+            feed(&mut key_board, &[0xe0, 0x2a, 0xe0, 0x37]);
+            assert!(matches!(
+                key_board.input_event(0x38),
+                Some(KeyEvent::Key { .. })
+            ));
+            assert_eq!(
+                key_board.input_event(0x30),
+                Some(KeyEvent::SecureAttention(SecureAttention::SysRq(
+                    ScanType::CharB
+                )))
+            );
+
+            // Releasing SysRq ends it
+            feed(&mut key_board, &[0xb0, 0xe0, 0xb7, 0xe0, 0xaa]);
+            assert!(matches!(
+                key_board.input_event(0x30),
+                Some(KeyEvent::Key { .. })
+            ));
+        }
     }
 }
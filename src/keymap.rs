@@ -0,0 +1,516 @@
+use super::dead_key::DeadKey;
+use super::layout::{KeyModifierState, Layout};
+use super::layouts::{level_symbol, qwerty_row, symbol_into_char, CapsBehaviour, KeySymbol};
+use super::scan_code_set::{ScanTypeSet, SCAN_TYPE_COUNT};
+use super::{Key, ScanType};
+use core::fmt;
+
+// A keymap file is an 8 byte header followed by one fixed size row per key:
+//
+//   header: "VXKM", version, flags, row count, reserved (0)
+//   row:    scan type id, caps behaviour, four little endian u32 symbols
+//
+// The symbols are the base, Shift, AltGr and Shift+AltGr levels. 0 is an empty level,
+// DEAD_KEY_TAG | n is dead key n and anything else is a Unicode scalar value. Keys without a row
+// fall back as on the built in table layouts: letters to QWERTY, the rest to the US layout.
+pub const KEYMAP_MAGIC: [u8; 4] = *b"VXKM";
+pub const KEYMAP_VERSION: u8 = 1;
+pub const KEYMAP_HEADER_LEN: usize = 8;
+pub const KEYMAP_ROW_LEN: usize = 18;
+
+const FLAG_ALT_GR: u8 = 0x01;
+
+const DEAD_KEY_TAG: u32 = 0x8000_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Why a keymap was rejected and the offset of the offending byte
+pub struct KeymapError {
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+impl KeymapError {
+    fn new(position: usize, reason: &'static str) -> Self {
+        return Self { position, reason };
+    }
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{} (byte {})", self.reason, self.position);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// One key of a keymap
+pub struct KeymapEntry {
+    pub scan_type: ScanType,
+    pub caps: CapsBehaviour,
+    pub levels: [KeySymbol; 4],
+}
+
+impl KeymapEntry {
+    pub const fn new(scan_type: ScanType, caps: CapsBehaviour, levels: [KeySymbol; 4]) -> Self {
+        return Self {
+            scan_type,
+            caps,
+            levels,
+        };
+    }
+}
+
+impl CapsBehaviour {
+    fn as_u8(self) -> u8 {
+        return match self {
+            CapsBehaviour::Auto => 0,
+            CapsBehaviour::Shift => 1,
+            CapsBehaviour::Ignore => 2,
+        };
+    }
+
+    fn from_u8(value: u8) -> Option<CapsBehaviour> {
+        return match value {
+            0 => Some(CapsBehaviour::Auto),
+            1 => Some(CapsBehaviour::Shift),
+            2 => Some(CapsBehaviour::Ignore),
+            _ => None,
+        };
+    }
+}
+
+// The id a scan type is stored as is its u8 value. Existing keymaps depend on these, which is
+// why ScanType only ever grows at the end.
+fn scan_type_id(scan_type: ScanType) -> Option<u8> {
+    let id = scan_type.as_u8();
+
+    if id as usize >= SCAN_TYPE_COUNT {
+        return None;
+    }
+
+    return Some(id);
+}
+
+fn scan_type_from_id(id: u8) -> Option<ScanType> {
+    if id as usize >= SCAN_TYPE_COUNT {
+        return None;
+    }
+
+    return ScanType::from_u8(id);
+}
+
+fn encode_symbol(symbol: KeySymbol) -> u32 {
+    return match symbol {
+        KeySymbol::Empty => 0,
+        KeySymbol::Char(ch) => ch as u32,
+        KeySymbol::Dead(dead_key) => DEAD_KEY_TAG | dead_key.as_u8() as u32,
+    };
+}
+
+fn decode_symbol(value: u32) -> Option<KeySymbol> {
+    if value == 0 {
+        return Some(KeySymbol::Empty);
+    }
+
+    if value & DEAD_KEY_TAG != 0 {
+        let index = value & !DEAD_KEY_TAG;
+
+        if index > u8::MAX as u32 {
+            return None;
+        }
+
+        return DeadKey::from_u8(index as u8).map(KeySymbol::Dead);
+    }
+
+    return core::char::from_u32(value).map(KeySymbol::Char);
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+
+    return u32::from_le_bytes(bytes);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A layout described by a keymap file. It borrows the validated bytes and decodes rows on
+// lookup, so a keymap can be used straight out of an initrd without copying it. Each scan type
+// indexes straight to its row.
+pub struct TableLayout<'a> {
+    data: &'a [u8],
+    // Row number + 1 for every scan type, 0 where there is no row
+    index: [u8; SCAN_TYPE_COUNT],
+}
+
+impl<'a> TableLayout<'a> {
+    // Checks the whole keymap up front so lookups never see a malformed row
+    pub fn parse(data: &'a [u8]) -> Result<Self, KeymapError> {
+        if data.len() < KEYMAP_HEADER_LEN {
+            return Err(KeymapError::new(data.len(), "Keymap header truncated."));
+        }
+
+        for (i, (byte, expected)) in data.iter().zip(KEYMAP_MAGIC.iter()).enumerate() {
+            if byte != expected {
+                return Err(KeymapError::new(i, "Not a keymap."));
+            }
+        }
+
+        if data[4] != KEYMAP_VERSION {
+            return Err(KeymapError::new(4, "Unsupported keymap version."));
+        }
+
+        if data[5] & !FLAG_ALT_GR != 0 {
+            return Err(KeymapError::new(5, "Unknown keymap flags."));
+        }
+
+        if data[7] != 0 {
+            return Err(KeymapError::new(7, "Reserved byte is not zero."));
+        }
+
+        let expected_len = KEYMAP_HEADER_LEN + data[6] as usize * KEYMAP_ROW_LEN;
+
+        if data.len() < expected_len {
+            return Err(KeymapError::new(data.len(), "Keymap rows truncated."));
+        }
+
+        if data.len() > expected_len {
+            return Err(KeymapError::new(
+                expected_len,
+                "Trailing bytes after keymap.",
+            ));
+        }
+
+        let mut index = [0u8; SCAN_TYPE_COUNT];
+
+        for (i, row) in (KEYMAP_HEADER_LEN..data.len())
+            .step_by(KEYMAP_ROW_LEN)
+            .enumerate()
+        {
+            let scan_type = match scan_type_from_id(data[row]) {
+                Some(scan_type) => scan_type,
+                None => return Err(KeymapError::new(row, "Unknown scan type.")),
+            };
+
+            // Every scan type has at most one row, so the row numbers fit
+            let slot = &mut index[scan_type.as_u8() as usize];
+
+            if *slot != 0 {
+                return Err(KeymapError::new(row, "Scan type repeated."));
+            }
+
+            *slot = i as u8 + 1;
+
+            if CapsBehaviour::from_u8(data[row + 1]).is_none() {
+                return Err(KeymapError::new(row + 1, "Unknown caps behaviour."));
+            }
+
+            for offset in (row + 2..row + KEYMAP_ROW_LEN).step_by(4) {
+                if decode_symbol(read_u32(data, offset)).is_none() {
+                    return Err(KeymapError::new(offset, "Invalid key symbol."));
+                }
+            }
+        }
+
+        return Ok(Self { data, index });
+    }
+
+    pub fn len(&self) -> usize {
+        return self.data[6] as usize;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    pub fn entry(&self, index: usize) -> Option<KeymapEntry> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let row = KEYMAP_HEADER_LEN + index * KEYMAP_ROW_LEN;
+        let mut levels = [KeySymbol::Empty; 4];
+
+        for (i, level) in levels.iter_mut().enumerate() {
+            *level = decode_symbol(read_u32(self.data, row + 2 + i * 4))?;
+        }
+
+        return Some(KeymapEntry::new(
+            scan_type_from_id(self.data[row])?,
+            CapsBehaviour::from_u8(self.data[row + 1])?,
+            levels,
+        ));
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = KeymapEntry> + 'a {
+        let layout = *self;
+
+        return (0..self.len()).filter_map(move |i| layout.entry(i));
+    }
+
+    fn find(&self, scan_type: ScanType) -> Option<([KeySymbol; 4], CapsBehaviour)> {
+        let row = match self.index.get(scan_type.as_u8() as usize) {
+            Some(row) => *row,
+            None => 0,
+        };
+
+        if row != 0 {
+            let entry = self.entry(row as usize - 1)?;
+
+            return Some((entry.levels, entry.caps));
+        }
+
+        return qwerty_row(scan_type).map(|levels| (levels, CapsBehaviour::Auto));
+    }
+
+    fn symbol(&self, modifiers: &KeyModifierState, key: Key) -> Option<KeySymbol> {
+        if key.keypad() {
+            return None;
+        }
+
+        let (levels, caps) = self.find(key.scan_type())?;
+
+        return Some(level_symbol(&levels, caps, modifiers));
+    }
+}
+
+impl<'a> Layout for TableLayout<'a> {
+    fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+        return symbol_into_char(self.symbol(modifiers, key), modifiers, key);
+    }
+
+    fn has_alt_gr(&self) -> bool {
+        return self.data[5] & FLAG_ALT_GR != 0;
+    }
+
+    fn dead_key(&self, modifiers: &KeyModifierState, key: Key) -> Option<DeadKey> {
+        return match self.symbol(modifiers, key) {
+            Some(KeySymbol::Dead(dead_key)) => Some(dead_key),
+            _ => None,
+        };
+    }
+}
+
+// The number of bytes a keymap of this many entries takes
+pub const fn keymap_len(entries: usize) -> usize {
+    return KEYMAP_HEADER_LEN + entries * KEYMAP_ROW_LEN;
+}
+
+// Writes a keymap into the buffer and returns its length
+pub fn write_keymap(
+    entries: &[KeymapEntry],
+    has_alt_gr: bool,
+    buffer: &mut [u8],
+) -> Result<usize, &'static str> {
+    if entries.len() > u8::MAX as usize {
+        return Err("Too many keymap entries.");
+    }
+
+    let len = keymap_len(entries.len());
+
+    if buffer.len() < len {
+        return Err("Buffer too small for keymap.");
+    }
+
+    let mut seen = ScanTypeSet::new();
+
+    for entry in entries.iter() {
+        if scan_type_id(entry.scan_type).is_none() {
+            return Err("Keymap entry for unknown scan type.");
+        }
+
+        if seen.contains(entry.scan_type) {
+            return Err("Scan type repeated in keymap.");
+        }
+
+        seen.insert(entry.scan_type);
+    }
+
+    buffer[..4].copy_from_slice(&KEYMAP_MAGIC);
+    buffer[4] = KEYMAP_VERSION;
+    buffer[5] = if has_alt_gr { FLAG_ALT_GR } else { 0 };
+    buffer[6] = entries.len() as u8;
+    buffer[7] = 0;
+
+    for (entry, row) in entries
+        .iter()
+        .zip(buffer[KEYMAP_HEADER_LEN..len].chunks_mut(KEYMAP_ROW_LEN))
+    {
+        // Every entry was checked to have an id above
+        row[0] = scan_type_id(entry.scan_type).unwrap_or_default();
+        row[1] = entry.caps.as_u8();
+
+        for (symbol, bytes) in entry.levels.iter().zip(row[2..].chunks_mut(4)) {
+            bytes.copy_from_slice(&encode_symbol(*symbol).to_le_bytes());
+        }
+    }
+
+    return Ok(len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::layouts::test_util::press;
+    use super::super::{Keyboard, ReaderMode};
+    use super::*;
+    use KeySymbol::{Char, Dead, Empty};
+
+    const ENTRIES: [KeymapEntry; 4] = [
+        KeymapEntry::new(
+            ScanType::CharY,
+            CapsBehaviour::Auto,
+            [Char('z'), Char('Z'), Char('←'), Empty],
+        ),
+        KeymapEntry::new(
+            ScanType::CharZ,
+            CapsBehaviour::Auto,
+            [Char('y'), Char('Y'), Char('»'), Empty],
+        ),
+        KeymapEntry::new(
+            ScanType::Num4,
+            CapsBehaviour::Shift,
+            [Char('4'), Char('$'), Char('€'), Empty],
+        ),
+        KeymapEntry::new(
+            ScanType::SymbolEquals,
+            CapsBehaviour::Ignore,
+            [Dead(DeadKey::Acute), Dead(DeadKey::Grave), Empty, Empty],
+        ),
+    ];
+
+    fn write(buffer: &mut [u8]) -> &[u8] {
+        let len = write_keymap(&ENTRIES, true, buffer).unwrap();
+
+        return &buffer[..len];
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut buffer = [0u8; keymap_len(4)];
+        let layout = TableLayout::parse(write(&mut buffer)).unwrap();
+
+        assert!(layout.has_alt_gr());
+        assert_eq!(layout.len(), 4);
+
+        for (entry, expected) in layout.entries().zip(ENTRIES.iter()) {
+            assert_eq!(entry, *expected);
+        }
+    }
+
+    #[test]
+    fn test_typing() {
+        let mut buffer = [0u8; keymap_len(4)];
+        let layout = TableLayout::parse(write(&mut buffer)).unwrap();
+        let mut key_board = Keyboard::new(ReaderMode::Set1, layout);
+
+        assert_eq!(press(&mut key_board, 0x15), Some('z'));
+        assert_eq!(press(&mut key_board, 0x2c), Some('y'));
+        assert_eq!(press(&mut key_board, 0x10), Some('q'));
+        assert_eq!(press(&mut key_board, 0x39), Some(' '));
+
+        assert_eq!(press(&mut key_board, 0x0d), None);
+        assert_eq!(press(&mut key_board, 0x12), Some('é'));
+
+        key_board.input_byte(0xe0);
+        key_board.input_byte(0x38);
+        assert_eq!(press(&mut key_board, 0x05), Some('€'));
+        assert_eq!(press(&mut key_board, 0x15), Some('←'));
+        key_board.input_byte(0xe0);
+        key_board.input_byte(0xb8);
+
+        press(&mut key_board, 0x3a);
+        assert_eq!(press(&mut key_board, 0x15), Some('Z'));
+        assert_eq!(press(&mut key_board, 0x05), Some('$'));
+        assert_eq!(press(&mut key_board, 0x0d), None);
+        assert_eq!(press(&mut key_board, 0x12), Some('É'));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut buffer = [0u8; keymap_len(4) + 1];
+        let len = write(&mut buffer).len();
+
+        let check = |position: usize, value: u8, reason: &'static str| {
+            let mut data = buffer;
+            data[position] = value;
+
+            assert_eq!(
+                TableLayout::parse(&data[..len]),
+                Err(KeymapError::new(position, reason))
+            );
+        };
+
+        check(2, b'X', "Not a keymap.");
+        check(4, 2, "Unsupported keymap version.");
+        check(5, 0x80, "Unknown keymap flags.");
+        check(7, 1, "Reserved byte is not zero.");
+        check(26, 200, "Unknown scan type.");
+        check(26, ScanType::Unknown.as_u8(), "Unknown scan type.");
+        check(
+            26,
+            scan_type_id(ScanType::CharY).unwrap(),
+            "Scan type repeated.",
+        );
+        check(27, 3, "Unknown caps behaviour.");
+
+        // Symbols are reported at their first byte
+        let mut data = buffer;
+        data[29] = 0xd8;
+        assert_eq!(
+            TableLayout::parse(&data[..len]),
+            Err(KeymapError::new(28, "Invalid key symbol."))
+        );
+        data[29] = 0;
+        data[65] = 0x80;
+        assert_eq!(
+            TableLayout::parse(&data[..len]),
+            Err(KeymapError::new(64, "Invalid key symbol."))
+        );
+
+        assert_eq!(
+            TableLayout::parse(&buffer[..5]),
+            Err(KeymapError::new(5, "Keymap header truncated."))
+        );
+        assert_eq!(
+            TableLayout::parse(&buffer[..len - 1]),
+            Err(KeymapError::new(len - 1, "Keymap rows truncated."))
+        );
+        assert_eq!(
+            TableLayout::parse(&buffer[..]),
+            Err(KeymapError::new(len, "Trailing bytes after keymap."))
+        );
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut buffer = [0u8; keymap_len(4)];
+
+        assert_eq!(
+            write_keymap(&ENTRIES, false, &mut buffer[..keymap_len(3)]),
+            Err("Buffer too small for keymap.")
+        );
+        assert_eq!(
+            write_keymap(&[ENTRIES[0], ENTRIES[0]], false, &mut buffer),
+            Err("Scan type repeated in keymap.")
+        );
+
+        let unknown = KeymapEntry::new(ScanType::Unknown, CapsBehaviour::Auto, [Empty; 4]);
+        assert_eq!(
+            write_keymap(&[unknown], false, &mut buffer),
+            Err("Keymap entry for unknown scan type.")
+        );
+    }
+
+    #[test]
+    fn test_scan_type_ids() {
+        // These are part of the file format and must never change
+        assert_eq!(scan_type_id(ScanType::Num0), Some(0));
+        assert_eq!(scan_type_id(ScanType::CharA), Some(10));
+        assert_eq!(scan_type_id(ScanType::Escape), Some(49));
+        assert_eq!(scan_type_id(ScanType::Yen), Some(116));
+        assert_eq!(scan_type_id(ScanType::Unknown), None);
+
+        for scan_type in ScanType::ALL.iter() {
+            let id = scan_type_id(*scan_type).unwrap();
+            assert_eq!(scan_type_from_id(id), Some(*scan_type));
+        }
+    }
+}
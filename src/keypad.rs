@@ -0,0 +1,51 @@
+use super::layout::{KeyModifierState, Layout};
+use super::{Key, ScanType};
+
+// Shift inverts NumLock while it is held, as on a PC
+pub(crate) fn num_lock_active(modifiers: &KeyModifierState) -> bool {
+    return modifiers.num_lock != modifiers.shift_down();
+}
+
+// The key a keypad key stands for when NumLock is off. Keypad 5 has no second function.
+pub(crate) fn navigation_key(scan_type: ScanType) -> Option<ScanType> {
+    return match scan_type {
+        ScanType::Num0 => Some(ScanType::Insert),
+        ScanType::Num1 => Some(ScanType::End),
+        ScanType::Num2 => Some(ScanType::CursorDown),
+        ScanType::Num3 => Some(ScanType::PageDown),
+        ScanType::Num4 => Some(ScanType::CursorLeft),
+        ScanType::Num6 => Some(ScanType::CursorRight),
+        ScanType::Num7 => Some(ScanType::Home),
+        ScanType::Num8 => Some(ScanType::CursorUp),
+        ScanType::Num9 => Some(ScanType::PageUp),
+        ScanType::SymbolPeriod => Some(ScanType::Delete),
+        _ => None,
+    };
+}
+
+// The operator keys and Enter are the same on every layout and ignore Shift. The digits and the
+// decimal separator come from the layout, without Shift since it only inverted NumLock.
+pub(crate) fn keypad_char<L: Layout + ?Sized>(
+    layout: &L,
+    modifiers: &KeyModifierState,
+    key: Key,
+) -> Option<char> {
+    match key.scan_type() {
+        ScanType::Enter => return Some('\n'),
+        ScanType::SymbolForwardSlash => return Some('/'),
+        ScanType::SymbolAsterisk => return Some('*'),
+        ScanType::SymbolMinus => return Some('-'),
+        ScanType::SymbolPlus => return Some('+'),
+        _ => (),
+    }
+
+    if !num_lock_active(modifiers) {
+        return None;
+    }
+
+    let mut modifiers = *modifiers;
+    modifiers.left_shift = false;
+    modifiers.right_shift = false;
+
+    return layout.key_into_char(&modifiers, key);
+}
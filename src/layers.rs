@@ -0,0 +1,345 @@
+use super::scan_code_set::{KeySet, KEY_SLOT_COUNT, SCAN_TYPE_COUNT};
+use super::{Key, ScanType};
+
+pub const LAYER_COUNT: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerAction {
+    // Whatever the next active layer below does with the key, the key itself below layer 0
+    Transparent,
+    Key(ScanType),
+    Disabled,
+    // Turns the layer on while the key is held
+    Momentary(u8),
+    // Turns the layer on or off with each press
+    Toggle(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// One remap table in the layer stack, every key starts out transparent
+pub struct Layer {
+    actions: [LayerAction; SCAN_TYPE_COUNT],
+}
+
+impl Layer {
+    pub const fn transparent() -> Self {
+        return Self {
+            actions: [LayerAction::Transparent; SCAN_TYPE_COUNT],
+        };
+    }
+
+    pub const fn with(mut self, from: ScanType, action: LayerAction) -> Self {
+        if (from as usize) < SCAN_TYPE_COUNT {
+            self.actions[from as usize] = action;
+        }
+
+        return self;
+    }
+
+    pub const fn map(self, from: ScanType, to: ScanType) -> Self {
+        return self.with(from, LayerAction::Key(to));
+    }
+
+    pub const fn disable(self, key: ScanType) -> Self {
+        return self.with(key, LayerAction::Disabled);
+    }
+
+    pub const fn momentary(self, key: ScanType, layer: u8) -> Self {
+        return self.with(key, LayerAction::Momentary(layer));
+    }
+
+    pub const fn toggle(self, key: ScanType, layer: u8) -> Self {
+        return self.with(key, LayerAction::Toggle(layer));
+    }
+
+    pub fn set(&mut self, from: ScanType, action: LayerAction) {
+        if let Some(slot) = self.actions.get_mut(from.as_u8() as usize) {
+            *slot = action;
+        }
+    }
+
+    pub fn get(&self, from: ScanType) -> LayerAction {
+        return match self.actions.get(from.as_u8() as usize) {
+            Some(action) => *action,
+            None => LayerAction::Transparent,
+        };
+    }
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        return Self::transparent();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// QMK style layers: a stack of tables where the highest active layer that doesn't leave a key
+// transparent decides what it does. Layer 0 is always active. Each held key remembers the layer
+// its press came from so its release does the same thing, whatever the layers did meanwhile.
+pub struct Layers {
+    layers: [Layer; LAYER_COUNT],
+    toggled: u8,
+    // How many held keys keep each layer on
+    momentary: [u8; LAYER_COUNT],
+    held: KeySet,
+    pressed_on: [u8; KEY_SLOT_COUNT],
+}
+
+impl Layers {
+    pub const fn new() -> Self {
+        return Self {
+            layers: [Layer::transparent(); LAYER_COUNT],
+            toggled: 0,
+            momentary: [0; LAYER_COUNT],
+            held: KeySet::new(),
+            pressed_on: [0; KEY_SLOT_COUNT],
+        };
+    }
+
+    pub const fn with_layer(mut self, index: u8, layer: Layer) -> Self {
+        if (index as usize) < LAYER_COUNT {
+            self.layers[index as usize] = layer;
+        }
+
+        return self;
+    }
+
+    pub fn set_layer(&mut self, index: u8, layer: Layer) {
+        if let Some(slot) = self.layers.get_mut(index as usize) {
+            *slot = layer;
+        }
+    }
+
+    pub fn layer(&self, index: u8) -> Option<&Layer> {
+        return self.layers.get(index as usize);
+    }
+
+    pub fn is_active(&self, index: u8) -> bool {
+        if index as usize >= LAYER_COUNT {
+            return false;
+        }
+
+        return index == 0
+            || self.toggled & (1 << index) != 0
+            || self.momentary[index as usize] > 0;
+    }
+
+    // Bit n is set while layer n is active
+    pub fn active_layers(&self) -> u8 {
+        let mut active = 0;
+
+        for index in 0..LAYER_COUNT as u8 {
+            if self.is_active(index) {
+                active |= 1 << index;
+            }
+        }
+
+        return active;
+    }
+
+    pub fn highest_active(&self) -> u8 {
+        return 7 - self.active_layers().leading_zeros() as u8;
+    }
+
+    pub fn is_toggled(&self, index: u8) -> bool {
+        return (index as usize) < LAYER_COUNT && self.toggled & (1 << index) != 0;
+    }
+
+    pub fn set_toggled(&mut self, index: u8, on: bool) {
+        if index as usize >= LAYER_COUNT {
+            return;
+        }
+
+        if on {
+            self.toggled |= 1 << index;
+        } else {
+            self.toggled &= !(1 << index);
+        }
+    }
+
+    // The action for the key looking down from the given layer, and the layer it came from
+    fn resolve(&self, scan_type: ScanType, from: u8) -> (u8, LayerAction) {
+        for index in (0..=from).rev() {
+            if !self.is_active(index) {
+                continue;
+            }
+
+            match self.layers[index as usize].get(scan_type) {
+                LayerAction::Transparent => continue,
+                action => return (index, action),
+            }
+        }
+
+        return (0, LayerAction::Key(scan_type));
+    }
+
+    // Returns what the key is delivered as, None for disabled keys and layer keys
+    pub fn map(&mut self, key: Key) -> Option<Key> {
+        let scan_type = key.scan_type();
+        let index = match key.slot() {
+            Some(index) => index,
+            None => return Some(key),
+        };
+
+        if !key.is_pressed() {
+            if !self.held.remove(&key) {
+                return Some(key);
+            }
+
+            // Only the layer the press came from is looked at, even if it has been switched off
+            let layer = &self.layers[self.pressed_on[index] as usize];
+
+            return match layer.get(scan_type) {
+                LayerAction::Key(target) => Some(key.with_scan_type(target)),
+                LayerAction::Momentary(n) if (n as usize) < LAYER_COUNT => {
+                    self.momentary[n as usize] = self.momentary[n as usize].saturating_sub(1);
+                    None
+                }
+                LayerAction::Transparent => Some(key),
+                _ => None,
+            };
+        }
+
+        // Repeats do what the first press did without switching layers again
+        if self.held.contains(&key) {
+            return match self.layers[self.pressed_on[index] as usize].get(scan_type) {
+                LayerAction::Key(target) => Some(key.with_scan_type(target)),
+                LayerAction::Transparent => Some(key),
+                _ => None,
+            };
+        }
+
+        let (layer, action) = self.resolve(scan_type, LAYER_COUNT as u8 - 1);
+        self.held.insert(&key);
+        self.pressed_on[index] = layer;
+
+        return match action {
+            LayerAction::Key(target) => Some(key.with_scan_type(target)),
+            LayerAction::Momentary(n) if (n as usize) < LAYER_COUNT => {
+                self.momentary[n as usize] += 1;
+                None
+            }
+            LayerAction::Toggle(n) => {
+                self.set_toggled(n, !self.is_toggled(n));
+                None
+            }
+            _ => None,
+        };
+    }
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::KeyState;
+    use super::*;
+
+    const VIM_ARROWS: Layers = Layers::new()
+        .with_layer(0, Layer::transparent().momentary(ScanType::CapsLock, 1))
+        .with_layer(
+            1,
+            Layer::transparent()
+                .map(ScanType::CharH, ScanType::CursorLeft)
+                .map(ScanType::CharJ, ScanType::CursorDown)
+                .map(ScanType::CharK, ScanType::CursorUp)
+                .map(ScanType::CharL, ScanType::CursorRight)
+                .disable(ScanType::CharQ)
+                .toggle(ScanType::CharT, 2),
+        )
+        .with_layer(
+            2,
+            Layer::transparent().map(ScanType::CharA, ScanType::CharB),
+        );
+
+    fn press(layers: &mut Layers, scan_type: ScanType) -> Option<ScanType> {
+        return layers
+            .map(Key::new(scan_type, KeyState::Pressed))
+            .map(|key| key.scan_type());
+    }
+
+    fn release(layers: &mut Layers, scan_type: ScanType) -> Option<ScanType> {
+        return layers
+            .map(Key::new(scan_type, KeyState::Released))
+            .map(|key| key.scan_type());
+    }
+
+    #[test]
+    fn test_momentary() {
+        let mut layers = VIM_ARROWS;
+
+        assert_eq!(press(&mut layers, ScanType::CharH), Some(ScanType::CharH));
+        assert_eq!(release(&mut layers, ScanType::CharH), Some(ScanType::CharH));
+
+        assert_eq!(press(&mut layers, ScanType::CapsLock), None);
+        assert!(layers.is_active(1));
+        assert_eq!(layers.highest_active(), 1);
+        assert_eq!(
+            press(&mut layers, ScanType::CharH),
+            Some(ScanType::CursorLeft)
+        );
+        assert_eq!(
+            press(&mut layers, ScanType::CharH),
+            Some(ScanType::CursorLeft)
+        );
+        assert_eq!(press(&mut layers, ScanType::CharQ), None);
+
+        // Transparent keys fall through to layer 0
+        assert_eq!(press(&mut layers, ScanType::CharA), Some(ScanType::CharA));
+
+        assert_eq!(release(&mut layers, ScanType::CapsLock), None);
+        assert_eq!(layers.active_layers(), 0b1);
+
+        // The release matches the press from layer 1
+        assert_eq!(
+            release(&mut layers, ScanType::CharH),
+            Some(ScanType::CursorLeft)
+        );
+        assert_eq!(release(&mut layers, ScanType::CharQ), None);
+        assert_eq!(release(&mut layers, ScanType::CharA), Some(ScanType::CharA));
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut layers = VIM_ARROWS;
+
+        press(&mut layers, ScanType::CapsLock);
+        assert_eq!(press(&mut layers, ScanType::CharT), None);
+        assert_eq!(press(&mut layers, ScanType::CharT), None);
+        assert_eq!(release(&mut layers, ScanType::CharT), None);
+        release(&mut layers, ScanType::CapsLock);
+
+        assert!(layers.is_toggled(2));
+        assert_eq!(layers.active_layers(), 0b101);
+        assert_eq!(press(&mut layers, ScanType::CharA), Some(ScanType::CharB));
+        assert_eq!(press(&mut layers, ScanType::CharH), Some(ScanType::CharH));
+
+        layers.set_toggled(2, false);
+        assert_eq!(release(&mut layers, ScanType::CharA), Some(ScanType::CharB));
+        assert_eq!(press(&mut layers, ScanType::CharA), Some(ScanType::CharA));
+    }
+
+    #[test]
+    fn test_keypad_apart() {
+        let mut layers = Layers::new()
+            .with_layer(0, Layer::transparent().momentary(ScanType::CapsLock, 1))
+            .with_layer(1, Layer::transparent().map(ScanType::Num7, ScanType::Home));
+
+        press(&mut layers, ScanType::CapsLock);
+        assert_eq!(press(&mut layers, ScanType::Num7), Some(ScanType::Home));
+        release(&mut layers, ScanType::CapsLock);
+
+        // The keypad 7 pressed meanwhile is its own key
+        let keypad = Key::new_keypad(ScanType::Num7, KeyState::Pressed);
+        assert_eq!(layers.map(keypad), Some(keypad));
+        assert_eq!(
+            layers.map(keypad.inverted_state()),
+            Some(keypad.inverted_state())
+        );
+        assert_eq!(release(&mut layers, ScanType::Num7), Some(ScanType::Home));
+    }
+}
@@ -1,3 +1,6 @@
+use super::dead_key::DeadKey;
+use super::key_stroke::{find_key_strokes, KeyStrokes};
+use super::sticky_keys::ModifierKeys;
 use super::{Key, ScanType};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,6 +16,16 @@ pub struct KeyModifierState {
     pub caps_lock: bool,
     pub num_lock: bool,
     pub scroll_lock: bool,
+    // Set by the keyboard when the layout uses RightAlt (or Ctrl+LeftAlt) as AltGr
+    pub alt_gr: bool,
+    // The active layout group, 0 is the primary layout
+    pub group: u8,
+    // Toggled by the Katakana/Hiragana key, layouts with kana type those instead
+    pub kana_lock: bool,
+    // Sticky Keys: modifiers latched for the next key and modifiers locked until tapped again.
+    // Both are already folded into the fields above, which otherwise follow the held keys.
+    pub latched: ModifierKeys,
+    pub locked: ModifierKeys,
 }
 
 impl KeyModifierState {
@@ -29,6 +42,11 @@ impl KeyModifierState {
             caps_lock: false,
             num_lock: false,
             scroll_lock: false,
+            alt_gr: false,
+            group: 0,
+            kana_lock: false,
+            latched: ModifierKeys::new(),
+            locked: ModifierKeys::new(),
         };
     }
 
@@ -47,156 +65,309 @@ impl KeyModifierState {
     pub fn gui_down(&self) -> bool {
         return self.left_gui || self.right_gui;
     }
+
+    // The shift level a layout should pick the character from
+    pub fn level(&self) -> KeyLevel {
+        return match (self.shift_down(), self.alt_gr) {
+            (false, false) => KeyLevel::Base,
+            (true, false) => KeyLevel::Shift,
+            (false, true) => KeyLevel::AltGr,
+            (true, true) => KeyLevel::ShiftAltGr,
+        };
+    }
+
+    // Whether one of the eight modifier keys is down, false for other keys
+    pub(crate) fn modifier(&self, scan_type: ScanType) -> bool {
+        return match scan_type {
+            ScanType::LeftGUI => self.left_gui,
+            ScanType::RightGUI => self.right_gui,
+            ScanType::LeftAlt => self.left_alt,
+            ScanType::RightAlt => self.right_alt,
+            ScanType::LeftShift => self.left_shift,
+            ScanType::RightShift => self.right_shift,
+            ScanType::LeftCtrl => self.left_ctrl,
+            ScanType::RightCtrl => self.right_ctrl,
+            _ => false,
+        };
+    }
+
+    // Sets the field of one of the eight modifier keys, other keys are ignored
+    pub(crate) fn set_modifier(&mut self, scan_type: ScanType, down: bool) {
+        match scan_type {
+            ScanType::LeftGUI => self.left_gui = down,
+            ScanType::RightGUI => self.right_gui = down,
+            ScanType::LeftAlt => self.left_alt = down,
+            ScanType::RightAlt => self.right_alt = down,
+            ScanType::LeftShift => self.left_shift = down,
+            ScanType::RightShift => self.right_shift = down,
+            ScanType::LeftCtrl => self.left_ctrl = down,
+            ScanType::RightCtrl => self.right_ctrl = down,
+            _ => (),
+        }
+    }
+
+    // Updates the state for a modifier or lock key, other keys are ignored
+    pub(crate) fn apply_key(&mut self, key: &Key) {
+        match key.scan_type() {
+            ScanType::NumLock => {
+                // Toggle only when pressed
+                if key.is_pressed() {
+                    self.num_lock = !self.num_lock;
+                }
+            }
+            ScanType::CapsLock => {
+                // Toggle only when pressed
+                if key.is_pressed() {
+                    self.caps_lock = !self.caps_lock;
+                }
+            }
+            ScanType::ScrollLock => {
+                // Toggle only when pressed
+                if key.is_pressed() {
+                    self.scroll_lock = !self.scroll_lock;
+                }
+            }
+            ScanType::KatakanaHiragana => {
+                // Toggle only when pressed
+                if key.is_pressed() {
+                    self.kana_lock = !self.kana_lock;
+                }
+            }
+            scan_type => self.set_modifier(scan_type, key.is_pressed()),
+        }
+    }
+}
+
+impl Default for KeyModifierState {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+// Levels 1 to 4 of a key: base, Shift, AltGr and Shift+AltGr
+pub enum KeyLevel {
+    Base = 0,
+    Shift,
+    AltGr,
+    ShiftAltGr,
+}
+
+impl KeyLevel {
+    #[inline]
+    pub fn index(self) -> usize {
+        return self as usize;
+    }
+
+    pub fn is_shifted(self) -> bool {
+        return self == KeyLevel::Shift || self == KeyLevel::ShiftAltGr;
+    }
+
+    pub fn is_alt_gr(self) -> bool {
+        return self == KeyLevel::AltGr || self == KeyLevel::ShiftAltGr;
+    }
 }
 
 // These only specify a way to create a character from a key only.
 pub trait Layout {
     fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char>;
+
+    // Whether RightAlt acts as AltGr instead of a second Alt key
+    fn has_alt_gr(&self) -> bool {
+        return false;
+    }
+
+    // The number of layout groups the keyboard can switch between
+    fn group_count(&self) -> u8 {
+        return 1;
+    }
+
+    fn group_has_alt_gr(&self, _group: u8) -> bool {
+        return self.has_alt_gr();
+    }
+
+    // Dead keys produce no character themselves, they modify the next key instead
+    fn dead_key(&self, _modifiers: &KeyModifierState, _key: Key) -> Option<DeadKey> {
+        return None;
+    }
+
+    // Combines a pending dead key with the next character, None if they don't combine
+    fn compose(&self, _modifiers: &KeyModifierState, dead_key: DeadKey, ch: char) -> Option<char> {
+        return dead_key.compose(ch);
+    }
+
+    // The keys and levels that type a character on the first group, None if no key does
+    fn char_to_keys(&self, ch: char) -> Option<KeyStrokes> {
+        return find_key_strokes(self, ch);
+    }
+}
+
+// The US QWERTY layout. Caps Lock only affects letters, SymbolPlus and SymbolAsterisk only exist
+// on the keypad.
+crate::layout! {
+    pub struct USStandardLayout;
+    alt_gr: false;
+    keys {
+        CharA => ('a', 'A', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharE => ('e', 'E', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        CharM => ('m', 'M', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharZ => ('z', 'Z', _, _, Auto),
+        Num0 => ('0', ')', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '@', _, _, Ignore),
+        Num3 => ('3', '#', _, _, Ignore),
+        Num4 => ('4', '$', _, _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', '^', _, _, Ignore),
+        Num7 => ('7', '&', _, _, Ignore),
+        Num8 => ('8', '*', _, _, Ignore),
+        Num9 => ('9', '(', _, _, Ignore),
+        SymbolPlus => ('+', '+', _, _, Ignore),
+        SymbolMinus => ('-', '_', _, _, Ignore),
+        SymbolEquals => ('=', '+', _, _, Ignore),
+        SymbolOpenSquareBracket => ('[', '{', _, _, Ignore),
+        SymbolCloseSquareBracket => (']', '}', _, _, Ignore),
+        SymbolSemicolon => (';', ':', _, _, Ignore),
+        SymbolSingleQuote => ('\'', '"', _, _, Ignore),
+        SymbolBacktick => ('`', '~', _, _, Ignore),
+        SymbolBackslash => ('\\', '|', _, _, Ignore),
+        SymbolComma => (',', '<', _, _, Ignore),
+        SymbolPeriod => ('.', '>', _, _, Ignore),
+        SymbolForwardSlash => ('/', '?', _, _, Ignore),
+        SymbolAsterisk => ('*', '*', _, _, Ignore),
+        Space => (' ', ' ', _, _, Ignore),
+        Tab => ('\t', '\t', _, _, Ignore),
+    }
+    keypad {
+        Num0 => ('0', _, _, _, Ignore),
+        Num1 => ('1', _, _, _, Ignore),
+        Num2 => ('2', _, _, _, Ignore),
+        Num3 => ('3', _, _, _, Ignore),
+        Num4 => ('4', _, _, _, Ignore),
+        Num5 => ('5', _, _, _, Ignore),
+        Num6 => ('6', _, _, _, Ignore),
+        Num7 => ('7', _, _, _, Ignore),
+        Num8 => ('8', _, _, _, Ignore),
+        Num9 => ('9', _, _, _, Ignore),
+    }
+}
+
+// Swaps the case of a letter. Letters whose other case isn't a single character (such as ß)
+// are left alone.
+pub(crate) fn invert_case(ch: char) -> char {
+    let inverted = if ch.is_lowercase() {
+        single_char(ch.to_uppercase())
+    } else if ch.is_uppercase() {
+        single_char(ch.to_lowercase())
+    } else {
+        None
+    };
+
+    return inverted.unwrap_or(ch);
+}
+
+fn single_char<I: Iterator<Item = char>>(mut chars: I) -> Option<char> {
+    let ch = chars.next()?;
+
+    if chars.next().is_some() {
+        return None;
+    }
+
+    return Some(ch);
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct USStandardLayout;
-impl Layout for USStandardLayout {
+// A primary and a secondary layout, `KeyModifierState::group` picks the one in use. The groups of
+// the secondary follow those of the primary, so pairs nest to stack more than two layouts.
+pub struct LayoutPair<P, S> {
+    primary: P,
+    secondary: S,
+}
+
+impl<P: Layout, S: Layout> LayoutPair<P, S> {
+    pub const fn new(primary: P, secondary: S) -> Self {
+        return Self { primary, secondary };
+    }
+
+    pub fn primary(&self) -> &P {
+        return &self.primary;
+    }
+
+    pub fn secondary(&self) -> &S {
+        return &self.secondary;
+    }
+
+    // The modifiers with the group counted from the secondary's first, None while the group
+    // belongs to the primary
+    fn secondary_modifiers(&self, modifiers: &KeyModifierState) -> Option<KeyModifierState> {
+        let group = modifiers.group.checked_sub(self.primary.group_count())?;
+        let mut modifiers = *modifiers;
+        modifiers.group = group;
+
+        return Some(modifiers);
+    }
+}
+
+impl<P: Layout, S: Layout> Layout for LayoutPair<P, S> {
     fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
-        use ScanType::*;
-        let ch;
-
-        if modifiers.shift_down() {
-            // Shift is being held down
-
-            ch = match key.scan_type() {
-                // Check if the character is on the keypad
-                CharA => 'A',
-                CharB => 'B',
-                CharC => 'C',
-                CharD => 'D',
-                CharE => 'E',
-                CharF => 'F',
-                CharG => 'G',
-                CharH => 'H',
-                CharI => 'I',
-                CharJ => 'J',
-                CharK => 'K',
-                CharL => 'L',
-                CharM => 'M',
-                CharN => 'N',
-                CharO => 'O',
-                CharP => 'P',
-                CharQ => 'Q',
-                CharR => 'R',
-                CharS => 'S',
-                CharT => 'T',
-                CharU => 'U',
-                CharV => 'V',
-                CharW => 'W',
-                CharX => 'X',
-                CharY => 'Y',
-                CharZ => 'Z',
-                SymbolPlus => '+', // This is a symbol on the keypad
-                SymbolMinus => '_',
-                SymbolEquals => '+',
-                SymbolOpenSquareBracket => '{',
-                SymbolCloseSquareBracket => '}',
-                SymbolSemicolon => ':',
-                SymbolSingleQuote => '\"',
-                SymbolBacktick => '~',
-                SymbolBackslash => '|',
-                SymbolComma => '<',
-                SymbolPeriod => '>',
-                SymbolForwardSlash => '?',
-                SymbolAsterisk => '*', // This is a symbol on the keypad
-                Space => ' ',
-                Tab => '\t',
-                _ => {
-                    if key.keypad() {
-                        match key.scan_type() {
-                            // Ignore numbers when shifted on the keypad
-                            _ => return None,
-                        }
-                    } else {
-                        match key.scan_type() {
-                            Num0 => ')',
-                            Num1 => '!',
-                            Num2 => '@',
-                            Num3 => '#',
-                            Num4 => '$',
-                            Num5 => '%',
-                            Num6 => '^',
-                            Num7 => '&',
-                            Num8 => '*',
-                            Num9 => '(',
-                            _ => return None,
-                        }
-                    }
-                }
-            };
-        } else {
-            ch = match key.scan_type() {
-                Num0 => '0',
-                Num1 => '1',
-                Num2 => '2',
-                Num3 => '3',
-                Num4 => '4',
-                Num5 => '5',
-                Num6 => '6',
-                Num7 => '7',
-                Num8 => '8',
-                Num9 => '9',
-                CharA => 'a',
-                CharB => 'b',
-                CharC => 'c',
-                CharD => 'd',
-                CharE => 'e',
-                CharF => 'f',
-                CharG => 'g',
-                CharH => 'h',
-                CharI => 'i',
-                CharJ => 'j',
-                CharK => 'k',
-                CharL => 'l',
-                CharM => 'm',
-                CharN => 'n',
-                CharO => 'o',
-                CharP => 'p',
-                CharQ => 'q',
-                CharR => 'r',
-                CharS => 's',
-                CharT => 't',
-                CharU => 'u',
-                CharV => 'v',
-                CharW => 'w',
-                CharX => 'x',
-                CharY => 'y',
-                CharZ => 'z',
-                SymbolPlus => '+', // This is a symbol on the keypad
-                SymbolMinus => '-',
-                SymbolEquals => '=',
-                SymbolOpenSquareBracket => '[',
-                SymbolCloseSquareBracket => ']',
-                SymbolSemicolon => ';',
-                SymbolSingleQuote => '\'',
-                SymbolBacktick => '`',
-                SymbolBackslash => '\\',
-                SymbolComma => ',',
-                SymbolPeriod => '.',
-                SymbolForwardSlash => '/',
-                SymbolAsterisk => '*', // This is a symbol on the keypad
-                Space => ' ',
-                Tab => '\t',
-                _ => return None,
-            };
-        }
+        return match self.secondary_modifiers(modifiers) {
+            Some(modifiers) => self.secondary.key_into_char(&modifiers, key),
+            None => self.primary.key_into_char(modifiers, key),
+        };
+    }
 
-        if modifiers.caps_lock && ch.is_alphabetic() {
-            // Invert case
-            if ch.is_ascii_lowercase() {
-                return Some(ch.to_ascii_uppercase());
-            } else if ch.is_ascii_uppercase() {
-                // If for some reason ch is not ASCII we ignore it.
-                return Some(ch.to_ascii_lowercase());
-            }
-        }
+    fn has_alt_gr(&self) -> bool {
+        return self.primary.has_alt_gr() || self.secondary.has_alt_gr();
+    }
+
+    fn group_count(&self) -> u8 {
+        return self
+            .primary
+            .group_count()
+            .saturating_add(self.secondary.group_count());
+    }
+
+    fn group_has_alt_gr(&self, group: u8) -> bool {
+        return match group.checked_sub(self.primary.group_count()) {
+            Some(group) => self.secondary.group_has_alt_gr(group),
+            None => self.primary.group_has_alt_gr(group),
+        };
+    }
 
-        return Some(ch);
+    fn dead_key(&self, modifiers: &KeyModifierState, key: Key) -> Option<DeadKey> {
+        return match self.secondary_modifiers(modifiers) {
+            Some(modifiers) => self.secondary.dead_key(&modifiers, key),
+            None => self.primary.dead_key(modifiers, key),
+        };
+    }
+
+    fn compose(&self, modifiers: &KeyModifierState, dead_key: DeadKey, ch: char) -> Option<char> {
+        return match self.secondary_modifiers(modifiers) {
+            Some(modifiers) => self.secondary.compose(&modifiers, dead_key, ch),
+            None => self.primary.compose(modifiers, dead_key, ch),
+        };
     }
 }
@@ -0,0 +1,93 @@
+use super::KEYPAD_ROWS;
+
+// The Arabic (101) layout. Shift gives the harakat and the lam-alef ligatures, which are
+// produced as their single presentation form characters.
+crate::layout! {
+    pub struct ArabicLayout;
+    alt_gr: false;
+    keys {
+        SymbolBacktick => ('ذ', '\u{651}', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '@', _, _, Ignore),
+        Num3 => ('3', '#', _, _, Ignore),
+        Num4 => ('4', '$', _, _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', '^', _, _, Ignore),
+        Num7 => ('7', '&', _, _, Ignore),
+        Num8 => ('8', '*', _, _, Ignore),
+        Num9 => ('9', '(', _, _, Ignore),
+        Num0 => ('0', ')', _, _, Ignore),
+        SymbolMinus => ('-', '_', _, _, Ignore),
+        SymbolEquals => ('=', '+', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('ض', '\u{64e}', _, _, Ignore),
+        CharW => ('ص', '\u{64b}', _, _, Ignore),
+        CharE => ('ث', '\u{64f}', _, _, Ignore),
+        CharR => ('ق', '\u{64c}', _, _, Ignore),
+        CharT => ('ف', 'ﻹ', _, _, Ignore),
+        CharY => ('غ', 'إ', _, _, Ignore),
+        CharU => ('ع', '‘', _, _, Ignore),
+        CharI => ('ه', '÷', _, _, Ignore),
+        CharO => ('خ', '×', _, _, Ignore),
+        CharP => ('ح', '؛', _, _, Ignore),
+        SymbolOpenSquareBracket => ('ج', '<', _, _, Ignore),
+        SymbolCloseSquareBracket => ('د', '>', _, _, Ignore),
+        CharA => ('ش', '\u{650}', _, _, Ignore),
+        CharS => ('س', '\u{64d}', _, _, Ignore),
+        CharD => ('ي', ']', _, _, Ignore),
+        CharF => ('ب', '[', _, _, Ignore),
+        CharG => ('ل', 'ﻷ', _, _, Ignore),
+        CharH => ('ا', 'أ', _, _, Ignore),
+        CharJ => ('ت', 'ـ', _, _, Ignore),
+        CharK => ('ن', '،', _, _, Ignore),
+        CharL => ('م', '/', _, _, Ignore),
+        SymbolSemicolon => ('ك', ':', _, _, Ignore),
+        SymbolSingleQuote => ('ط', '"', _, _, Ignore),
+        SymbolBackslash => ('\\', '|', _, _, Ignore),
+        CharZ => ('ئ', '~', _, _, Ignore),
+        CharX => ('ء', '\u{652}', _, _, Ignore),
+        CharC => ('ؤ', '}', _, _, Ignore),
+        CharV => ('ر', '{', _, _, Ignore),
+        CharB => ('ﻻ', 'ﻵ', _, _, Ignore),
+        CharN => ('ى', 'آ', _, _, Ignore),
+        CharM => ('ة', '’', _, _, Ignore),
+        SymbolComma => ('و', ',', _, _, Ignore),
+        SymbolPeriod => ('ز', '.', _, _, Ignore),
+        SymbolForwardSlash => ('ظ', '؟', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        let layout = ArabicLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, "ذ1234567890-=");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "ضصثقفغعهخحجد");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "شسيبلاتنمكط\\");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW[2..], "][ﻷأـ،/:\"|");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW[1..], "ئءؤرﻻىةوزظ");
+        assert_legends(
+            layout,
+            KeyLevel::Shift,
+            &BOTTOM_ROW[1..],
+            "~\u{652}}{ﻵآ’,.؟",
+        );
+    }
+
+    #[test]
+    fn test_caps_lock_has_no_effect() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, ArabicLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x10), Some('ض'));
+    }
+}
@@ -0,0 +1,116 @@
+use super::KEYPAD_ROWS;
+
+// The French AZERTY layout. The digits are on the shifted number row and Caps Lock only
+// affects the letters.
+crate::layout! {
+    pub struct FrenchAzertyLayout;
+    alt_gr: true;
+    keys {
+        SymbolBacktick => ('²', _, _, _, Ignore),
+        Num1 => ('&', '1', _, _, Ignore),
+        Num2 => ('é', '2', Tilde, _, Ignore),
+        Num3 => ('"', '3', '#', _, Ignore),
+        Num4 => ('\'', '4', '{', _, Ignore),
+        Num5 => ('(', '5', '[', _, Ignore),
+        Num6 => ('-', '6', '|', _, Ignore),
+        Num7 => ('è', '7', Grave, _, Ignore),
+        Num8 => ('_', '8', '\\', _, Ignore),
+        Num9 => ('ç', '9', '^', _, Ignore),
+        Num0 => ('à', '0', '@', _, Ignore),
+        SymbolMinus => (')', '°', ']', _, Ignore),
+        SymbolEquals => ('=', '+', '}', _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('a', 'A', _, _, Auto),
+        CharW => ('z', 'Z', _, _, Auto),
+        CharE => ('e', 'E', '€', _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => (Circumflex, Diaeresis, _, _, Ignore),
+        SymbolCloseSquareBracket => ('$', '£', '¤', _, Ignore),
+        CharA => ('q', 'Q', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => ('m', 'M', _, _, Auto),
+        SymbolSingleQuote => ('ù', '%', _, _, Ignore),
+        SymbolBackslash => ('*', 'µ', _, _, Ignore),
+        Iso102nd => ('<', '>', _, _, Ignore),
+        CharZ => ('w', 'W', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => (',', '?', _, _, Ignore),
+        SymbolComma => (';', '.', _, _, Ignore),
+        SymbolPeriod => (':', '/', _, _, Ignore),
+        SymbolForwardSlash => ('!', '§', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        let layout = FrenchAzertyLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, "²&é\"'(-è_çà)=");
+        assert_legends(layout, KeyLevel::Shift, &NUMBER_ROW[1..], "1234567890°+");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "azertyuiop^$");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, "AZERTYUIOP¨£");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "qsdfghjklmù*");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW, "QSDFGHJKLM%µ");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW, "<wxcvbn,;:!");
+        assert_legends(layout, KeyLevel::Shift, &BOTTOM_ROW, ">WXCVBN?./§");
+    }
+
+    #[test]
+    fn test_alt_gr() {
+        assert_legends(
+            FrenchAzertyLayout,
+            KeyLevel::AltGr,
+            &NUMBER_ROW[2..],
+            "~#{[|`\\^@]}",
+        );
+        assert_legends(FrenchAzertyLayout, KeyLevel::AltGr, &[0x12, 0x1b], "€¤");
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, FrenchAzertyLayout);
+
+        assert_eq!(press(&mut key_board, 0x1a), None);
+        assert_eq!(press(&mut key_board, 0x18), Some('ô'));
+
+        key_board.input_byte(0x2a);
+        assert_eq!(press(&mut key_board, 0x1a), None);
+        key_board.input_byte(0xaa);
+        assert_eq!(press(&mut key_board, 0x12), Some('ë'));
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, FrenchAzertyLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x10), Some('A'));
+        assert_eq!(press(&mut key_board, 0x03), Some('é'));
+    }
+}
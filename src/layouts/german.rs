@@ -0,0 +1,126 @@
+use super::KEYPAD_ROWS;
+
+// The German QWERTZ layout with dead ^, ´ and ` keys
+crate::layout! {
+    pub struct GermanQwertzLayout;
+    alt_gr: true;
+    keys {
+        SymbolBacktick => (Circumflex, '°', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '"', '²', _, Ignore),
+        Num3 => ('3', '§', '³', _, Ignore),
+        Num4 => ('4', '$', _, _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', '&', _, _, Ignore),
+        Num7 => ('7', '/', '{', _, Ignore),
+        Num8 => ('8', '(', '[', _, Ignore),
+        Num9 => ('9', ')', ']', _, Ignore),
+        Num0 => ('0', '=', '}', _, Ignore),
+        SymbolMinus => ('ß', '?', '\\', 'ẞ', Ignore),
+        SymbolEquals => (Acute, Grave, _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('q', 'Q', '@', _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharE => ('e', 'E', '€', _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('z', 'Z', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => ('ü', 'Ü', _, _, Auto),
+        SymbolCloseSquareBracket => ('+', '*', '~', _, Ignore),
+        CharA => ('a', 'A', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => ('ö', 'Ö', _, _, Auto),
+        SymbolSingleQuote => ('ä', 'Ä', _, _, Auto),
+        SymbolBackslash => ('#', '\'', _, _, Ignore),
+        Iso102nd => ('<', '>', '|', _, Ignore),
+        CharZ => ('y', 'Y', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => ('m', 'M', 'µ', _, Auto),
+        SymbolComma => (',', ';', _, _, Ignore),
+        SymbolPeriod => ('.', ':', _, _, Ignore),
+        SymbolForwardSlash => ('-', '_', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        let layout = GermanQwertzLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, "^1234567890ß´");
+        assert_legends(layout, KeyLevel::Shift, &NUMBER_ROW, "°!\"§$%&/()=?`");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "qwertzuiopü+");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, "QWERTZUIOPÜ*");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "asdfghjklöä#");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW, "ASDFGHJKLÖÄ'");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW, "<yxcvbnm,.-");
+        assert_legends(layout, KeyLevel::Shift, &BOTTOM_ROW, ">YXCVBNM;:_");
+    }
+
+    #[test]
+    fn test_alt_gr() {
+        let codes = [
+            0x03, 0x04, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x10, 0x12, 0x1b, 0x56, 0x32,
+        ];
+
+        assert_legends(GermanQwertzLayout, KeyLevel::AltGr, &codes, "²³{[]}\\@€~|µ");
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, GermanQwertzLayout);
+
+        assert_eq!(press(&mut key_board, 0x29), None);
+        assert_eq!(press(&mut key_board, 0x12), Some('ê'));
+        assert_eq!(press(&mut key_board, 0x0d), None);
+        assert_eq!(press(&mut key_board, 0x12), Some('é'));
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, GermanQwertzLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x27), Some('Ö'));
+        assert_eq!(press(&mut key_board, 0x2c), Some('Y'));
+        assert_eq!(press(&mut key_board, 0x0c), Some('ß'));
+    }
+
+    #[test]
+    fn test_keypad() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, GermanQwertzLayout);
+        press(&mut key_board, 0x45);
+
+        assert_eq!(press(&mut key_board, 0x4a), Some('-'));
+        assert_eq!(press(&mut key_board, 0x53), Some('.'));
+        assert_eq!(press(&mut key_board, 0x39), Some(' '));
+
+        key_board.input_byte(0xe0);
+        key_board.input_byte(0x38);
+        assert_eq!(press(&mut key_board, 0x4f), Some('1'));
+        assert_eq!(press(&mut key_board, 0x39), Some(' '));
+    }
+}
@@ -0,0 +1,105 @@
+use super::KEYPAD_ROWS;
+
+// The Greek layout. The key right of L is the dead tonos (acute) and, shifted, the dialytika
+// (diaeresis). Final sigma sits on W and becomes Σ with Shift or Caps Lock.
+crate::layout! {
+    pub struct GreekLayout;
+    alt_gr: true;
+    keys {
+        SymbolBacktick => ('`', '~', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '@', '²', _, Ignore),
+        Num3 => ('3', '#', '³', _, Ignore),
+        Num4 => ('4', '$', _, _, Ignore),
+        Num5 => ('5', '%', '§', _, Ignore),
+        Num6 => ('6', '^', _, _, Ignore),
+        Num7 => ('7', '&', _, _, Ignore),
+        Num8 => ('8', '*', _, _, Ignore),
+        Num9 => ('9', '(', _, _, Ignore),
+        Num0 => ('0', ')', _, _, Ignore),
+        SymbolMinus => ('-', '_', _, _, Ignore),
+        SymbolEquals => ('=', '+', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => (';', ':', _, _, Ignore),
+        CharW => ('ς', 'Σ', _, _, Auto),
+        CharE => ('ε', 'Ε', '€', _, Auto),
+        CharR => ('ρ', 'Ρ', _, _, Auto),
+        CharT => ('τ', 'Τ', _, _, Auto),
+        CharY => ('υ', 'Υ', _, _, Auto),
+        CharU => ('θ', 'Θ', _, _, Auto),
+        CharI => ('ι', 'Ι', _, _, Auto),
+        CharO => ('ο', 'Ο', _, _, Auto),
+        CharP => ('π', 'Π', _, _, Auto),
+        SymbolOpenSquareBracket => ('[', '{', _, _, Ignore),
+        SymbolCloseSquareBracket => (']', '}', _, _, Ignore),
+        CharA => ('α', 'Α', _, _, Auto),
+        CharS => ('σ', 'Σ', _, _, Auto),
+        CharD => ('δ', 'Δ', _, _, Auto),
+        CharF => ('φ', 'Φ', _, _, Auto),
+        CharG => ('γ', 'Γ', _, _, Auto),
+        CharH => ('η', 'Η', _, _, Auto),
+        CharJ => ('ξ', 'Ξ', _, _, Auto),
+        CharK => ('κ', 'Κ', _, _, Auto),
+        CharL => ('λ', 'Λ', _, _, Auto),
+        SymbolSemicolon => (Acute, Diaeresis, _, _, Ignore),
+        SymbolSingleQuote => ('\'', '"', _, _, Ignore),
+        SymbolBackslash => ('\\', '|', _, _, Ignore),
+        CharZ => ('ζ', 'Ζ', _, _, Auto),
+        CharX => ('χ', 'Χ', _, _, Auto),
+        CharC => ('ψ', 'Ψ', _, _, Auto),
+        CharV => ('ω', 'Ω', _, _, Auto),
+        CharB => ('β', 'Β', _, _, Auto),
+        CharN => ('ν', 'Ν', _, _, Auto),
+        CharM => ('μ', 'Μ', _, _, Auto),
+        SymbolComma => (',', '<', _, _, Ignore),
+        SymbolPeriod => ('.', '>', _, _, Ignore),
+        SymbolForwardSlash => ('/', '?', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        let layout = GreekLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, "`1234567890-=");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, ";ςερτυθιοπ[]");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, ":ΣΕΡΤΥΘΙΟΠ{}");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "ασδφγηξκλ´'\\");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW, "ΑΣΔΦΓΗΞΚΛ¨\"|");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW[1..], "ζχψωβνμ,./");
+        assert_legends(layout, KeyLevel::Shift, &BOTTOM_ROW[1..], "ΖΧΨΩΒΝΜ<>?");
+        assert_legends(layout, KeyLevel::AltGr, &[0x03, 0x04, 0x12], "²³€");
+    }
+
+    #[test]
+    fn test_tonos() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, GreekLayout);
+
+        assert_eq!(press(&mut key_board, 0x27), None);
+        assert_eq!(press(&mut key_board, 0x1e), Some('ά'));
+
+        key_board.input_byte(0x2a);
+        assert_eq!(press(&mut key_board, 0x27), None);
+        key_board.input_byte(0xaa);
+        assert_eq!(press(&mut key_board, 0x17), Some('ϊ'));
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, GreekLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x11), Some('Σ'));
+        assert_eq!(press(&mut key_board, 0x1e), Some('Α'));
+        assert_eq!(press(&mut key_board, 0x10), Some(';'));
+    }
+}
@@ -0,0 +1,90 @@
+use super::KEYPAD_ROWS;
+
+// The standard Hebrew layout. Hebrew has no case so Shift on a letter gives the Latin capital
+// of the key and Caps Lock does nothing. Brackets are mirrored for right to left text.
+crate::layout! {
+    pub struct HebrewLayout;
+    alt_gr: true;
+    keys {
+        SymbolBacktick => (';', '~', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '@', _, _, Ignore),
+        Num3 => ('3', '#', _, _, Ignore),
+        Num4 => ('4', '$', '₪', _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', '^', _, _, Ignore),
+        Num7 => ('7', '&', _, _, Ignore),
+        Num8 => ('8', '*', _, _, Ignore),
+        Num9 => ('9', '(', _, _, Ignore),
+        Num0 => ('0', ')', _, _, Ignore),
+        SymbolMinus => ('-', '_', _, _, Ignore),
+        SymbolEquals => ('=', '+', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('/', 'Q', _, _, Ignore),
+        CharW => ('\'', 'W', _, _, Ignore),
+        CharE => ('ק', 'E', '€', _, Ignore),
+        CharR => ('ר', 'R', _, _, Ignore),
+        CharT => ('א', 'T', _, _, Ignore),
+        CharY => ('ט', 'Y', _, _, Ignore),
+        CharU => ('ו', 'U', _, _, Ignore),
+        CharI => ('ן', 'I', _, _, Ignore),
+        CharO => ('ם', 'O', _, _, Ignore),
+        CharP => ('פ', 'P', _, _, Ignore),
+        SymbolOpenSquareBracket => (']', '}', _, _, Ignore),
+        SymbolCloseSquareBracket => ('[', '{', _, _, Ignore),
+        CharA => ('ש', 'A', _, _, Ignore),
+        CharS => ('ד', 'S', _, _, Ignore),
+        CharD => ('ג', 'D', _, _, Ignore),
+        CharF => ('כ', 'F', _, _, Ignore),
+        CharG => ('ע', 'G', _, _, Ignore),
+        CharH => ('י', 'H', _, _, Ignore),
+        CharJ => ('ח', 'J', _, _, Ignore),
+        CharK => ('ל', 'K', _, _, Ignore),
+        CharL => ('ך', 'L', _, _, Ignore),
+        SymbolSemicolon => ('ף', ':', _, _, Ignore),
+        SymbolSingleQuote => (',', '"', _, _, Ignore),
+        SymbolBackslash => ('\\', '|', _, _, Ignore),
+        Iso102nd => ('\\', '|', _, _, Ignore),
+        CharZ => ('ז', 'Z', _, _, Ignore),
+        CharX => ('ס', 'X', _, _, Ignore),
+        CharC => ('ב', 'C', _, _, Ignore),
+        CharV => ('ה', 'V', _, _, Ignore),
+        CharB => ('נ', 'B', _, _, Ignore),
+        CharN => ('מ', 'N', _, _, Ignore),
+        CharM => ('צ', 'M', _, _, Ignore),
+        SymbolComma => ('ת', '>', _, _, Ignore),
+        SymbolPeriod => ('ץ', '<', _, _, Ignore),
+        SymbolForwardSlash => ('.', '?', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        let layout = HebrewLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, ";1234567890-=");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "/'קראטוןםפ][");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, "QWERTYUIOP}{");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "שדגכעיחלךף,\\");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW, "\\זסבהנמצתץ.");
+        assert_legends(layout, KeyLevel::Shift, &BOTTOM_ROW, "|ZXCVBNM><?");
+        assert_legends(layout, KeyLevel::AltGr, &[0x05, 0x12], "₪€");
+    }
+
+    #[test]
+    fn test_caps_lock_has_no_effect() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, HebrewLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x1e), Some('ש'));
+    }
+}
@@ -0,0 +1,208 @@
+use super::super::layout::{KeyModifierState, Layout};
+use super::super::static_layout::StaticLayout;
+use super::super::Key;
+use super::KEYPAD_ROWS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The Japanese JIS 109 layout. While kana lock is on (the Katakana/Hiragana key) the keys type
+// hiragana directly from the kana legends. Zenkaku/Hankaku, Henkan and Muhenkan are left to the
+// input method and produce no character.
+pub struct JisLayout;
+
+const JIS: StaticLayout<'static> = StaticLayout::new(
+    &crate::__layout_rows!(
+        // Zenkaku/Hankaku
+        SymbolBacktick => (_, _, _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '"', _, _, Ignore),
+        Num3 => ('3', '#', _, _, Ignore),
+        Num4 => ('4', '$', _, _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', '&', _, _, Ignore),
+        Num7 => ('7', '\'', _, _, Ignore),
+        Num8 => ('8', '(', _, _, Ignore),
+        Num9 => ('9', ')', _, _, Ignore),
+        Num0 => ('0', _, _, _, Ignore),
+        SymbolMinus => ('-', '=', _, _, Ignore),
+        SymbolEquals => ('^', '~', _, _, Ignore),
+        Yen => ('¥', '|', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharE => ('e', 'E', _, _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => ('@', '`', _, _, Ignore),
+        SymbolCloseSquareBracket => ('[', '{', _, _, Ignore),
+        CharA => ('a', 'A', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => (';', '+', _, _, Ignore),
+        SymbolSingleQuote => (':', '*', _, _, Ignore),
+        SymbolBackslash => (']', '}', _, _, Ignore),
+        CharZ => ('z', 'Z', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => ('m', 'M', _, _, Auto),
+        SymbolComma => (',', '<', _, _, Ignore),
+        SymbolPeriod => ('.', '>', _, _, Ignore),
+        SymbolForwardSlash => ('/', '?', _, _, Ignore),
+        Ro => ('\\', '_', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    ),
+    &KEYPAD_ROWS,
+    false,
+);
+
+// Shift gives the small kana and the corner brackets
+const KANA: StaticLayout<'static> = StaticLayout::new(
+    &crate::__layout_rows!(
+        SymbolBacktick => (_, _, _, _, Ignore),
+        Num1 => ('ぬ', 'ぬ', _, _, Ignore),
+        Num2 => ('ふ', 'ふ', _, _, Ignore),
+        Num3 => ('あ', 'ぁ', _, _, Ignore),
+        Num4 => ('う', 'ぅ', _, _, Ignore),
+        Num5 => ('え', 'ぇ', _, _, Ignore),
+        Num6 => ('お', 'ぉ', _, _, Ignore),
+        Num7 => ('や', 'ゃ', _, _, Ignore),
+        Num8 => ('ゆ', 'ゅ', _, _, Ignore),
+        Num9 => ('よ', 'ょ', _, _, Ignore),
+        Num0 => ('わ', 'を', _, _, Ignore),
+        SymbolMinus => ('ほ', 'ほ', _, _, Ignore),
+        SymbolEquals => ('へ', 'へ', _, _, Ignore),
+        Yen => ('ー', 'ー', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('た', 'た', _, _, Ignore),
+        CharW => ('て', 'て', _, _, Ignore),
+        CharE => ('い', 'ぃ', _, _, Ignore),
+        CharR => ('す', 'す', _, _, Ignore),
+        CharT => ('か', 'か', _, _, Ignore),
+        CharY => ('ん', 'ん', _, _, Ignore),
+        CharU => ('な', 'な', _, _, Ignore),
+        CharI => ('に', 'に', _, _, Ignore),
+        CharO => ('ら', 'ら', _, _, Ignore),
+        CharP => ('せ', 'せ', _, _, Ignore),
+        SymbolOpenSquareBracket => ('゛', '゛', _, _, Ignore),
+        SymbolCloseSquareBracket => ('゜', '「', _, _, Ignore),
+        CharA => ('ち', 'ち', _, _, Ignore),
+        CharS => ('と', 'と', _, _, Ignore),
+        CharD => ('し', 'し', _, _, Ignore),
+        CharF => ('は', 'は', _, _, Ignore),
+        CharG => ('き', 'き', _, _, Ignore),
+        CharH => ('く', 'く', _, _, Ignore),
+        CharJ => ('ま', 'ま', _, _, Ignore),
+        CharK => ('の', 'の', _, _, Ignore),
+        CharL => ('り', 'り', _, _, Ignore),
+        SymbolSemicolon => ('れ', 'れ', _, _, Ignore),
+        SymbolSingleQuote => ('け', 'け', _, _, Ignore),
+        SymbolBackslash => ('む', '」', _, _, Ignore),
+        CharZ => ('つ', 'っ', _, _, Ignore),
+        CharX => ('さ', 'さ', _, _, Ignore),
+        CharC => ('そ', 'そ', _, _, Ignore),
+        CharV => ('ひ', 'ひ', _, _, Ignore),
+        CharB => ('こ', 'こ', _, _, Ignore),
+        CharN => ('み', 'み', _, _, Ignore),
+        CharM => ('も', 'も', _, _, Ignore),
+        SymbolComma => ('ね', '、', _, _, Ignore),
+        SymbolPeriod => ('る', '。', _, _, Ignore),
+        SymbolForwardSlash => ('め', '・', _, _, Ignore),
+        Ro => ('ろ', 'ろ', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    ),
+    &KEYPAD_ROWS,
+    false,
+);
+
+impl Layout for JisLayout {
+    fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+        if modifiers.kana_lock {
+            return KANA.key_into_char(modifiers, key);
+        }
+
+        return JIS.key_into_char(modifiers, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    const JIS_NUMBER_ROW: [u8; 13] = [
+        0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x7d,
+    ];
+    const JIS_BOTTOM_ROW: [u8; 11] = [
+        0x2c, 0x2d, 0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x73,
+    ];
+
+    #[test]
+    fn test_legends() {
+        assert_legends(JisLayout, KeyLevel::Base, &JIS_NUMBER_ROW, "1234567890-^¥");
+        assert_legends(
+            JisLayout,
+            KeyLevel::Shift,
+            &JIS_NUMBER_ROW[..9],
+            "!\"#$%&'()",
+        );
+        assert_legends(JisLayout, KeyLevel::Shift, &JIS_NUMBER_ROW[10..], "=~|");
+        assert_legends(JisLayout, KeyLevel::Base, &TOP_ROW, "qwertyuiop@[");
+        assert_legends(JisLayout, KeyLevel::Shift, &TOP_ROW, "QWERTYUIOP`{");
+        assert_legends(JisLayout, KeyLevel::Base, &HOME_ROW, "asdfghjkl;:]");
+        assert_legends(JisLayout, KeyLevel::Shift, &HOME_ROW, "ASDFGHJKL+*}");
+        assert_legends(JisLayout, KeyLevel::Base, &JIS_BOTTOM_ROW, "zxcvbnm,./\\");
+        assert_legends(JisLayout, KeyLevel::Shift, &JIS_BOTTOM_ROW, "ZXCVBNM<>?_");
+    }
+
+    #[test]
+    fn test_kana_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, JisLayout);
+
+        assert_eq!(press(&mut key_board, 0x10), Some('q'));
+
+        assert_eq!(press(&mut key_board, 0x70), None); // Katakana/Hiragana
+        assert!(key_board.current_state().kana_lock);
+
+        let expected = "たていすかんなにらせ゛゜";
+        for (code, kana) in TOP_ROW.iter().zip(expected.chars()) {
+            assert_eq!(press(&mut key_board, *code), Some(kana));
+        }
+
+        assert_eq!(press(&mut key_board, 0x73), Some('ろ'));
+        assert_eq!(press(&mut key_board, 0x7d), Some('ー'));
+        assert_eq!(press(&mut key_board, 0x39), Some(' '));
+
+        key_board.input_byte(0x2a);
+        assert_eq!(press(&mut key_board, 0x2c), Some('っ'));
+        assert_eq!(press(&mut key_board, 0x33), Some('、'));
+        key_board.input_byte(0xaa);
+
+        press(&mut key_board, 0x70);
+        assert!(!key_board.current_state().kana_lock);
+        assert_eq!(press(&mut key_board, 0x10), Some('q'));
+    }
+
+    #[test]
+    fn test_ime_keys_have_no_character() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, JisLayout);
+
+        for code in [0x29, 0x79, 0x7b].iter() {
+            assert_eq!(press(&mut key_board, *code), None);
+        }
+    }
+}
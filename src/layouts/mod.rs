@@ -0,0 +1,202 @@
+use super::dead_key::DeadKey;
+use super::keymap::KeymapEntry;
+use super::layout::{invert_case, KeyLevel, KeyModifierState, Layout, USStandardLayout};
+use super::{Key, ScanType};
+
+mod arabic;
+mod french;
+mod german;
+mod greek;
+mod hebrew;
+mod jis;
+mod nordic;
+mod russian;
+mod spanish;
+mod uk;
+mod us_variants;
+
+pub use arabic::ArabicLayout;
+pub use french::FrenchAzertyLayout;
+pub use german::GermanQwertzLayout;
+pub use greek::GreekLayout;
+pub use hebrew::HebrewLayout;
+pub use jis::JisLayout;
+pub use nordic::{NordicLayout, NordicVariant};
+pub use russian::RussianLayout;
+pub use spanish::SpanishLayout;
+pub use uk::UKLayout;
+pub use us_variants::{ColemakLayout, DvorakLayout, WorkmanLayout};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// What a key produces on one level
+pub enum KeySymbol {
+    Empty,
+    Char(char),
+    Dead(DeadKey),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// How Caps Lock treats a key. Auto flips Shift only when the shifted level is the uppercase of
+// the unshifted one, Shift always flips it and Ignore leaves the key alone.
+pub enum CapsBehaviour {
+    Auto,
+    Shift,
+    Ignore,
+}
+
+// The keypad of the built-in layouts, the same characters on every level but Shift like on the
+// US keypad. Share it with `keypad: KEYPAD_ROWS;` in layout!.
+pub const KEYPAD_ROWS: [KeymapEntry; 15] = crate::__layout_rows!(
+    Num0 => ('0', _, '0', _, Ignore),
+    Num1 => ('1', _, '1', _, Ignore),
+    Num2 => ('2', _, '2', _, Ignore),
+    Num3 => ('3', _, '3', _, Ignore),
+    Num4 => ('4', _, '4', _, Ignore),
+    Num5 => ('5', _, '5', _, Ignore),
+    Num6 => ('6', _, '6', _, Ignore),
+    Num7 => ('7', _, '7', _, Ignore),
+    Num8 => ('8', _, '8', _, Ignore),
+    Num9 => ('9', _, '9', _, Ignore),
+    SymbolPeriod => ('.', _, '.', _, Ignore),
+    SymbolPlus => ('+', '+', '+', '+', Ignore),
+    SymbolMinus => ('-', '-', '-', '-', Ignore),
+    SymbolAsterisk => ('*', '*', '*', '*', Ignore),
+    SymbolForwardSlash => ('/', '/', '/', '/', Ignore),
+);
+
+// Letters that aren't listed keep their QWERTY position and have nothing on AltGr
+pub(crate) fn qwerty_row(scan_type: ScanType) -> Option<[KeySymbol; 4]> {
+    if scan_type.is_letter() {
+        let lower = (b'a' + scan_type.as_u8() - ScanType::CharA.as_u8()) as char;
+
+        return Some([
+            KeySymbol::Char(lower),
+            KeySymbol::Char(lower.to_ascii_uppercase()),
+            KeySymbol::Empty,
+            KeySymbol::Empty,
+        ]);
+    }
+
+    return None;
+}
+
+// Caps Lock only affects a pair of levels when the shifted one is the uppercase of the other
+fn is_case_pair(lower: KeySymbol, upper: KeySymbol) -> bool {
+    if let (KeySymbol::Char(lower), KeySymbol::Char(upper)) = (lower, upper) {
+        return lower.is_lowercase() && invert_case(lower) == upper;
+    }
+
+    return false;
+}
+
+// Picks the level of a key the modifiers select
+pub(crate) fn level_symbol(
+    levels: &[KeySymbol; 4],
+    caps: CapsBehaviour,
+    modifiers: &KeyModifierState,
+) -> KeySymbol {
+    let level = modifiers.level();
+    let base = if level.is_alt_gr() {
+        KeyLevel::AltGr.index()
+    } else {
+        KeyLevel::Base.index()
+    };
+
+    let mut shifted = level.is_shifted();
+
+    if modifiers.caps_lock {
+        shifted ^= match caps {
+            CapsBehaviour::Auto => is_case_pair(levels[base], levels[base + 1]),
+            CapsBehaviour::Shift => true,
+            CapsBehaviour::Ignore => false,
+        };
+    }
+
+    return levels[base + shifted as usize];
+}
+
+// The character for a symbol picked from a table. Keys the table doesn't cover (space, tab, the
+// keypad) behave as on the US layout.
+pub(crate) fn symbol_into_char(
+    symbol: Option<KeySymbol>,
+    modifiers: &KeyModifierState,
+    key: Key,
+) -> Option<char> {
+    return match symbol {
+        Some(KeySymbol::Char(ch)) => Some(ch),
+        Some(_) => None,
+        None if modifiers.alt_gr && !key.keypad() => match key.scan_type() {
+            ScanType::Space | ScanType::Tab => USStandardLayout.key_into_char(modifiers, key),
+            _ => None,
+        },
+        None => USStandardLayout.key_into_char(modifiers, key),
+    };
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::super::{Keyboard, ReaderMode};
+    use super::*;
+
+    pub(crate) fn press<T: Layout>(key_board: &mut Keyboard<T>, code: u8) -> Option<char> {
+        let ch = key_board.input_byte(code);
+        key_board.input_byte(code | 0x80);
+
+        return ch;
+    }
+
+    // A dead key's legend is typed by following it with space
+    fn type_legend<T: Layout>(key_board: &mut Keyboard<T>, code: u8) -> Option<char> {
+        let ch = press(key_board, code);
+
+        if ch.is_none() && key_board.pending_dead_key().is_some() {
+            return press(key_board, 0x39);
+        }
+
+        return ch;
+    }
+
+    // Types each set 1 code on the given level and checks it produces the legend below it
+    pub(crate) fn assert_legends<T: Layout>(
+        layout: T,
+        level: KeyLevel,
+        codes: &[u8],
+        legends: &str,
+    ) {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, layout);
+
+        assert_eq!(codes.len(), legends.chars().count());
+
+        if level.is_alt_gr() {
+            key_board.input_byte(0xe0);
+            key_board.input_byte(0x38);
+        }
+
+        if level.is_shifted() {
+            key_board.input_byte(0x2a);
+        }
+
+        for (code, legend) in codes.iter().zip(legends.chars()) {
+            assert_eq!(
+                type_legend(&mut key_board, *code),
+                Some(legend),
+                "code {:#x}",
+                code
+            );
+        }
+    }
+
+    // The set 1 codes of each row, left to right
+    pub(crate) const NUMBER_ROW: [u8; 13] = [
+        0x29, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+    ];
+    pub(crate) const TOP_ROW: [u8; 12] = [
+        0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+    ];
+    pub(crate) const HOME_ROW: [u8; 12] = [
+        0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x2b,
+    ];
+    pub(crate) const BOTTOM_ROW: [u8; 11] = [
+        0x56, 0x2c, 0x2d, 0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35,
+    ];
+}
@@ -0,0 +1,292 @@
+use super::super::dead_key::DeadKey;
+use super::super::layout::{KeyModifierState, Layout};
+use super::super::static_layout::StaticLayout;
+use super::super::Key;
+use super::KEYPAD_ROWS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NordicVariant {
+    Swedish,
+    // Identical to Swedish
+    Finnish,
+    Norwegian,
+    Danish,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The Nordic layouts share most of their keys, the variants differ around Enter and on the
+// number row.
+pub struct NordicLayout {
+    variant: NordicVariant,
+}
+
+impl NordicLayout {
+    pub const fn new(variant: NordicVariant) -> Self {
+        return Self { variant };
+    }
+
+    pub fn variant(&self) -> NordicVariant {
+        return self.variant;
+    }
+
+    fn table(&self) -> &'static StaticLayout<'static> {
+        return match self.variant {
+            NordicVariant::Swedish | NordicVariant::Finnish => &SWEDISH,
+            NordicVariant::Norwegian => &NORWEGIAN,
+            NordicVariant::Danish => &DANISH,
+        };
+    }
+}
+
+const SWEDISH: StaticLayout<'static> = StaticLayout::new(
+    &crate::__layout_rows!(
+        SymbolBacktick => ('§', '½', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '"', '@', _, Ignore),
+        Num3 => ('3', '#', '£', _, Ignore),
+        Num4 => ('4', '¤', '$', _, Ignore),
+        Num5 => ('5', '%', '€', _, Ignore),
+        Num6 => ('6', '&', _, _, Ignore),
+        Num7 => ('7', '/', '{', _, Ignore),
+        Num8 => ('8', '(', '[', _, Ignore),
+        Num9 => ('9', ')', ']', _, Ignore),
+        Num0 => ('0', '=', '}', _, Ignore),
+        SymbolMinus => ('+', '?', '\\', _, Ignore),
+        SymbolEquals => (Acute, Grave, _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharE => ('e', 'E', '€', _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => ('å', 'Å', _, _, Auto),
+        SymbolCloseSquareBracket => (Diaeresis, Circumflex, Tilde, _, Ignore),
+        CharA => ('a', 'A', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => ('ö', 'Ö', _, _, Auto),
+        SymbolSingleQuote => ('ä', 'Ä', _, _, Auto),
+        SymbolBackslash => ('\'', '*', _, _, Ignore),
+        Iso102nd => ('<', '>', '|', _, Ignore),
+        CharZ => ('z', 'Z', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => ('m', 'M', 'µ', _, Auto),
+        SymbolComma => (',', ';', _, _, Ignore),
+        SymbolPeriod => ('.', ':', _, _, Ignore),
+        SymbolForwardSlash => ('-', '_', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    ),
+    &KEYPAD_ROWS,
+    true,
+);
+
+const NORWEGIAN: StaticLayout<'static> = StaticLayout::new(
+    &crate::__layout_rows!(
+        SymbolBacktick => ('|', '§', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '"', '@', _, Ignore),
+        Num3 => ('3', '#', '£', _, Ignore),
+        Num4 => ('4', '¤', '$', _, Ignore),
+        Num5 => ('5', '%', '€', _, Ignore),
+        Num6 => ('6', '&', _, _, Ignore),
+        Num7 => ('7', '/', '{', _, Ignore),
+        Num8 => ('8', '(', '[', _, Ignore),
+        Num9 => ('9', ')', ']', _, Ignore),
+        Num0 => ('0', '=', '}', _, Ignore),
+        SymbolMinus => ('+', '?', _, _, Ignore),
+        SymbolEquals => ('\\', Grave, Acute, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharE => ('e', 'E', '€', _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => ('å', 'Å', _, _, Auto),
+        SymbolCloseSquareBracket => (Diaeresis, Circumflex, Tilde, _, Ignore),
+        CharA => ('a', 'A', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => ('ø', 'Ø', _, _, Auto),
+        SymbolSingleQuote => ('æ', 'Æ', _, _, Auto),
+        SymbolBackslash => ('\'', '*', _, _, Ignore),
+        Iso102nd => ('<', '>', _, _, Ignore),
+        CharZ => ('z', 'Z', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => ('m', 'M', 'µ', _, Auto),
+        SymbolComma => (',', ';', _, _, Ignore),
+        SymbolPeriod => ('.', ':', _, _, Ignore),
+        SymbolForwardSlash => ('-', '_', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    ),
+    &KEYPAD_ROWS,
+    true,
+);
+
+const DANISH: StaticLayout<'static> = StaticLayout::new(
+    &crate::__layout_rows!(
+        SymbolBacktick => ('½', '§', _, _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '"', '@', _, Ignore),
+        Num3 => ('3', '#', '£', _, Ignore),
+        Num4 => ('4', '¤', '$', _, Ignore),
+        Num5 => ('5', '%', '€', _, Ignore),
+        Num6 => ('6', '&', _, _, Ignore),
+        Num7 => ('7', '/', '{', _, Ignore),
+        Num8 => ('8', '(', '[', _, Ignore),
+        Num9 => ('9', ')', ']', _, Ignore),
+        Num0 => ('0', '=', '}', _, Ignore),
+        SymbolMinus => ('+', '?', _, _, Ignore),
+        SymbolEquals => (Acute, Grave, '|', _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharE => ('e', 'E', '€', _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => ('å', 'Å', _, _, Auto),
+        SymbolCloseSquareBracket => (Diaeresis, Circumflex, Tilde, _, Ignore),
+        CharA => ('a', 'A', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => ('æ', 'Æ', _, _, Auto),
+        SymbolSingleQuote => ('ø', 'Ø', _, _, Auto),
+        SymbolBackslash => ('\'', '*', _, _, Ignore),
+        Iso102nd => ('<', '>', '\\', _, Ignore),
+        CharZ => ('z', 'Z', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => ('m', 'M', 'µ', _, Auto),
+        SymbolComma => (',', ';', _, _, Ignore),
+        SymbolPeriod => ('.', ':', _, _, Ignore),
+        SymbolForwardSlash => ('-', '_', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    ),
+    &KEYPAD_ROWS,
+    true,
+);
+
+impl Layout for NordicLayout {
+    fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+        return self.table().key_into_char(modifiers, key);
+    }
+
+    fn has_alt_gr(&self) -> bool {
+        return true;
+    }
+
+    fn dead_key(&self, modifiers: &KeyModifierState, key: Key) -> Option<DeadKey> {
+        return self.table().dead_key(modifiers, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    const SWEDISH: NordicLayout = NordicLayout::new(NordicVariant::Swedish);
+    const FINNISH: NordicLayout = NordicLayout::new(NordicVariant::Finnish);
+    const NORWEGIAN: NordicLayout = NordicLayout::new(NordicVariant::Norwegian);
+    const DANISH: NordicLayout = NordicLayout::new(NordicVariant::Danish);
+
+    #[test]
+    fn test_swedish_legends() {
+        for layout in [SWEDISH, FINNISH].iter() {
+            assert_legends(*layout, KeyLevel::Base, &NUMBER_ROW, "§1234567890+´");
+            assert_legends(*layout, KeyLevel::Shift, &NUMBER_ROW, "½!\"#¤%&/()=?`");
+            assert_legends(*layout, KeyLevel::Base, &TOP_ROW, "qwertyuiopå¨");
+            assert_legends(*layout, KeyLevel::Shift, &TOP_ROW, "QWERTYUIOPÅ^");
+            assert_legends(*layout, KeyLevel::Base, &HOME_ROW, "asdfghjklöä'");
+            assert_legends(*layout, KeyLevel::Shift, &HOME_ROW, "ASDFGHJKLÖÄ*");
+            assert_legends(*layout, KeyLevel::Base, &BOTTOM_ROW, "<zxcvbnm,.-");
+            assert_legends(*layout, KeyLevel::Shift, &BOTTOM_ROW, ">ZXCVBNM;:_");
+        }
+
+        let codes = [0x03, 0x04, 0x05, 0x06, 0x08, 0x0c, 0x1b, 0x56];
+        assert_legends(SWEDISH, KeyLevel::AltGr, &codes, "@£$€{\\~|");
+    }
+
+    #[test]
+    fn test_norwegian_legends() {
+        assert_legends(NORWEGIAN, KeyLevel::Base, &NUMBER_ROW, "|1234567890+\\");
+        assert_legends(NORWEGIAN, KeyLevel::Shift, &NUMBER_ROW, "§!\"#¤%&/()=?`");
+        assert_legends(NORWEGIAN, KeyLevel::Base, &HOME_ROW, "asdfghjkløæ'");
+        assert_legends(NORWEGIAN, KeyLevel::Shift, &HOME_ROW, "ASDFGHJKLØÆ*");
+        assert_legends(NORWEGIAN, KeyLevel::AltGr, &[0x0d, 0x03], "´@");
+    }
+
+    #[test]
+    fn test_danish_legends() {
+        assert_legends(DANISH, KeyLevel::Base, &NUMBER_ROW, "½1234567890+´");
+        assert_legends(DANISH, KeyLevel::Shift, &NUMBER_ROW, "§!\"#¤%&/()=?`");
+        assert_legends(DANISH, KeyLevel::Base, &HOME_ROW, "asdfghjklæø'");
+        assert_legends(DANISH, KeyLevel::Shift, &HOME_ROW, "ASDFGHJKLÆØ*");
+        assert_legends(DANISH, KeyLevel::AltGr, &[0x0d, 0x56], "|\\");
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, SWEDISH);
+
+        assert_eq!(press(&mut key_board, 0x1b), None);
+        assert_eq!(press(&mut key_board, 0x16), Some('ü'));
+        assert_eq!(press(&mut key_board, 0x0d), None);
+        assert_eq!(press(&mut key_board, 0x12), Some('é'));
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, DANISH);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x27), Some('Æ'));
+        assert_eq!(press(&mut key_board, 0x1a), Some('Å'));
+        assert_eq!(press(&mut key_board, 0x02), Some('1'));
+    }
+}
@@ -0,0 +1,96 @@
+use super::KEYPAD_ROWS;
+
+// The Russian ЙЦУКЕН layout
+crate::layout! {
+    pub struct RussianLayout;
+    alt_gr: true;
+    keys {
+        SymbolBacktick => ('ё', 'Ё', _, _, Auto),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '"', _, _, Ignore),
+        Num3 => ('3', '№', _, _, Ignore),
+        Num4 => ('4', ';', _, _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', ':', _, _, Ignore),
+        Num7 => ('7', '?', _, _, Ignore),
+        Num8 => ('8', '*', '₽', _, Ignore),
+        Num9 => ('9', '(', _, _, Ignore),
+        Num0 => ('0', ')', _, _, Ignore),
+        SymbolMinus => ('-', '_', _, _, Ignore),
+        SymbolEquals => ('=', '+', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('й', 'Й', _, _, Auto),
+        CharW => ('ц', 'Ц', _, _, Auto),
+        CharE => ('у', 'У', _, _, Auto),
+        CharR => ('к', 'К', _, _, Auto),
+        CharT => ('е', 'Е', _, _, Auto),
+        CharY => ('н', 'Н', _, _, Auto),
+        CharU => ('г', 'Г', _, _, Auto),
+        CharI => ('ш', 'Ш', _, _, Auto),
+        CharO => ('щ', 'Щ', _, _, Auto),
+        CharP => ('з', 'З', _, _, Auto),
+        SymbolOpenSquareBracket => ('х', 'Х', _, _, Auto),
+        SymbolCloseSquareBracket => ('ъ', 'Ъ', _, _, Auto),
+        CharA => ('ф', 'Ф', _, _, Auto),
+        CharS => ('ы', 'Ы', _, _, Auto),
+        CharD => ('в', 'В', _, _, Auto),
+        CharF => ('а', 'А', _, _, Auto),
+        CharG => ('п', 'П', _, _, Auto),
+        CharH => ('р', 'Р', _, _, Auto),
+        CharJ => ('о', 'О', _, _, Auto),
+        CharK => ('л', 'Л', _, _, Auto),
+        CharL => ('д', 'Д', _, _, Auto),
+        SymbolSemicolon => ('ж', 'Ж', _, _, Auto),
+        SymbolSingleQuote => ('э', 'Э', _, _, Auto),
+        SymbolBackslash => ('\\', '/', _, _, Ignore),
+        Iso102nd => ('\\', '/', _, _, Ignore),
+        CharZ => ('я', 'Я', _, _, Auto),
+        CharX => ('ч', 'Ч', _, _, Auto),
+        CharC => ('с', 'С', _, _, Auto),
+        CharV => ('м', 'М', _, _, Auto),
+        CharB => ('и', 'И', _, _, Auto),
+        CharN => ('т', 'Т', _, _, Auto),
+        CharM => ('ь', 'Ь', _, _, Auto),
+        SymbolComma => ('б', 'Б', _, _, Auto),
+        SymbolPeriod => ('ю', 'Ю', _, _, Auto),
+        SymbolForwardSlash => ('.', ',', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        let layout = RussianLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, "ё1234567890-=");
+        assert_legends(layout, KeyLevel::Shift, &NUMBER_ROW, "Ё!\"№;%:?*()_+");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "йцукенгшщзхъ");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, "ЙЦУКЕНГШЩЗХЪ");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "фывапролджэ\\");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW, "ФЫВАПРОЛДЖЭ/");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW, "\\ячсмитьбю.");
+        assert_legends(layout, KeyLevel::Shift, &BOTTOM_ROW, "/ЯЧСМИТЬБЮ,");
+        assert_legends(layout, KeyLevel::AltGr, &[0x09], "₽");
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, RussianLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x10), Some('Й'));
+        assert_eq!(press(&mut key_board, 0x29), Some('Ё'));
+        assert_eq!(press(&mut key_board, 0x35), Some('.'));
+
+        key_board.input_byte(0x2a);
+        assert_eq!(press(&mut key_board, 0x10), Some('й'));
+    }
+}
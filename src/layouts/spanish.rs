@@ -0,0 +1,113 @@
+use super::KEYPAD_ROWS;
+
+// The Spanish (Spain) layout with ñ, ç and the dead accent keys
+crate::layout! {
+    pub struct SpanishLayout;
+    alt_gr: true;
+    keys {
+        SymbolBacktick => ('º', 'ª', '\\', _, Ignore),
+        Num1 => ('1', '!', '|', _, Ignore),
+        Num2 => ('2', '"', '@', _, Ignore),
+        Num3 => ('3', '·', '#', _, Ignore),
+        Num4 => ('4', '$', Tilde, _, Ignore),
+        Num5 => ('5', '%', '€', _, Ignore),
+        Num6 => ('6', '&', '¬', _, Ignore),
+        Num7 => ('7', '/', _, _, Ignore),
+        Num8 => ('8', '(', _, _, Ignore),
+        Num9 => ('9', ')', _, _, Ignore),
+        Num0 => ('0', '=', _, _, Ignore),
+        SymbolMinus => ('\'', '?', _, _, Ignore),
+        SymbolEquals => ('¡', '¿', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharE => ('e', 'E', '€', _, Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharU => ('u', 'U', _, _, Auto),
+        CharI => ('i', 'I', _, _, Auto),
+        CharO => ('o', 'O', _, _, Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => (Grave, Circumflex, '[', _, Ignore),
+        SymbolCloseSquareBracket => ('+', '*', ']', _, Ignore),
+        CharA => ('a', 'A', _, _, Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => ('ñ', 'Ñ', _, _, Auto),
+        SymbolSingleQuote => (Acute, Diaeresis, '{', _, Ignore),
+        SymbolBackslash => ('ç', 'Ç', '}', _, Auto),
+        Iso102nd => ('<', '>', _, _, Ignore),
+        CharZ => ('z', 'Z', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', _, _, Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => ('m', 'M', _, _, Auto),
+        SymbolComma => (',', ';', _, _, Ignore),
+        SymbolPeriod => ('.', ':', _, _, Ignore),
+        SymbolForwardSlash => ('-', '_', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        let layout = SpanishLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, "º1234567890'¡");
+        assert_legends(layout, KeyLevel::Shift, &NUMBER_ROW, "ª!\"·$%&/()=?¿");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "qwertyuiop`+");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, "QWERTYUIOP^*");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "asdfghjklñ´ç");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW, "ASDFGHJKLÑ¨Ç");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW, "<zxcvbnm,.-");
+        assert_legends(layout, KeyLevel::Shift, &BOTTOM_ROW, ">ZXCVBNM;:_");
+    }
+
+    #[test]
+    fn test_alt_gr() {
+        let codes = [
+            0x29, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x12, 0x1a, 0x1b, 0x28, 0x2b,
+        ];
+
+        assert_legends(SpanishLayout, KeyLevel::AltGr, &codes, "\\|@#~€¬€[]{}");
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, SpanishLayout);
+
+        assert_eq!(press(&mut key_board, 0x28), None);
+        assert_eq!(press(&mut key_board, 0x1e), Some('á'));
+
+        key_board.input_byte(0x2a);
+        assert_eq!(press(&mut key_board, 0x28), None);
+        assert_eq!(press(&mut key_board, 0x16), Some('Ü'));
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, SpanishLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x27), Some('Ñ'));
+        assert_eq!(press(&mut key_board, 0x2b), Some('Ç'));
+        assert_eq!(press(&mut key_board, 0x0d), Some('¡'));
+    }
+}
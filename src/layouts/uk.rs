@@ -0,0 +1,101 @@
+use super::KEYPAD_ROWS;
+
+// The UK (extended) layout: £ on Shift+3, # next to Enter, \ on the ISO key and the acute
+// vowels on AltGr.
+crate::layout! {
+    pub struct UKLayout;
+    alt_gr: true;
+    keys {
+        SymbolBacktick => ('`', '¬', '¦', _, Ignore),
+        Num1 => ('1', '!', _, _, Ignore),
+        Num2 => ('2', '"', _, _, Ignore),
+        Num3 => ('3', '£', _, _, Ignore),
+        Num4 => ('4', '$', '€', _, Ignore),
+        Num5 => ('5', '%', _, _, Ignore),
+        Num6 => ('6', '^', _, _, Ignore),
+        Num7 => ('7', '&', _, _, Ignore),
+        Num8 => ('8', '*', _, _, Ignore),
+        Num9 => ('9', '(', _, _, Ignore),
+        Num0 => ('0', ')', _, _, Ignore),
+        SymbolMinus => ('-', '_', _, _, Ignore),
+        SymbolEquals => ('=', '+', _, _, Ignore),
+        Tab => ('\t', '\t', '\t', '\t', Ignore),
+        CharQ => ('q', 'Q', _, _, Auto),
+        CharW => ('w', 'W', _, _, Auto),
+        CharE => ('e', 'E', 'é', 'É', Auto),
+        CharR => ('r', 'R', _, _, Auto),
+        CharT => ('t', 'T', _, _, Auto),
+        CharY => ('y', 'Y', _, _, Auto),
+        CharU => ('u', 'U', 'ú', 'Ú', Auto),
+        CharI => ('i', 'I', 'í', 'Í', Auto),
+        CharO => ('o', 'O', 'ó', 'Ó', Auto),
+        CharP => ('p', 'P', _, _, Auto),
+        SymbolOpenSquareBracket => ('[', '{', _, _, Ignore),
+        SymbolCloseSquareBracket => (']', '}', _, _, Ignore),
+        CharA => ('a', 'A', 'á', 'Á', Auto),
+        CharS => ('s', 'S', _, _, Auto),
+        CharD => ('d', 'D', _, _, Auto),
+        CharF => ('f', 'F', _, _, Auto),
+        CharG => ('g', 'G', _, _, Auto),
+        CharH => ('h', 'H', _, _, Auto),
+        CharJ => ('j', 'J', _, _, Auto),
+        CharK => ('k', 'K', _, _, Auto),
+        CharL => ('l', 'L', _, _, Auto),
+        SymbolSemicolon => (';', ':', _, _, Ignore),
+        SymbolSingleQuote => ('\'', '@', _, _, Ignore),
+        SymbolBackslash => ('#', '~', _, _, Ignore),
+        Iso102nd => ('\\', '|', _, _, Ignore),
+        CharZ => ('z', 'Z', _, _, Auto),
+        CharX => ('x', 'X', _, _, Auto),
+        CharC => ('c', 'C', 'ç', 'Ç', Auto),
+        CharV => ('v', 'V', _, _, Auto),
+        CharB => ('b', 'B', _, _, Auto),
+        CharN => ('n', 'N', _, _, Auto),
+        CharM => ('m', 'M', _, _, Auto),
+        SymbolComma => (',', '<', _, _, Ignore),
+        SymbolPeriod => ('.', '>', _, _, Ignore),
+        SymbolForwardSlash => ('/', '?', _, _, Ignore),
+        Space => (' ', ' ', ' ', ' ', Ignore),
+    }
+    keypad: KEYPAD_ROWS;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_legends() {
+        assert_legends(UKLayout, KeyLevel::Base, &NUMBER_ROW, "`1234567890-=");
+        assert_legends(UKLayout, KeyLevel::Shift, &NUMBER_ROW, "¬!\"£$%^&*()_+");
+        assert_legends(UKLayout, KeyLevel::Base, &TOP_ROW, "qwertyuiop[]");
+        assert_legends(UKLayout, KeyLevel::Shift, &TOP_ROW, "QWERTYUIOP{}");
+        assert_legends(UKLayout, KeyLevel::Base, &HOME_ROW, "asdfghjkl;'#");
+        assert_legends(UKLayout, KeyLevel::Shift, &HOME_ROW, "ASDFGHJKL:@~");
+        assert_legends(UKLayout, KeyLevel::Base, &BOTTOM_ROW, "\\zxcvbnm,./");
+        assert_legends(UKLayout, KeyLevel::Shift, &BOTTOM_ROW, "|ZXCVBNM<>?");
+    }
+
+    #[test]
+    fn test_alt_gr() {
+        assert_legends(UKLayout, KeyLevel::AltGr, &[0x29, 0x05, 0x12, 0x1e], "¦€éá");
+        assert_legends(UKLayout, KeyLevel::ShiftAltGr, &[0x12, 0x18], "ÉÓ");
+    }
+
+    #[test]
+    fn test_caps_lock() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, UKLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x1e), Some('A'));
+        assert_eq!(press(&mut key_board, 0x04), Some('3'));
+
+        // Caps Lock applies to the AltGr level as well
+        key_board.input_byte(0xe0);
+        key_board.input_byte(0x38);
+        assert_eq!(press(&mut key_board, 0x12), Some('É'));
+    }
+}
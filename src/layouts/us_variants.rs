@@ -0,0 +1,182 @@
+use super::super::layout::{KeyModifierState, Layout, USStandardLayout};
+use super::super::remap::KeyRemap;
+use super::super::{Key, ScanType::*};
+
+// These layouts only move the US legends around, so each physical key is turned into the US
+// key carrying the same legend and the US layout does the rest (Shift, Caps Lock, keypad).
+fn us_legend_char(remap: &KeyRemap, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+    if key.keypad() {
+        return USStandardLayout.key_into_char(modifiers, key);
+    }
+
+    return USStandardLayout.key_into_char(modifiers, remap.apply(key)?);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DvorakLayout;
+
+const DVORAK_KEYS: KeyRemap = KeyRemap::identity()
+    .remap(SymbolMinus, SymbolOpenSquareBracket)
+    .remap(SymbolEquals, SymbolCloseSquareBracket)
+    .remap(CharQ, SymbolSingleQuote)
+    .remap(CharW, SymbolComma)
+    .remap(CharE, SymbolPeriod)
+    .remap(CharR, CharP)
+    .remap(CharT, CharY)
+    .remap(CharY, CharF)
+    .remap(CharU, CharG)
+    .remap(CharI, CharC)
+    .remap(CharO, CharR)
+    .remap(CharP, CharL)
+    .remap(SymbolOpenSquareBracket, SymbolForwardSlash)
+    .remap(SymbolCloseSquareBracket, SymbolEquals)
+    .remap(CharS, CharO)
+    .remap(CharD, CharE)
+    .remap(CharF, CharU)
+    .remap(CharG, CharI)
+    .remap(CharH, CharD)
+    .remap(CharJ, CharH)
+    .remap(CharK, CharT)
+    .remap(CharL, CharN)
+    .remap(SymbolSemicolon, CharS)
+    .remap(SymbolSingleQuote, SymbolMinus)
+    .remap(CharZ, SymbolSemicolon)
+    .remap(CharX, CharQ)
+    .remap(CharC, CharJ)
+    .remap(CharV, CharK)
+    .remap(CharB, CharX)
+    .remap(CharN, CharB)
+    .remap(SymbolComma, CharW)
+    .remap(SymbolPeriod, CharV)
+    .remap(SymbolForwardSlash, CharZ);
+
+impl Layout for DvorakLayout {
+    fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+        return us_legend_char(&DVORAK_KEYS, modifiers, key);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Colemak without the Caps Lock as Backspace change, use a `KeyRemap` for that
+pub struct ColemakLayout;
+
+const COLEMAK_KEYS: KeyRemap = KeyRemap::identity()
+    .remap(CharE, CharF)
+    .remap(CharR, CharP)
+    .remap(CharT, CharG)
+    .remap(CharY, CharJ)
+    .remap(CharU, CharL)
+    .remap(CharI, CharU)
+    .remap(CharO, CharY)
+    .remap(CharP, SymbolSemicolon)
+    .remap(CharS, CharR)
+    .remap(CharD, CharS)
+    .remap(CharF, CharT)
+    .remap(CharG, CharD)
+    .remap(CharJ, CharN)
+    .remap(CharK, CharE)
+    .remap(CharL, CharI)
+    .remap(SymbolSemicolon, CharO)
+    .remap(CharN, CharK);
+
+impl Layout for ColemakLayout {
+    fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+        return us_legend_char(&COLEMAK_KEYS, modifiers, key);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkmanLayout;
+
+const WORKMAN_KEYS: KeyRemap = KeyRemap::identity()
+    .remap(CharW, CharD)
+    .remap(CharE, CharR)
+    .remap(CharR, CharW)
+    .remap(CharT, CharB)
+    .remap(CharY, CharJ)
+    .remap(CharU, CharF)
+    .remap(CharI, CharU)
+    .remap(CharO, CharP)
+    .remap(CharP, SymbolSemicolon)
+    .remap(CharD, CharH)
+    .remap(CharF, CharT)
+    .remap(CharH, CharY)
+    .remap(CharJ, CharN)
+    .remap(CharK, CharE)
+    .remap(CharL, CharO)
+    .remap(SymbolSemicolon, CharI)
+    .remap(CharC, CharM)
+    .remap(CharV, CharC)
+    .remap(CharB, CharV)
+    .remap(CharN, CharK)
+    .remap(CharM, CharL);
+
+impl Layout for WorkmanLayout {
+    fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+        return us_legend_char(&WORKMAN_KEYS, modifiers, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::layout::KeyLevel;
+    use super::super::super::{Keyboard, ReaderMode};
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn test_dvorak_legends() {
+        let layout = DvorakLayout;
+
+        assert_legends(layout, KeyLevel::Base, &NUMBER_ROW, "`1234567890[]");
+        assert_legends(layout, KeyLevel::Shift, &NUMBER_ROW, "~!@#$%^&*(){}");
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "',.pyfgcrl/=");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, "\"<>PYFGCRL?+");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "aoeuidhtns-\\");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW, "AOEUIDHTNS_|");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW[1..], ";qjkxbmwvz");
+        assert_legends(layout, KeyLevel::Shift, &BOTTOM_ROW[1..], ":QJKXBMWVZ");
+    }
+
+    #[test]
+    fn test_colemak_legends() {
+        let layout = ColemakLayout;
+
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "qwfpgjluy;[]");
+        assert_legends(layout, KeyLevel::Shift, &TOP_ROW, "QWFPGJLUY:{}");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "arstdhneio'\\");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW[1..], "zxcvbkm,./");
+    }
+
+    #[test]
+    fn test_workman_legends() {
+        let layout = WorkmanLayout;
+
+        assert_legends(layout, KeyLevel::Base, &TOP_ROW, "qdrwbjfup;[]");
+        assert_legends(layout, KeyLevel::Base, &HOME_ROW, "ashtgyneoi'\\");
+        assert_legends(layout, KeyLevel::Shift, &HOME_ROW, "ASHTGYNEOI\"|");
+        assert_legends(layout, KeyLevel::Base, &BOTTOM_ROW[1..], "zxmcvkl,./");
+    }
+
+    #[test]
+    fn test_caps_lock_only_letters() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, DvorakLayout);
+        press(&mut key_board, 0x3a);
+
+        assert_eq!(press(&mut key_board, 0x10), Some('\''));
+        assert_eq!(press(&mut key_board, 0x13), Some('P'));
+        assert_eq!(press(&mut key_board, 0x2c), Some(';'));
+
+        key_board.input_byte(0x2a);
+        assert_eq!(press(&mut key_board, 0x13), Some('p'));
+    }
+
+    #[test]
+    fn test_keypad_is_not_moved() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, DvorakLayout);
+
+        press(&mut key_board, 0x45);
+        assert_eq!(press(&mut key_board, 0x53), Some('.'));
+        assert_eq!(press(&mut key_board, 0x4a), Some('-'));
+    }
+}
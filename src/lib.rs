@@ -1,10 +1,63 @@
 #![no_std]
+
+mod alt_numpad;
+mod dead_key;
+mod encoder;
+mod event;
+mod hotkey;
+mod key_filter;
+mod key_stroke;
 mod keyboard;
+mod keymap;
+mod keypad;
+mod layers;
 mod layout;
+mod layouts;
+mod line_editor;
+mod macro_recorder;
+mod mouse;
+mod mouse_keys;
+mod names;
 mod reader;
+mod remap;
 mod scan_code_set;
+mod sequence;
+mod static_layout;
+mod sticky_keys;
+mod tap_hold;
+#[cfg(feature = "windows-vk")]
+pub mod windows_vk;
+#[cfg(feature = "x11")]
+pub mod x11;
 
+pub use dead_key::{ComposeTable, DeadKey};
+pub use encoder::{Encoder, ScanCodeSequence};
+pub use event::{KeyEvent, SecureAttention};
+pub use hotkey::{
+    Hotkey, HotkeyBinding, HotkeyMatch, HotkeyModifiers, HotkeyTable, ModifierSide,
+    HOTKEY_TABLE_CAPACITY,
+};
+pub use key_stroke::{KeyStroke, KeyStrokes};
 pub use keyboard::Keyboard;
-pub use layout::{KeyModifierState, Layout, USStandardLayout};
+pub use keymap::{
+    keymap_len, write_keymap, KeymapEntry, KeymapError, TableLayout, KEYMAP_HEADER_LEN,
+    KEYMAP_MAGIC, KEYMAP_ROW_LEN, KEYMAP_VERSION,
+};
+pub use layers::{Layer, LayerAction, Layers, LAYER_COUNT};
+pub use layout::{KeyLevel, KeyModifierState, Layout, LayoutPair, USStandardLayout};
+pub use layouts::{
+    ArabicLayout, CapsBehaviour, ColemakLayout, DvorakLayout, FrenchAzertyLayout,
+    GermanQwertzLayout, GreekLayout, HebrewLayout, JisLayout, KeySymbol, NordicLayout,
+    NordicVariant, RussianLayout, SpanishLayout, UKLayout, WorkmanLayout, KEYPAD_ROWS,
+};
+pub use line_editor::{LineEdit, LineEditor, HISTORY_CAPACITY, LINE_CAPACITY};
+pub use macro_recorder::{MacroPlayer, MacroRecorder, MacroStep, MacroTrigger, MacroTriggers};
+pub use mouse::{MouseButtons, MouseEvent, MouseReader};
+pub use mouse_keys::{AccelerationCurve, MouseKeys, MouseKeysConfig};
 pub use reader::{Reader, ReaderMode};
+pub use remap::KeyRemap;
 pub use scan_code_set::{Key, KeyState, ScanType};
+pub use sequence::{SequenceMatch, SequenceMatcher, SEQUENCE_MAX_LEN, SEQUENCE_TABLE_CAPACITY};
+pub use static_layout::StaticLayout;
+pub use sticky_keys::ModifierKeys;
+pub use tap_hold::{DualRole, TapHold};
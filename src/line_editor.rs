@@ -0,0 +1,615 @@
+use super::event::KeyEvent;
+use super::layout::KeyModifierState;
+use super::{Key, ScanType};
+
+// Bytes of UTF-8 a line can hold
+pub const LINE_CAPACITY: usize = 128;
+// Lines kept for Up and Down, the oldest is dropped first
+pub const HISTORY_CAPACITY: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// What the caller has to do to show the line. Columns count characters from the line's start.
+pub enum LineEdit<'a> {
+    // Move to column, print text, clear to the end of the screen line and move to cursor
+    Redraw {
+        column: usize,
+        text: &'a str,
+        cursor: usize,
+    },
+    MoveCursor(usize),
+    // The line is full
+    Bell,
+    // Enter was pressed, the next input starts an empty line
+    Done(&'a str),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LineBuffer {
+    bytes: [u8; LINE_CAPACITY],
+    len: usize,
+}
+
+impl LineBuffer {
+    const fn new() -> Self {
+        return Self {
+            bytes: [0u8; LINE_CAPACITY],
+            len: 0,
+        };
+    }
+
+    // Only whole characters are ever written so this can't fail
+    fn as_str(&self) -> &str {
+        return core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+// A readline for kernels without an allocator. It takes the events of a Keyboard, edits a fixed
+// buffer and says how to render each change. Keys it doesn't know are ignored.
+pub struct LineEditor {
+    line: LineBuffer,
+    // A byte offset into the line, always on a character boundary
+    cursor: usize,
+    history: [LineBuffer; HISTORY_CAPACITY],
+    history_len: usize,
+    // Where the next line goes in the history ring
+    history_head: usize,
+    // How far back Up has gone, 1 is the latest line
+    browsing: usize,
+    // The line that was being typed before Up was pressed
+    draft: LineBuffer,
+    done: bool,
+}
+
+impl LineEditor {
+    pub const fn new() -> Self {
+        return Self {
+            line: LineBuffer::new(),
+            cursor: 0,
+            history: [LineBuffer::new(); HISTORY_CAPACITY],
+            history_len: 0,
+            history_head: 0,
+            browsing: 0,
+            draft: LineBuffer::new(),
+            done: false,
+        };
+    }
+
+    pub fn line(&self) -> &str {
+        return self.line.as_str();
+    }
+
+    // The cursor's column
+    pub fn cursor(&self) -> usize {
+        return self.column(self.cursor);
+    }
+
+    pub fn history_len(&self) -> usize {
+        return self.history_len;
+    }
+
+    // Lines from the history, 0 is the latest
+    pub fn history(&self, index: usize) -> Option<&str> {
+        if index >= self.history_len {
+            return None;
+        }
+
+        let slot = (self.history_head + HISTORY_CAPACITY - 1 - index) % HISTORY_CAPACITY;
+
+        return Some(self.history[slot].as_str());
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history_len = 0;
+        self.history_head = 0;
+        self.browsing = 0;
+    }
+
+    // Starts an empty line without touching the history
+    pub fn clear(&mut self) {
+        self.line = LineBuffer::new();
+        self.cursor = 0;
+        self.browsing = 0;
+        self.done = false;
+    }
+
+    pub fn input(&mut self, event: &KeyEvent) -> Option<LineEdit<'_>> {
+        if self.done {
+            self.clear();
+        }
+
+        return match *event {
+            KeyEvent::Key { key, modifiers, ch } if key.is_pressed() => {
+                self.input_key(key, &modifiers, ch)
+            }
+            KeyEvent::Char(ch) => self.insert(ch),
+            _ => None,
+        };
+    }
+
+    fn input_key(
+        &mut self,
+        key: Key,
+        modifiers: &KeyModifierState,
+        ch: Option<char>,
+    ) -> Option<LineEdit<'_>> {
+        // AltGr emulated with Ctrl+Alt types characters
+        let ctrl = modifiers.ctrl_down() && !modifiers.alt_gr;
+
+        match key.scan_type() {
+            ScanType::Enter => return Some(self.finish()),
+            ScanType::Backspace => return self.backspace(),
+            ScanType::Delete => return self.delete(),
+            ScanType::CursorLeft if ctrl => return self.move_to(self.word_start()),
+            ScanType::CursorRight if ctrl => return self.move_to(self.word_end()),
+            ScanType::CursorLeft => return self.move_to(self.previous_boundary(self.cursor)),
+            ScanType::CursorRight => return self.move_to(self.next_boundary(self.cursor)),
+            ScanType::Home => return self.move_to(0),
+            ScanType::End => return self.move_to(self.line.len),
+            ScanType::CursorUp => return self.browse(self.browsing + 1),
+            ScanType::CursorDown if self.browsing > 0 => return self.browse(self.browsing - 1),
+            _ => (),
+        }
+
+        if ctrl {
+            // The layout gives the letter with Ctrl held, the key is the fallback when it gives
+            // none or one that isn't a Latin letter, e.g. 'г' for Ctrl+U on a Russian layout
+            let letter = match ch {
+                Some(ch) if ch.is_ascii_alphabetic() => ch.to_ascii_lowercase(),
+                _ => match key.scan_type() {
+                    ScanType::CharA => 'a',
+                    ScanType::CharE => 'e',
+                    ScanType::CharU => 'u',
+                    ScanType::CharW => 'w',
+                    _ => return None,
+                },
+            };
+
+            return match letter {
+                'a' => self.move_to(0),
+                'e' => self.move_to(self.line.len),
+                'u' => self.remove(0, self.cursor),
+                'w' => self.remove(self.rubout_start(), self.cursor),
+                _ => None,
+            };
+        }
+
+        return match ch {
+            Some(ch) => self.insert(ch),
+            None => None,
+        };
+    }
+
+    fn insert(&mut self, ch: char) -> Option<LineEdit<'_>> {
+        if ch.is_control() {
+            return None;
+        }
+
+        let mut encoded = [0u8; 4];
+        let encoded = ch.encode_utf8(&mut encoded).as_bytes();
+        let len = self.line.len;
+
+        if len + encoded.len() > LINE_CAPACITY {
+            return Some(LineEdit::Bell);
+        }
+
+        let from = self.cursor;
+        self.line.bytes.copy_within(from..len, from + encoded.len());
+        self.line.bytes[from..from + encoded.len()].copy_from_slice(encoded);
+        self.line.len += encoded.len();
+        self.cursor += encoded.len();
+
+        return Some(self.redraw(from));
+    }
+
+    fn backspace(&mut self) -> Option<LineEdit<'_>> {
+        return self.remove(self.previous_boundary(self.cursor), self.cursor);
+    }
+
+    fn delete(&mut self) -> Option<LineEdit<'_>> {
+        return self.remove(self.cursor, self.next_boundary(self.cursor));
+    }
+
+    // Removes the bytes between two character boundaries and leaves the cursor at the start
+    fn remove(&mut self, start: usize, end: usize) -> Option<LineEdit<'_>> {
+        if start == end {
+            return None;
+        }
+
+        let len = self.line.len;
+        self.line.bytes.copy_within(end..len, start);
+        self.line.len -= end - start;
+        self.cursor = start;
+
+        return Some(self.redraw(start));
+    }
+
+    fn move_to(&mut self, cursor: usize) -> Option<LineEdit<'_>> {
+        if cursor == self.cursor {
+            return None;
+        }
+
+        self.cursor = cursor;
+
+        return Some(LineEdit::MoveCursor(self.column(cursor)));
+    }
+
+    // Up goes back through the history and Down forward again, past the latest line is the draft
+    fn browse(&mut self, browsing: usize) -> Option<LineEdit<'_>> {
+        if browsing > self.history_len {
+            return None;
+        }
+
+        if self.browsing == 0 {
+            self.draft = self.line;
+        }
+
+        self.line = match browsing {
+            0 => self.draft,
+            _ => {
+                let slot = (self.history_head + HISTORY_CAPACITY - browsing) % HISTORY_CAPACITY;
+                self.history[slot]
+            }
+        };
+        self.browsing = browsing;
+        self.cursor = self.line.len;
+
+        return Some(self.redraw(0));
+    }
+
+    // Empty lines and repeats of the latest line stay out of the history
+    fn finish(&mut self) -> LineEdit<'_> {
+        let repeat = self.history(0) == Some(self.line.as_str());
+
+        if self.line.len > 0 && !repeat {
+            self.history[self.history_head] = self.line;
+            self.history_head = (self.history_head + 1) % HISTORY_CAPACITY;
+            self.history_len = (self.history_len + 1).min(HISTORY_CAPACITY);
+        }
+
+        self.browsing = 0;
+        self.done = true;
+
+        return LineEdit::Done(self.line.as_str());
+    }
+
+    fn redraw(&self, from: usize) -> LineEdit<'_> {
+        return LineEdit::Redraw {
+            column: self.column(from),
+            text: &self.line.as_str()[from..],
+            cursor: self.column(self.cursor),
+        };
+    }
+
+    fn column(&self, offset: usize) -> usize {
+        return self.line.as_str()[..offset].chars().count();
+    }
+
+    fn previous_boundary(&self, offset: usize) -> usize {
+        return match self.line.as_str()[..offset].chars().next_back() {
+            Some(ch) => offset - ch.len_utf8(),
+            None => offset,
+        };
+    }
+
+    fn next_boundary(&self, offset: usize) -> usize {
+        return match self.line.as_str()[offset..].chars().next() {
+            Some(ch) => offset + ch.len_utf8(),
+            None => offset,
+        };
+    }
+
+    // Skips back over anything that isn't part of a word and then over the word
+    fn skip_back<F: Fn(char) -> bool>(&self, in_word: F) -> usize {
+        let mut offset = self.cursor;
+        let mut seen_word = false;
+
+        for ch in self.line.as_str()[..self.cursor].chars().rev() {
+            if in_word(ch) {
+                seen_word = true;
+            } else if seen_word {
+                break;
+            }
+
+            offset -= ch.len_utf8();
+        }
+
+        return offset;
+    }
+
+    fn word_start(&self) -> usize {
+        return self.skip_back(char::is_alphanumeric);
+    }
+
+    // Ctrl+W takes everything back to the previous whitespace like a shell does
+    fn rubout_start(&self) -> usize {
+        return self.skip_back(|ch| !ch.is_whitespace());
+    }
+
+    fn word_end(&self) -> usize {
+        let mut offset = self.cursor;
+        let mut seen_word = false;
+
+        for ch in self.line.as_str()[self.cursor..].chars() {
+            if ch.is_alphanumeric() {
+                seen_word = true;
+            } else if seen_word {
+                break;
+            }
+
+            offset += ch.len_utf8();
+        }
+
+        return offset;
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::layouts::RussianLayout;
+    use super::super::{KeyState, Keyboard, ReaderMode, USStandardLayout};
+    use super::*;
+
+    fn key(scan_type: ScanType, ch: Option<char>) -> KeyEvent {
+        return KeyEvent::Key {
+            key: Key::new(scan_type, KeyState::Pressed),
+            modifiers: KeyModifierState::new(),
+            ch,
+        };
+    }
+
+    fn ctrl(scan_type: ScanType, ch: Option<char>) -> KeyEvent {
+        let mut modifiers = KeyModifierState::new();
+        modifiers.left_ctrl = true;
+
+        return KeyEvent::Key {
+            key: Key::new(scan_type, KeyState::Pressed),
+            modifiers,
+            ch,
+        };
+    }
+
+    fn type_str(editor: &mut LineEditor, text: &str) {
+        for ch in text.chars() {
+            editor.input(&KeyEvent::Char(ch));
+        }
+    }
+
+    #[test]
+    fn test_insert_and_render() {
+        let mut editor = LineEditor::new();
+
+        type_str(&mut editor, "hllo");
+        assert_eq!(
+            editor.input(&key(ScanType::Home, None)),
+            Some(LineEdit::MoveCursor(0))
+        );
+        assert_eq!(editor.input(&key(ScanType::Home, None)), None);
+        editor.input(&key(ScanType::CursorRight, None));
+        assert_eq!(
+            editor.input(&key(ScanType::CharE, Some('e'))),
+            Some(LineEdit::Redraw {
+                column: 1,
+                text: "ello",
+                cursor: 2
+            })
+        );
+        assert_eq!(editor.line(), "hello");
+
+        // Columns count characters, not bytes
+        editor.input(&key(ScanType::End, None));
+        type_str(&mut editor, "é");
+        assert_eq!(editor.cursor(), 6);
+        assert_eq!(
+            editor.input(&key(ScanType::Backspace, None)),
+            Some(LineEdit::Redraw {
+                column: 5,
+                text: "",
+                cursor: 5
+            })
+        );
+        assert_eq!(
+            editor.input(&key(ScanType::Enter, None)),
+            Some(LineEdit::Done("hello"))
+        );
+
+        // The next line starts empty
+        assert_eq!(
+            editor.input(&key(ScanType::CharA, Some('a'))),
+            Some(LineEdit::Redraw {
+                column: 0,
+                text: "a",
+                cursor: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut editor = LineEditor::new();
+
+        type_str(&mut editor, "abc");
+        assert_eq!(editor.input(&key(ScanType::Delete, None)), None);
+        editor.input(&key(ScanType::CursorLeft, None));
+        editor.input(&key(ScanType::CursorLeft, None));
+        assert_eq!(
+            editor.input(&key(ScanType::Delete, None)),
+            Some(LineEdit::Redraw {
+                column: 1,
+                text: "c",
+                cursor: 1
+            })
+        );
+        editor.input(&key(ScanType::Home, None));
+        assert_eq!(editor.input(&key(ScanType::Backspace, None)), None);
+        assert_eq!(editor.line(), "ac");
+    }
+
+    #[test]
+    fn test_ctrl_keys() {
+        let mut editor = LineEditor::new();
+
+        type_str(&mut editor, "echo foo.bar baz");
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CharW, Some('w'))),
+            Some(LineEdit::Redraw {
+                column: 13,
+                text: "",
+                cursor: 13
+            })
+        );
+        assert_eq!(editor.line(), "echo foo.bar ");
+
+        // Word movement stops at punctuation, Ctrl+W only at whitespace
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CursorLeft, None)),
+            Some(LineEdit::MoveCursor(9))
+        );
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CursorLeft, None)),
+            Some(LineEdit::MoveCursor(5))
+        );
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CursorRight, None)),
+            Some(LineEdit::MoveCursor(8))
+        );
+        editor.input(&ctrl(ScanType::CursorRight, None));
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CharW, Some('w'))),
+            Some(LineEdit::Redraw {
+                column: 5,
+                text: " ",
+                cursor: 5
+            })
+        );
+
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CharA, Some('a'))),
+            Some(LineEdit::MoveCursor(0))
+        );
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CharE, None)),
+            Some(LineEdit::MoveCursor(6))
+        );
+        editor.input(&key(ScanType::CursorLeft, None));
+        assert_eq!(
+            editor.input(&ctrl(ScanType::CharU, Some('u'))),
+            Some(LineEdit::Redraw {
+                column: 0,
+                text: " ",
+                cursor: 0
+            })
+        );
+        assert_eq!(editor.input(&ctrl(ScanType::CharX, Some('x'))), None);
+        assert_eq!(editor.line(), " ");
+    }
+
+    #[test]
+    fn test_ctrl_keys_non_latin() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, RussianLayout);
+        let mut editor = LineEditor::new();
+        let mut feed = |bytes: &[u8], editor: &mut LineEditor| {
+            for byte in bytes.iter() {
+                let mut event = key_board.input_event(*byte);
+
+                while let Some(current) = event {
+                    editor.input(&current);
+                    event = key_board.next_event();
+                }
+            }
+        };
+
+        feed(&[0x20, 0xa0, 0x1e, 0x9e], &mut editor);
+        assert_eq!(editor.line(), "вф");
+
+        // Ctrl+U, the layout gives 'г'
+        feed(&[0x1d, 0x16], &mut editor);
+        assert_eq!(editor.line(), "");
+    }
+
+    #[test]
+    fn test_full_line() {
+        let mut editor = LineEditor::new();
+
+        for _ in 0..LINE_CAPACITY - 1 {
+            editor.input(&KeyEvent::Char('a'));
+        }
+
+        assert_eq!(editor.input(&KeyEvent::Char('é')), Some(LineEdit::Bell));
+        assert!(editor.input(&KeyEvent::Char('a')).is_some());
+        assert_eq!(editor.input(&KeyEvent::Char('a')), Some(LineEdit::Bell));
+        assert_eq!(editor.line().len(), LINE_CAPACITY);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut editor = LineEditor::new();
+
+        for line in ["one", "two", "two", ""].iter() {
+            type_str(&mut editor, line);
+            editor.input(&key(ScanType::Enter, None));
+        }
+
+        assert_eq!(editor.history_len(), 2);
+        assert_eq!(editor.history(0), Some("two"));
+        assert_eq!(editor.history(1), Some("one"));
+
+        type_str(&mut editor, "dra");
+        assert_eq!(
+            editor.input(&key(ScanType::CursorUp, None)),
+            Some(LineEdit::Redraw {
+                column: 0,
+                text: "two",
+                cursor: 3
+            })
+        );
+        editor.input(&key(ScanType::CursorUp, None));
+        assert_eq!(editor.line(), "one");
+        assert_eq!(editor.input(&key(ScanType::CursorUp, None)), None);
+        editor.input(&key(ScanType::CursorDown, None));
+        editor.input(&key(ScanType::CursorDown, None));
+        assert_eq!(editor.line(), "dra");
+        assert_eq!(editor.input(&key(ScanType::CursorDown, None)), None);
+
+        // The ring drops the oldest lines
+        for ch in "abcdefghij".chars() {
+            editor.clear();
+            editor.input(&KeyEvent::Char(ch));
+            editor.input(&key(ScanType::Enter, None));
+        }
+
+        assert_eq!(editor.history_len(), HISTORY_CAPACITY);
+        assert_eq!(editor.history(0), Some("j"));
+        assert_eq!(editor.history(HISTORY_CAPACITY - 1), Some("c"));
+        assert_eq!(editor.history(HISTORY_CAPACITY), None);
+    }
+
+    #[test]
+    fn test_keyboard_events() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+        let mut editor = LineEditor::new();
+        let mut done = false;
+
+        // Shift+h, i, then the keypad Enter
+        for byte in [0x2a, 0x23, 0xa3, 0xaa, 0x17, 0x97, 0xe0, 0x1c].iter() {
+            let mut event = key_board.input_event(*byte);
+
+            while let Some(current) = event {
+                if let Some(LineEdit::Done(line)) = editor.input(&current) {
+                    assert_eq!(line, "Hi");
+                    done = true;
+                }
+
+                event = key_board.next_event();
+            }
+        }
+
+        assert!(done);
+    }
+}
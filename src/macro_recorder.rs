@@ -0,0 +1,502 @@
+use super::encoder::{Encoder, ScanCodeSequence};
+use super::hotkey::Hotkey;
+use super::layout::KeyModifierState;
+use super::scan_code_set::KeySet;
+use super::{Key, KeyState, ScanType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A recorded key and the milliseconds since the step before it, or since recording started
+pub struct MacroStep {
+    pub key: Key,
+    pub delay: u32,
+}
+
+impl MacroStep {
+    pub const fn new(key: Key, delay: u32) -> Self {
+        return Self { key, delay };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroTrigger {
+    Record,
+    Stop,
+    Play,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Hotkeys that control the recorder, the record hotkey also stops a recording
+pub struct MacroTriggers {
+    pub record: Option<Hotkey>,
+    pub stop: Option<Hotkey>,
+    pub play: Option<Hotkey>,
+}
+
+impl MacroTriggers {
+    pub const fn new() -> Self {
+        return Self {
+            record: None,
+            stop: None,
+            play: None,
+        };
+    }
+}
+
+impl Default for MacroTriggers {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[derive(Debug)]
+// Records keys into a buffer the caller owns. Feed it the keys as the reader decodes them so a
+// replay through a keyboard goes through the same remapping and layout. Timestamps are
+// milliseconds from the caller's monotonic clock.
+pub struct MacroRecorder<'a> {
+    buffer: &'a mut [MacroStep],
+    len: usize,
+    recording: bool,
+    last: u64,
+    // Recorded keys that haven't been released yet
+    held: KeySet,
+    triggers: MacroTriggers,
+    // The key of the trigger that fired, its release isn't recorded either
+    trigger_key: Option<Key>,
+}
+
+impl<'a> MacroRecorder<'a> {
+    pub fn new(buffer: &'a mut [MacroStep]) -> Self {
+        return Self {
+            buffer,
+            len: 0,
+            recording: false,
+            last: 0,
+            held: KeySet::new(),
+            triggers: MacroTriggers::new(),
+            trigger_key: None,
+        };
+    }
+
+    pub fn set_triggers(&mut self, triggers: MacroTriggers) {
+        self.triggers = triggers;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        return self.recording;
+    }
+
+    pub fn steps(&self) -> &[MacroStep] {
+        return &self.buffer[..self.len];
+    }
+
+    // Starts a new recording, dropping the previous one
+    pub fn start(&mut self, now: u64) {
+        self.len = 0;
+        self.recording = true;
+        self.last = now;
+        self.held = KeySet::new();
+    }
+
+    // Whether the key is released by a step after the given one
+    fn released_after(&self, index: usize, key: Key) -> bool {
+        return self.buffer[index + 1..self.len]
+            .iter()
+            .any(|step| step.key == key.inverted_state());
+    }
+
+    // Presses that were never released, like the modifiers of a stop hotkey, are left out
+    pub fn stop(&mut self) {
+        if !self.recording {
+            return;
+        }
+
+        self.recording = false;
+
+        let mut kept = 0;
+        let mut carried = 0u32;
+
+        for i in 0..self.len {
+            let step = self.buffer[i];
+
+            // Only the presses since the key was last released are still held
+            let unreleased = step.key.is_pressed()
+                && self.held.contains(&step.key)
+                && !self.released_after(i, step.key);
+
+            if unreleased {
+                carried = carried.saturating_add(step.delay);
+                continue;
+            }
+
+            self.buffer[kept] = MacroStep::new(step.key, step.delay.saturating_add(carried));
+            carried = 0;
+            kept += 1;
+        }
+
+        self.len = kept;
+        self.held = KeySet::new();
+    }
+
+    pub fn record(&mut self, key: Key, now: u64) -> Result<(), &'static str> {
+        if !self.recording {
+            return Err("Not recording.");
+        }
+
+        // Releases of keys that were already down when recording started
+        if !key.is_pressed() && !self.held.remove(&key) {
+            return Ok(());
+        }
+
+        if self.len == self.buffer.len() {
+            return Err("Macro buffer full.");
+        }
+
+        if key.is_pressed() {
+            self.held.insert(&key);
+        }
+
+        let delay = now.saturating_sub(self.last).min(u32::MAX as u64) as u32;
+        self.buffer[self.len] = MacroStep::new(key, delay);
+        self.len += 1;
+        self.last = now;
+
+        return Ok(());
+    }
+
+    // Handles the trigger hotkeys and records everything else while recording. Returns the
+    // trigger that fired, Play is left to the caller to start a MacroPlayer with the steps.
+    pub fn input(
+        &mut self,
+        key: Key,
+        modifiers: &KeyModifierState,
+        now: u64,
+    ) -> Option<MacroTrigger> {
+        if !key.is_pressed() && self.trigger_key == Some(key.inverted_state()) {
+            self.trigger_key = None;
+            return None;
+        }
+
+        let matches = |hotkey: Option<Hotkey>| match hotkey {
+            Some(hotkey) => hotkey.matches(&key, modifiers),
+            None => false,
+        };
+
+        let trigger = if matches(self.triggers.record) {
+            if self.recording {
+                Some(MacroTrigger::Stop)
+            } else {
+                Some(MacroTrigger::Record)
+            }
+        } else if matches(self.triggers.stop) {
+            Some(MacroTrigger::Stop)
+        } else if matches(self.triggers.play) && !self.recording {
+            Some(MacroTrigger::Play)
+        } else {
+            None
+        };
+
+        match trigger {
+            Some(MacroTrigger::Record) => self.start(now),
+            Some(MacroTrigger::Stop) => self.stop(),
+            Some(MacroTrigger::Play) => (),
+            None => {
+                // A full buffer ends the recording
+                if self.recording && self.record(key, now).is_err() {
+                    self.stop();
+                }
+            }
+        }
+
+        if trigger.is_some() {
+            self.trigger_key = Some(key);
+        }
+
+        return trigger;
+    }
+}
+
+#[derive(Debug, Clone)]
+// Replays recorded steps on the caller's clock, as keys or as scan codes
+pub struct MacroPlayer<'a> {
+    steps: &'a [MacroStep],
+    next: usize,
+    due: u64,
+}
+
+impl<'a> MacroPlayer<'a> {
+    pub fn new(steps: &'a [MacroStep], now: u64) -> Self {
+        let due = match steps.first() {
+            Some(step) => now + step.delay as u64,
+            None => now,
+        };
+
+        return Self {
+            steps,
+            next: 0,
+            due,
+        };
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.next == self.steps.len();
+    }
+
+    // The next key once its time has come, call until None to catch up
+    pub fn next_key(&mut self, now: u64) -> Option<Key> {
+        if self.is_finished() || now < self.due {
+            return None;
+        }
+
+        let key = self.steps[self.next].key;
+        self.next += 1;
+
+        if let Some(step) = self.steps.get(self.next) {
+            self.due += step.delay as u64;
+        }
+
+        return Some(key);
+    }
+
+    pub fn next_bytes(
+        &mut self,
+        encoder: &Encoder,
+        now: u64,
+    ) -> Option<Result<ScanCodeSequence, &'static str>> {
+        loop {
+            let key = self.next_key(now)?;
+
+            // Pause has no release code, its press already stands for both
+            if key.scan_type() == ScanType::Pause && key.state() == KeyState::Released {
+                continue;
+            }
+
+            return Some(encoder.encode(key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Reader, ReaderMode};
+    use super::*;
+
+    fn pressed(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Pressed);
+    }
+
+    fn released(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Released);
+    }
+
+    #[test]
+    fn test_record_with_triggers() {
+        let mut buffer = [MacroStep::new(pressed(ScanType::Unknown), 0); 8];
+        let mut recorder = MacroRecorder::new(&mut buffer);
+        recorder.set_triggers(MacroTriggers {
+            record: Some(Hotkey::parse("F9").unwrap()),
+            stop: Some(Hotkey::parse("Ctrl+F2").unwrap()),
+            play: Some(Hotkey::parse("F10").unwrap()),
+        });
+
+        let mut reader = Reader::new(ReaderMode::Set1);
+        let mut modifiers = KeyModifierState::new();
+        let mut triggers = [None; 9];
+        let input = [
+            (0x43, 0),
+            (0xc3, 5),
+            (0x1e, 100),
+            (0x9e, 150),
+            (0x1d, 200),
+            (0x3c, 210),
+            (0xbc, 220),
+            (0x9d, 230),
+            (0x44, 300),
+        ];
+
+        for (i, (byte, now)) in input.iter().enumerate() {
+            let key = reader.input_scan_code(*byte).unwrap().unwrap();
+            modifiers.apply_key(&key);
+            triggers[i] = recorder.input(key, &modifiers, *now);
+        }
+
+        assert_eq!(
+            triggers,
+            [
+                Some(MacroTrigger::Record),
+                None,
+                None,
+                None,
+                None,
+                Some(MacroTrigger::Stop),
+                None,
+                None,
+                Some(MacroTrigger::Play)
+            ]
+        );
+        assert!(!recorder.is_recording());
+
+        // The trigger keys and the Ctrl held for the stop hotkey are left out
+        assert_eq!(
+            recorder.steps(),
+            [
+                MacroStep::new(pressed(ScanType::CharA), 100),
+                MacroStep::new(released(ScanType::CharA), 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_skips_keys_held_before_start() {
+        let mut buffer = [MacroStep::new(pressed(ScanType::Unknown), 0); 4];
+        let mut recorder = MacroRecorder::new(&mut buffer);
+
+        assert_eq!(
+            recorder.record(pressed(ScanType::CharA), 0),
+            Err("Not recording.")
+        );
+
+        recorder.start(10);
+        recorder.record(released(ScanType::LeftShift), 20).unwrap();
+        recorder.record(pressed(ScanType::CharB), 30).unwrap();
+        recorder.record(pressed(ScanType::CharC), 35).unwrap();
+        recorder.record(released(ScanType::CharB), 40).unwrap();
+        recorder.stop();
+
+        // The unreleased C carries its delay over to the release of B
+        assert_eq!(
+            recorder.steps(),
+            [
+                MacroStep::new(pressed(ScanType::CharB), 20),
+                MacroStep::new(released(ScanType::CharB), 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stop_keeps_released_presses() {
+        let mut buffer = [MacroStep::new(pressed(ScanType::Unknown), 0); 8];
+        let mut recorder = MacroRecorder::new(&mut buffer);
+        recorder.set_triggers(MacroTriggers {
+            record: None,
+            stop: Some(Hotkey::parse("Ctrl+F2").unwrap()),
+            play: None,
+        });
+
+        let mut reader = Reader::new(ReaderMode::Set1);
+        let mut modifiers = KeyModifierState::new();
+
+        // Ctrl+C, then Ctrl+F2 to stop
+        recorder.start(0);
+        for (byte, now) in [
+            (0x1d, 10),
+            (0x2e, 20),
+            (0xae, 30),
+            (0x9d, 40),
+            (0x1d, 50),
+            (0x3c, 60),
+        ] {
+            let key = reader.input_scan_code(byte).unwrap().unwrap();
+            modifiers.apply_key(&key);
+            recorder.input(key, &modifiers, now);
+        }
+        assert!(!recorder.is_recording());
+
+        // Only the Ctrl held for the stop hotkey is left out
+        assert_eq!(
+            recorder.steps(),
+            [
+                MacroStep::new(pressed(ScanType::LeftCtrl), 10),
+                MacroStep::new(pressed(ScanType::CharC), 10),
+                MacroStep::new(released(ScanType::CharC), 10),
+                MacroStep::new(released(ScanType::LeftCtrl), 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_keypad_apart() {
+        let mut buffer = [MacroStep::new(pressed(ScanType::Unknown), 0); 4];
+        let mut recorder = MacroRecorder::new(&mut buffer);
+        let keypad = Key::new_keypad(ScanType::Num7, KeyState::Pressed);
+
+        // The top-row 7 was down before recording, its release doesn't pair with the keypad 7
+        recorder.start(0);
+        recorder.record(keypad, 10).unwrap();
+        recorder.record(released(ScanType::Num7), 20).unwrap();
+        recorder.record(keypad.inverted_state(), 30).unwrap();
+        recorder.stop();
+
+        assert_eq!(
+            recorder.steps(),
+            [
+                MacroStep::new(keypad, 10),
+                MacroStep::new(keypad.inverted_state(), 20)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_buffer_full_stops_recording() {
+        let mut buffer = [MacroStep::new(pressed(ScanType::Unknown), 0); 2];
+        let mut recorder = MacroRecorder::new(&mut buffer);
+        let modifiers = KeyModifierState::new();
+
+        recorder.start(0);
+        recorder.input(pressed(ScanType::CharA), &modifiers, 1);
+        recorder.input(released(ScanType::CharA), &modifiers, 2);
+        assert!(recorder.is_recording());
+        assert_eq!(
+            recorder.record(pressed(ScanType::CharB), 3),
+            Err("Macro buffer full.")
+        );
+
+        recorder.input(pressed(ScanType::CharB), &modifiers, 3);
+        assert!(!recorder.is_recording());
+        assert_eq!(recorder.steps().len(), 2);
+    }
+
+    #[test]
+    fn test_play_keys() {
+        let steps = [
+            MacroStep::new(pressed(ScanType::CharA), 100),
+            MacroStep::new(released(ScanType::CharA), 50),
+        ];
+        let mut player = MacroPlayer::new(&steps, 1000);
+
+        assert_eq!(player.next_key(1099), None);
+        assert_eq!(player.next_key(1100), Some(pressed(ScanType::CharA)));
+        assert_eq!(player.next_key(1100), None);
+        assert!(!player.is_finished());
+        assert_eq!(player.next_key(1150), Some(released(ScanType::CharA)));
+        assert!(player.is_finished());
+        assert_eq!(player.next_key(2000), None);
+    }
+
+    #[test]
+    fn test_play_bytes() {
+        let steps = [
+            MacroStep::new(pressed(ScanType::CursorUp), 0),
+            MacroStep::new(pressed(ScanType::Pause), 10),
+            MacroStep::new(released(ScanType::Pause), 10),
+            MacroStep::new(released(ScanType::CursorUp), 10),
+        ];
+        let encoder = Encoder::new(ReaderMode::Set1);
+        let mut player = MacroPlayer::new(&steps, 0);
+
+        assert_eq!(
+            player.next_bytes(&encoder, 0).unwrap().unwrap().as_slice(),
+            [0xe0, 0x48]
+        );
+        assert_eq!(
+            player.next_bytes(&encoder, 10).unwrap().unwrap().as_slice(),
+            [0xe1, 0x1d, 0x45, 0xe1, 0x9d, 0xc5]
+        );
+
+        // Catching up skips the release of pause
+        assert_eq!(
+            player.next_bytes(&encoder, 30).unwrap().unwrap().as_slice(),
+            [0xe0, 0xc8]
+        );
+        assert!(player.is_finished());
+    }
+}
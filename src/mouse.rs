@@ -0,0 +1,196 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MouseButtons {
+    pub left: bool,
+    pub right: bool,
+    pub middle: bool,
+}
+
+impl MouseButtons {
+    pub const fn new() -> Self {
+        return Self {
+            left: false,
+            right: false,
+            middle: false,
+        };
+    }
+
+    pub fn any(&self) -> bool {
+        return self.left || self.right || self.middle;
+    }
+
+    pub(crate) fn union(self, other: MouseButtons) -> MouseButtons {
+        return MouseButtons {
+            left: self.left || other.left,
+            right: self.right || other.right,
+            middle: self.middle || other.middle,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+// One standard PS/2 mouse packet: movement since the last packet and the buttons held now.
+// Positive dy is up, as the mouse reports it.
+pub struct MouseEvent {
+    pub dx: i16,
+    pub dy: i16,
+    pub buttons: MouseButtons,
+}
+
+// The movement fields are 9 bit two's complement, with the sign in the first byte
+const MOVEMENT_MIN: i16 = -256;
+const MOVEMENT_MAX: i16 = 255;
+
+impl MouseEvent {
+    pub const fn new(dx: i16, dy: i16, buttons: MouseButtons) -> Self {
+        return Self { dx, dy, buttons };
+    }
+
+    // Decodes a 3 byte packet. Movement that overflowed is reported as the largest value.
+    pub fn from_packet(packet: [u8; 3]) -> Result<MouseEvent, &'static str> {
+        let flags = packet[0];
+
+        if flags & 0x08 == 0 {
+            return Err("Not the first byte of a mouse packet.");
+        }
+
+        let movement = |value: u8, sign: u8, overflow: u8| -> i16 {
+            let negative = flags & sign != 0;
+
+            return match (flags & overflow != 0, negative) {
+                (true, true) => MOVEMENT_MIN,
+                (true, false) => MOVEMENT_MAX,
+                (false, true) => value as i16 - 0x100,
+                (false, false) => value as i16,
+            };
+        };
+
+        return Ok(MouseEvent {
+            dx: movement(packet[1], 0x10, 0x40),
+            dy: movement(packet[2], 0x20, 0x80),
+            buttons: MouseButtons {
+                left: flags & 0x01 != 0,
+                right: flags & 0x02 != 0,
+                middle: flags & 0x04 != 0,
+            },
+        });
+    }
+
+    // Encodes the event as a packet, movement outside -256..=255 sets the overflow bit
+    pub fn to_packet(&self) -> [u8; 3] {
+        let mut flags = 0x08;
+
+        let mut movement = |value: i16, sign: u8, overflow: u8| -> u8 {
+            if !(MOVEMENT_MIN..=MOVEMENT_MAX).contains(&value) {
+                flags |= overflow;
+            }
+
+            let value = value.clamp(MOVEMENT_MIN, MOVEMENT_MAX);
+
+            if value < 0 {
+                flags |= sign;
+            }
+
+            return value as u8;
+        };
+
+        let dx = movement(self.dx, 0x10, 0x40);
+        let dy = movement(self.dy, 0x20, 0x80);
+
+        if self.buttons.left {
+            flags |= 0x01;
+        }
+
+        if self.buttons.right {
+            flags |= 0x02;
+        }
+
+        if self.buttons.middle {
+            flags |= 0x04;
+        }
+
+        return [flags, dx, dy];
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+// Collects the bytes of a PS/2 mouse stream into packets
+pub struct MouseReader {
+    packet: [u8; 3],
+    len: usize,
+}
+
+impl MouseReader {
+    pub const fn new() -> Self {
+        return Self {
+            packet: [0u8; 3],
+            len: 0,
+        };
+    }
+
+    // Returns the event once a packet is complete. A first byte without the always set bit is
+    // dropped so the reader falls back in step with the mouse.
+    pub fn input_byte(&mut self, byte: u8) -> Result<Option<MouseEvent>, &'static str> {
+        if self.len == 0 && byte & 0x08 == 0 {
+            return Err("Not the first byte of a mouse packet.");
+        }
+
+        self.packet[self.len] = byte;
+        self.len += 1;
+
+        if self.len < self.packet.len() {
+            return Ok(None);
+        }
+
+        self.len = 0;
+
+        return MouseEvent::from_packet(self.packet).map(Some);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: MouseButtons = MouseButtons {
+        left: true,
+        right: false,
+        middle: false,
+    };
+
+    #[test]
+    fn test_packet_round_trip() {
+        let events = [
+            MouseEvent::new(0, 0, MouseButtons::new()),
+            MouseEvent::new(5, -3, LEFT),
+            MouseEvent::new(-256, 255, MouseButtons::new()),
+        ];
+
+        for event in events.iter() {
+            assert_eq!(MouseEvent::from_packet(event.to_packet()), Ok(*event));
+        }
+
+        assert_eq!(MouseEvent::new(5, -3, LEFT).to_packet(), [0x29, 0x05, 0xfd]);
+        assert_eq!(
+            MouseEvent::new(300, -300, MouseButtons::new()).to_packet(),
+            [0xe8, 0xff, 0x00]
+        );
+        assert_eq!(
+            MouseEvent::from_packet([0xe8, 0xff, 0x00]),
+            Ok(MouseEvent::new(255, -256, MouseButtons::new()))
+        );
+        assert!(MouseEvent::from_packet([0x00, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_reader() {
+        let mut reader = MouseReader::new();
+
+        assert!(reader.input_byte(0x01).is_err());
+        assert_eq!(reader.input_byte(0x29), Ok(None));
+        assert_eq!(reader.input_byte(0x05), Ok(None));
+        assert_eq!(
+            reader.input_byte(0xfd),
+            Ok(Some(MouseEvent::new(5, -3, LEFT)))
+        );
+    }
+}
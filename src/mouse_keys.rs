@@ -0,0 +1,476 @@
+use super::event::EventQueue;
+use super::hotkey::Hotkey;
+use super::layout::KeyModifierState;
+use super::mouse::{MouseButtons, MouseEvent};
+use super::scan_code_set::ScanTypeSet;
+use super::{Key, ScanType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// How the pointer speeds up while a direction is held
+pub enum AccelerationCurve {
+    // Always the initial speed
+    Constant,
+    // Speeds up evenly until the maximum
+    Linear,
+    // Stays slow for fine positioning at first, then catches up
+    Quadratic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Speeds are in pointer units per step, times in milliseconds
+pub struct MouseKeysConfig {
+    pub initial_speed: u16,
+    pub max_speed: u16,
+    // How long a direction has to be held to reach the maximum speed
+    pub time_to_max: u32,
+    // Time between steps while a direction is held
+    pub interval: u32,
+    pub curve: AccelerationCurve,
+}
+
+impl MouseKeysConfig {
+    pub const fn new() -> Self {
+        return Self {
+            initial_speed: 1,
+            max_speed: 20,
+            time_to_max: 1000,
+            interval: 20,
+            curve: AccelerationCurve::Linear,
+        };
+    }
+
+    // The distance of one step after holding a direction for the given time
+    pub fn speed(&self, held: u64) -> u16 {
+        let range = self.max_speed.saturating_sub(self.initial_speed) as u64;
+        let time_to_max = self.time_to_max.max(1) as u64;
+        let held = held.min(time_to_max);
+
+        let extra = match self.curve {
+            AccelerationCurve::Constant => 0,
+            AccelerationCurve::Linear => range * held / time_to_max,
+            AccelerationCurve::Quadratic => range * held * held / (time_to_max * time_to_max),
+        };
+
+        return self.initial_speed.saturating_add(extra as u16);
+    }
+}
+
+impl Default for MouseKeysConfig {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+// The direction of the keypad digits around 5, positive dy is up like a PS/2 mouse reports it
+fn direction(scan_type: ScanType) -> Option<(i16, i16)> {
+    return match scan_type {
+        ScanType::Num1 => Some((-1, -1)),
+        ScanType::Num2 => Some((0, -1)),
+        ScanType::Num3 => Some((1, -1)),
+        ScanType::Num4 => Some((-1, 0)),
+        ScanType::Num6 => Some((1, 0)),
+        ScanType::Num7 => Some((-1, 1)),
+        ScanType::Num8 => Some((0, 1)),
+        ScanType::Num9 => Some((1, 1)),
+        _ => None,
+    };
+}
+
+const LEFT: MouseButtons = MouseButtons {
+    left: true,
+    right: false,
+    middle: false,
+};
+
+const RIGHT: MouseButtons = MouseButtons {
+    left: false,
+    right: true,
+    middle: false,
+};
+
+const BOTH: MouseButtons = MouseButtons {
+    left: true,
+    right: true,
+    middle: false,
+};
+
+// Steps caught up in one tick, after a long gap the pointer doesn't jump across the screen
+const MAX_CATCH_UP_STEPS: u32 = 64;
+
+#[derive(Debug, Clone, Copy)]
+// Mouse Keys: while active the keypad drives the pointer. The digits around 5 move it, 5 clicks,
+// '+' double clicks, 0 holds the button down for dragging and '.' lets go of it. '/', '-' and '*'
+// pick the left, right or both buttons. Movement repeats on the caller's timestamps, from
+// input or tick, and speeds up along the configured curve.
+pub struct MouseKeys {
+    config: MouseKeysConfig,
+    active: bool,
+    toggle: Option<Hotkey>,
+    // The key that last switched Mouse Keys while it is held, its repeats and release are ours
+    toggle_key: Option<Key>,
+    // The keypad keys whose presses Mouse Keys used, only their releases are kept from the
+    // keyboard
+    taken: ScanTypeSet,
+    button: MouseButtons,
+    locked: MouseButtons,
+    // Bit n is set while keypad n is held, the digits are the first scan types
+    directions: u16,
+    move_start: u64,
+    next_step: u64,
+    events: EventQueue<MouseEvent>,
+}
+
+impl MouseKeys {
+    pub const fn new(config: MouseKeysConfig) -> Self {
+        return Self {
+            config,
+            active: false,
+            toggle: None,
+            toggle_key: None,
+            taken: ScanTypeSet::new(),
+            button: LEFT,
+            locked: MouseButtons::new(),
+            directions: 0,
+            move_start: 0,
+            next_step: 0,
+            events: EventQueue::new(),
+        };
+    }
+
+    pub fn config(&self) -> &MouseKeysConfig {
+        return &self.config;
+    }
+
+    pub fn set_config(&mut self, config: MouseKeysConfig) {
+        self.config = config;
+    }
+
+    pub fn is_active(&self) -> bool {
+        return self.active;
+    }
+
+    // Deactivating lets go of a locked button and stops any movement
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+
+        if !active {
+            self.directions = 0;
+
+            if self.locked.any() {
+                self.locked = MouseButtons::new();
+                self.push(0, 0, MouseButtons::new());
+            }
+        }
+    }
+
+    // The hotkey that switches Mouse Keys on and off, e.g. "LeftAlt+LeftShift+NumLock"
+    pub fn set_toggle(&mut self, hotkey: Option<Hotkey>) {
+        self.toggle = hotkey;
+    }
+
+    pub fn next_event(&mut self) -> Option<MouseEvent> {
+        return self.events.pop();
+    }
+
+    fn push(&mut self, dx: i16, dy: i16, buttons: MouseButtons) {
+        self.events.push(MouseEvent::new(dx, dy, buttons));
+    }
+
+    // The held keys added up, opposite keys cancel out
+    fn direction(&self) -> (i16, i16) {
+        let (mut x, mut y) = (0i16, 0i16);
+
+        for scan_type in ScanType::ALL[..10].iter() {
+            if self.directions & (1 << *scan_type as u16) == 0 {
+                continue;
+            }
+
+            if let Some((dx, dy)) = direction(*scan_type) {
+                x += dx;
+                y += dy;
+            }
+        }
+
+        return (x.signum(), y.signum());
+    }
+
+    fn click(&mut self, count: usize) {
+        for _ in 0..count {
+            self.push(0, 0, self.locked.union(self.button));
+            self.push(0, 0, self.locked);
+        }
+    }
+
+    // Returns whether Mouse Keys used the key, the keyboard should ignore it then
+    pub fn input(&mut self, key: Key, modifiers: &KeyModifierState, now: u64) -> bool {
+        self.tick(now);
+
+        let scan_type = key.scan_type();
+
+        if let Some(toggle_key) = self.toggle_key {
+            if toggle_key.scan_type() == scan_type && toggle_key.keypad() == key.keypad() {
+                if !key.is_pressed() {
+                    self.toggle_key = None;
+                }
+
+                return true;
+            }
+        }
+
+        if let Some(toggle) = self.toggle {
+            if toggle.matches(&key, modifiers) {
+                self.set_active(!self.active);
+                self.toggle_key = Some(key);
+                return true;
+            }
+        }
+
+        if !key.is_pressed() {
+            // A key pressed before Mouse Keys took over is released to the keyboard
+            if !key.keypad() || !self.taken.remove(scan_type) {
+                return false;
+            }
+
+            if direction(scan_type).is_some() {
+                self.directions &= !(1 << scan_type as u16);
+            }
+
+            return true;
+        }
+
+        if !self.active || !key.keypad() {
+            return false;
+        }
+
+        if direction(scan_type).is_some() {
+            let bit = 1 << scan_type as u16;
+
+            if self.directions & bit == 0 {
+                // A new direction restarts the acceleration
+                self.directions |= bit;
+                self.move_start = now;
+                self.next_step = now + self.config.interval.max(1) as u64;
+
+                let (x, y) = self.direction();
+                let speed = self.config.initial_speed as i16;
+                self.push(x * speed, y * speed, self.locked);
+            }
+
+            self.taken.insert(scan_type);
+
+            return true;
+        }
+
+        match scan_type {
+            ScanType::Num5 => self.click(1),
+            ScanType::SymbolPlus => self.click(2),
+            ScanType::Num0 => {
+                self.locked = self.locked.union(self.button);
+                self.push(0, 0, self.locked);
+            }
+            ScanType::SymbolPeriod => {
+                self.locked = MouseButtons::new();
+                self.push(0, 0, self.locked);
+            }
+            ScanType::SymbolForwardSlash => self.button = LEFT,
+            ScanType::SymbolMinus => self.button = RIGHT,
+            ScanType::SymbolAsterisk => self.button = BOTH,
+            _ => return false,
+        }
+
+        self.taken.insert(scan_type);
+
+        return true;
+    }
+
+    // Moves the pointer for the steps that are due by now, as a single event
+    pub fn tick(&mut self, now: u64) {
+        if self.directions == 0 {
+            return;
+        }
+
+        let (x, y) = self.direction();
+
+        let interval = self.config.interval.max(1) as u64;
+        let (mut dx, mut dy) = (0i16, 0i16);
+        let mut steps = 0;
+
+        while self.next_step <= now {
+            if steps == MAX_CATCH_UP_STEPS {
+                self.next_step = now + interval;
+                break;
+            }
+
+            let speed = self.config.speed(self.next_step - self.move_start) as i16;
+            dx = dx.saturating_add(x * speed);
+            dy = dy.saturating_add(y * speed);
+            self.next_step += interval;
+            steps += 1;
+        }
+
+        if dx != 0 || dy != 0 {
+            self.push(dx, dy, self.locked);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::KeyState;
+    use super::*;
+
+    fn press(mouse_keys: &mut MouseKeys, scan_type: ScanType, now: u64) -> bool {
+        let key = Key::new_keypad(scan_type, KeyState::Pressed);
+
+        return mouse_keys.input(key, &KeyModifierState::new(), now);
+    }
+
+    fn release(mouse_keys: &mut MouseKeys, scan_type: ScanType, now: u64) -> bool {
+        let key = Key::new_keypad(scan_type, KeyState::Released);
+
+        return mouse_keys.input(key, &KeyModifierState::new(), now);
+    }
+
+    fn buttons(mouse_keys: &mut MouseKeys) -> Option<MouseButtons> {
+        return mouse_keys.next_event().map(|event| event.buttons);
+    }
+
+    #[test]
+    fn test_curves() {
+        let mut config = MouseKeysConfig::new();
+        config.initial_speed = 2;
+        config.max_speed = 12;
+        config.time_to_max = 1000;
+
+        assert_eq!(config.speed(0), 2);
+        assert_eq!(config.speed(500), 7);
+        assert_eq!(config.speed(5000), 12);
+
+        config.curve = AccelerationCurve::Quadratic;
+        assert_eq!(config.speed(500), 4);
+        assert_eq!(config.speed(1000), 12);
+
+        config.curve = AccelerationCurve::Constant;
+        assert_eq!(config.speed(1000), 2);
+    }
+
+    #[test]
+    fn test_movement() {
+        let mut config = MouseKeysConfig::new();
+        config.initial_speed = 1;
+        config.max_speed = 11;
+        config.time_to_max = 100;
+        config.interval = 10;
+
+        let mut mouse_keys = MouseKeys::new(config);
+
+        // Inactive it leaves the keypad alone
+        assert!(!press(&mut mouse_keys, ScanType::Num8, 0));
+        mouse_keys.set_active(true);
+
+        assert!(press(&mut mouse_keys, ScanType::Num8, 0));
+        assert_eq!(
+            mouse_keys.next_event(),
+            Some(MouseEvent::new(0, 1, MouseButtons::new()))
+        );
+
+        // Typematic repeats don't move, the timestamps do
+        assert!(press(&mut mouse_keys, ScanType::Num8, 5));
+        assert_eq!(mouse_keys.next_event(), None);
+        mouse_keys.tick(20);
+        assert_eq!(
+            mouse_keys.next_event(),
+            Some(MouseEvent::new(0, 5, MouseButtons::new()))
+        );
+
+        // Diagonals and opposite keys
+        assert!(press(&mut mouse_keys, ScanType::Num4, 20));
+        assert_eq!(
+            mouse_keys.next_event(),
+            Some(MouseEvent::new(-1, 1, MouseButtons::new()))
+        );
+        mouse_keys.tick(30);
+        assert_eq!(
+            mouse_keys.next_event(),
+            Some(MouseEvent::new(-2, 2, MouseButtons::new()))
+        );
+        assert!(press(&mut mouse_keys, ScanType::Num6, 30));
+        mouse_keys.next_event();
+        mouse_keys.tick(40);
+        assert_eq!(
+            mouse_keys.next_event(),
+            Some(MouseEvent::new(0, 2, MouseButtons::new()))
+        );
+
+        assert!(release(&mut mouse_keys, ScanType::Num4, 40));
+        assert!(release(&mut mouse_keys, ScanType::Num6, 40));
+        assert!(release(&mut mouse_keys, ScanType::Num8, 40));
+        mouse_keys.tick(100);
+        assert_eq!(mouse_keys.next_event(), None);
+    }
+
+    #[test]
+    fn test_buttons() {
+        let mut mouse_keys = MouseKeys::new(MouseKeysConfig::new());
+        mouse_keys.set_active(true);
+
+        assert!(press(&mut mouse_keys, ScanType::Num5, 0));
+        assert!(release(&mut mouse_keys, ScanType::Num5, 0));
+        assert_eq!(buttons(&mut mouse_keys), Some(LEFT));
+        assert_eq!(buttons(&mut mouse_keys), Some(MouseButtons::new()));
+        assert_eq!(buttons(&mut mouse_keys), None);
+
+        assert!(press(&mut mouse_keys, ScanType::SymbolMinus, 0));
+        assert!(press(&mut mouse_keys, ScanType::SymbolPlus, 0));
+        assert_eq!(buttons(&mut mouse_keys), Some(RIGHT));
+        assert_eq!(buttons(&mut mouse_keys), Some(MouseButtons::new()));
+        assert_eq!(buttons(&mut mouse_keys), Some(RIGHT));
+        assert_eq!(buttons(&mut mouse_keys), Some(MouseButtons::new()));
+
+        // Drag lock holds the button through movement until '.'
+        assert!(press(&mut mouse_keys, ScanType::SymbolForwardSlash, 0));
+        assert!(press(&mut mouse_keys, ScanType::Num0, 0));
+        assert_eq!(buttons(&mut mouse_keys), Some(LEFT));
+        assert!(press(&mut mouse_keys, ScanType::Num6, 0));
+        assert_eq!(mouse_keys.next_event(), Some(MouseEvent::new(1, 0, LEFT)));
+        assert!(press(&mut mouse_keys, ScanType::SymbolPeriod, 0));
+        assert_eq!(buttons(&mut mouse_keys), Some(MouseButtons::new()));
+
+        // Keys Mouse Keys has no use for pass through
+        assert!(!press(&mut mouse_keys, ScanType::Enter, 0));
+        assert!(!mouse_keys.input(
+            Key::new(ScanType::Num5, KeyState::Pressed),
+            &KeyModifierState::new(),
+            0
+        ));
+    }
+
+    #[test]
+    fn test_releases() {
+        let mut mouse_keys = MouseKeys::new(MouseKeysConfig::new());
+        mouse_keys.set_toggle(Some("Alt+Shift+NumLock".parse().unwrap()));
+
+        // Held before Mouse Keys was switched on, the release goes to the keyboard
+        assert!(!press(&mut mouse_keys, ScanType::Num8, 0));
+        mouse_keys.set_active(true);
+        assert!(!release(&mut mouse_keys, ScanType::Num8, 10));
+        assert!(!release(&mut mouse_keys, ScanType::Num5, 10));
+
+        // Taken while active, released after switching off
+        assert!(press(&mut mouse_keys, ScanType::Num4, 20));
+        mouse_keys.set_active(false);
+        assert!(release(&mut mouse_keys, ScanType::Num4, 30));
+
+        // The toggle's repeats don't switch again and its release is swallowed
+        let mut modifiers = KeyModifierState::new();
+        modifiers.left_alt = true;
+        modifiers.left_shift = true;
+        let num_lock = Key::new(ScanType::NumLock, KeyState::Pressed);
+        assert!(mouse_keys.input(num_lock, &modifiers, 40));
+        assert!(mouse_keys.input(num_lock, &modifiers, 50));
+        assert!(mouse_keys.is_active());
+        let num_lock = Key::new(ScanType::NumLock, KeyState::Released);
+        assert!(mouse_keys.input(num_lock, &KeyModifierState::new(), 60));
+        assert!(!mouse_keys.input(num_lock, &KeyModifierState::new(), 70));
+    }
+}
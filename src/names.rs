@@ -0,0 +1,411 @@
+use super::encoder::Encoder;
+use super::{Key, KeyState, ScanType};
+use core::fmt;
+use core::str::FromStr;
+
+// Alternative spellings accepted when parsing, the bool marks a keypad key
+const ALIASES: [(&str, ScanType, bool); 84] = [
+    ("ctrl", ScanType::LeftCtrl, false),
+    ("control", ScanType::LeftCtrl, false),
+    ("lctrl", ScanType::LeftCtrl, false),
+    ("lcontrol", ScanType::LeftCtrl, false),
+    ("rctrl", ScanType::RightCtrl, false),
+    ("rcontrol", ScanType::RightCtrl, false),
+    ("shift", ScanType::LeftShift, false),
+    ("lshift", ScanType::LeftShift, false),
+    ("rshift", ScanType::RightShift, false),
+    ("alt", ScanType::LeftAlt, false),
+    ("lalt", ScanType::LeftAlt, false),
+    ("option", ScanType::LeftAlt, false),
+    ("ralt", ScanType::RightAlt, false),
+    ("altgr", ScanType::RightAlt, false),
+    ("gui", ScanType::LeftGUI, false),
+    ("lgui", ScanType::LeftGUI, false),
+    ("win", ScanType::LeftGUI, false),
+    ("lwin", ScanType::LeftGUI, false),
+    ("super", ScanType::LeftGUI, false),
+    ("meta", ScanType::LeftGUI, false),
+    ("cmd", ScanType::LeftGUI, false),
+    ("rgui", ScanType::RightGUI, false),
+    ("rwin", ScanType::RightGUI, false),
+    ("rsuper", ScanType::RightGUI, false),
+    ("esc", ScanType::Escape, false),
+    ("bksp", ScanType::Backspace, false),
+    ("bs", ScanType::Backspace, false),
+    ("return", ScanType::Enter, false),
+    ("spacebar", ScanType::Space, false),
+    ("pgup", ScanType::PageUp, false),
+    ("prior", ScanType::PageUp, false),
+    ("pgdn", ScanType::PageDown, false),
+    ("pgdown", ScanType::PageDown, false),
+    ("next", ScanType::PageDown, false),
+    ("arrowup", ScanType::CursorUp, false),
+    ("arrowdown", ScanType::CursorDown, false),
+    ("arrowleft", ScanType::CursorLeft, false),
+    ("arrowright", ScanType::CursorRight, false),
+    ("ins", ScanType::Insert, false),
+    ("del", ScanType::Delete, false),
+    ("numlk", ScanType::NumLock, false),
+    ("scrlk", ScanType::ScrollLock, false),
+    ("caps", ScanType::CapsLock, false),
+    ("prtsc", ScanType::PrintScreen, false),
+    ("prtscr", ScanType::PrintScreen, false),
+    ("sysrq", ScanType::PrintScreen, false),
+    ("break", ScanType::Pause, false),
+    ("menu", ScanType::Apps, false),
+    ("application", ScanType::Apps, false),
+    ("power", ScanType::ACPIPower, false),
+    ("sleep", ScanType::ACPISleep, false),
+    ("wake", ScanType::ACPIWake, false),
+    ("plus", ScanType::SymbolPlus, true),
+    ("minus", ScanType::SymbolMinus, false),
+    ("dash", ScanType::SymbolMinus, false),
+    ("equals", ScanType::SymbolEquals, false),
+    ("lbracket", ScanType::SymbolOpenSquareBracket, false),
+    ("openbracket", ScanType::SymbolOpenSquareBracket, false),
+    ("rbracket", ScanType::SymbolCloseSquareBracket, false),
+    ("closebracket", ScanType::SymbolCloseSquareBracket, false),
+    ("semicolon", ScanType::SymbolSemicolon, false),
+    ("quote", ScanType::SymbolSingleQuote, false),
+    ("apostrophe", ScanType::SymbolSingleQuote, false),
+    ("backtick", ScanType::SymbolBacktick, false),
+    ("grave", ScanType::SymbolBacktick, false),
+    ("tilde", ScanType::SymbolBacktick, false),
+    ("backslash", ScanType::SymbolBackslash, false),
+    ("intlbackslash", ScanType::Iso102nd, false),
+    ("oem102", ScanType::Iso102nd, false),
+    ("kana", ScanType::KatakanaHiragana, false),
+    ("convert", ScanType::Henkan, false),
+    ("nonconvert", ScanType::Muhenkan, false),
+    ("zenkakuhankaku", ScanType::SymbolBacktick, false),
+    ("comma", ScanType::SymbolComma, false),
+    ("period", ScanType::SymbolPeriod, false),
+    ("dot", ScanType::SymbolPeriod, false),
+    ("slash", ScanType::SymbolForwardSlash, false),
+    ("asterisk", ScanType::SymbolAsterisk, true),
+    ("multiply", ScanType::SymbolAsterisk, true),
+    ("add", ScanType::SymbolPlus, true),
+    ("subtract", ScanType::SymbolMinus, true),
+    ("decimal", ScanType::SymbolPeriod, true),
+    ("divide", ScanType::SymbolForwardSlash, true),
+    ("kpenter", ScanType::Enter, true),
+];
+
+impl ScanType {
+    // The canonical human-readable name of the key
+    pub fn name(self) -> &'static str {
+        use ScanType::*;
+
+        return match self {
+            Num0 => "0",
+            Num1 => "1",
+            Num2 => "2",
+            Num3 => "3",
+            Num4 => "4",
+            Num5 => "5",
+            Num6 => "6",
+            Num7 => "7",
+            Num8 => "8",
+            Num9 => "9",
+            CharA => "A",
+            CharB => "B",
+            CharC => "C",
+            CharD => "D",
+            CharE => "E",
+            CharF => "F",
+            CharG => "G",
+            CharH => "H",
+            CharI => "I",
+            CharJ => "J",
+            CharK => "K",
+            CharL => "L",
+            CharM => "M",
+            CharN => "N",
+            CharO => "O",
+            CharP => "P",
+            CharQ => "Q",
+            CharR => "R",
+            CharS => "S",
+            CharT => "T",
+            CharU => "U",
+            CharV => "V",
+            CharW => "W",
+            CharX => "X",
+            CharY => "Y",
+            CharZ => "Z",
+            SymbolPlus => "+",
+            SymbolMinus => "-",
+            SymbolEquals => "=",
+            SymbolOpenSquareBracket => "[",
+            SymbolCloseSquareBracket => "]",
+            SymbolSemicolon => ";",
+            SymbolSingleQuote => "'",
+            SymbolBacktick => "`",
+            SymbolBackslash => "\\",
+            SymbolComma => ",",
+            SymbolPeriod => ".",
+            SymbolForwardSlash => "/",
+            SymbolAsterisk => "*",
+            Escape => "Escape",
+            Backspace => "Backspace",
+            Tab => "Tab",
+            Enter => "Enter",
+            LeftCtrl => "Left Ctrl",
+            RightCtrl => "Right Ctrl",
+            LeftShift => "Left Shift",
+            RightShift => "Right Shift",
+            LeftAlt => "Left Alt",
+            RightAlt => "Right Alt",
+            LeftGUI => "Left GUI",
+            RightGUI => "Right GUI",
+            Space => "Space",
+            F1 => "F1",
+            F2 => "F2",
+            F3 => "F3",
+            F4 => "F4",
+            F5 => "F5",
+            F6 => "F6",
+            F7 => "F7",
+            F8 => "F8",
+            F9 => "F9",
+            F10 => "F10",
+            F11 => "F11",
+            F12 => "F12",
+            NumLock => "Num Lock",
+            ScrollLock => "Scroll Lock",
+            CapsLock => "Caps Lock",
+            Home => "Home",
+            PageUp => "Page Up",
+            PageDown => "Page Down",
+            CursorUp => "Up",
+            CursorLeft => "Left",
+            CursorRight => "Right",
+            CursorDown => "Down",
+            Insert => "Insert",
+            Delete => "Delete",
+            End => "End",
+            ACPIPower => "ACPI Power",
+            ACPISleep => "ACPI Sleep",
+            ACPIWake => "ACPI Wake",
+            PreviousTrack => "Previous Track",
+            NextTrack => "Next Track",
+            Mute => "Mute",
+            Calculator => "Calculator",
+            Stop => "Stop",
+            Play => "Play",
+            WWWHome => "WWW Home",
+            VolumeUp => "Volume Up",
+            VolumeDown => "Volume Down",
+            Apps => "Apps",
+            WWWSearch => "WWW Search",
+            WWWFavorites => "WWW Favorites",
+            WWWRefresh => "WWW Refresh",
+            WWWStop => "WWW Stop",
+            WWWForward => "WWW Forward",
+            WWWBack => "WWW Back",
+            MyComputer => "My Computer",
+            Email => "Email",
+            MediaSelect => "Media Select",
+            PrintScreen => "Print Screen",
+            Pause => "Pause",
+            Iso102nd => "ISO 102nd",
+            KatakanaHiragana => "Katakana Hiragana",
+            Ro => "Ro",
+            Henkan => "Henkan",
+            Muhenkan => "Muhenkan",
+            Yen => "Yen",
+            Unknown => "Unknown",
+        };
+    }
+}
+
+// Compares ignoring ASCII case, spaces and underscores so "Left Ctrl", "left_ctrl" and
+// "LEFTCTRL" are all the same name.
+fn names_match(input: &str, name: &str) -> bool {
+    let mut a = input.bytes().filter(|b| *b != b' ' && *b != b'_');
+    let mut b = name.bytes().filter(|b| *b != b' ' && *b != b'_');
+
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) if x.eq_ignore_ascii_case(&y) => (),
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(input: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = input.get(..prefix.len())?;
+
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    return Some(input[prefix.len()..].trim_start_matches([' ', '_']));
+}
+
+// Returns the scan type and whether the name refers to the keypad
+pub(crate) fn parse_key_name(name: &str) -> Option<(ScanType, bool)> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    for scan_type in ScanType::ALL.iter() {
+        if names_match(name, scan_type.name()) {
+            // Keys like '+' and '*' only exist on the keypad
+            let keypad_only = Encoder::base_code_s1(*scan_type, false).is_none()
+                && Encoder::base_code_s1(*scan_type, true).is_some();
+
+            return Some((*scan_type, keypad_only));
+        }
+    }
+
+    for (alias, scan_type, keypad) in ALIASES.iter() {
+        if names_match(name, alias) {
+            return Some((*scan_type, *keypad));
+        }
+    }
+
+    for prefix in ["numpad", "keypad", "kp"].iter() {
+        if let Some(rest) = strip_prefix_ignore_case(name, prefix) {
+            let (scan_type, _) = parse_key_name(rest)?;
+
+            // Only accept keys that actually exist on the keypad
+            Encoder::base_code_s1(scan_type, true)?;
+
+            return Some((scan_type, true));
+        }
+    }
+
+    return None;
+}
+
+impl fmt::Display for ScanType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(self.name());
+    }
+}
+
+impl FromStr for ScanType {
+    type Err = &'static str;
+
+    // Keypad names resolve to the matching scan type, use Key to keep the keypad flag.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match parse_key_name(s) {
+            Some((scan_type, _)) => Ok(scan_type),
+            None => Err("Unknown key name."),
+        };
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.keypad() {
+            f.write_str("Numpad ")?;
+        }
+
+        return f.write_str(self.scan_type().name());
+    }
+}
+
+impl FromStr for Key {
+    type Err = &'static str;
+
+    // Parses a pressed key
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match parse_key_name(s) {
+            Some((scan_type, true)) => Ok(Key::new_keypad(scan_type, KeyState::Pressed)),
+            Some((scan_type, false)) => Ok(Key::new(scan_type, KeyState::Pressed)),
+            None => Err("Unknown key name."),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Buffer {
+        bytes: [u8; 32],
+        len: usize,
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+
+            return Ok(());
+        }
+    }
+
+    fn check_display<T: fmt::Display>(value: T, expected: &str) {
+        use core::fmt::Write;
+
+        let mut buffer = Buffer {
+            bytes: [0u8; 32],
+            len: 0,
+        };
+        write!(buffer, "{}", value).unwrap();
+
+        assert_eq!(&buffer.bytes[..buffer.len], expected.as_bytes());
+    }
+
+    #[test]
+    fn test_display() {
+        check_display(ScanType::SymbolOpenSquareBracket, "[");
+        check_display(ScanType::LeftCtrl, "Left Ctrl");
+        check_display(
+            Key::new_keypad(ScanType::Num7, KeyState::Pressed),
+            "Numpad 7",
+        );
+        check_display(Key::new(ScanType::Num7, KeyState::Pressed), "7");
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        assert_eq!("ctrl".parse(), Ok(ScanType::LeftCtrl));
+        assert_eq!("LCtrl".parse(), Ok(ScanType::LeftCtrl));
+        assert_eq!("ESC".parse(), Ok(ScanType::Escape));
+        assert_eq!("PgUp".parse(), Ok(ScanType::PageUp));
+        assert_eq!("f11".parse(), Ok(ScanType::F11));
+        assert_eq!("right_alt".parse(), Ok(ScanType::RightAlt));
+        assert_eq!("[".parse(), Ok(ScanType::SymbolOpenSquareBracket));
+        assert!("nonsense".parse::<ScanType>().is_err());
+    }
+
+    #[test]
+    fn test_parse_keypad() {
+        assert_eq!(
+            "kp7".parse(),
+            Ok(Key::new_keypad(ScanType::Num7, KeyState::Pressed))
+        );
+        assert_eq!(
+            "Numpad Enter".parse(),
+            Ok(Key::new_keypad(ScanType::Enter, KeyState::Pressed))
+        );
+        assert_eq!(
+            "KP_/".parse(),
+            Ok(Key::new_keypad(
+                ScanType::SymbolForwardSlash,
+                KeyState::Pressed
+            ))
+        );
+        assert_eq!("7".parse(), Ok(Key::new(ScanType::Num7, KeyState::Pressed)));
+        assert_eq!(
+            "+".parse(),
+            Ok(Key::new_keypad(ScanType::SymbolPlus, KeyState::Pressed))
+        );
+        assert!("kpq".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for scan_type in ScanType::ALL.iter() {
+            assert_eq!(scan_type.name().parse(), Ok(*scan_type));
+        }
+    }
+}
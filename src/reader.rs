@@ -64,21 +64,20 @@ impl Reader {
 
         // This is the first code being interpreted for a key
         if first_free == 0 {
-            if code <= 0xd8 {
-                let k = Self::map_simple_scan_code_s1(code);
-
-                self.zero_scan_codes();
-
-                return Ok(k);
-            } else if code == 0xe0 || code == 0xe1 {
+            if code == 0xe0 || code == 0xe1 {
                 // Multimedia key so add it to the history
 
                 self.history_scan_codes[0] = code;
 
-                return Ok(None);
-            } else {
                 return Ok(None);
             }
+
+            // Anything that isn't a key (such as an ACK) maps to None
+            let k = Self::map_simple_scan_code_s1(code);
+
+            self.zero_scan_codes();
+
+            return Ok(k);
         } else if first_free == 1 {
             // The first code should be 0xe0, we need to check this. Otherwise if it is 0xe1 we should store the next code
 
@@ -194,7 +193,7 @@ impl Reader {
         return Ok(None);
     }
 
-    fn map_simple_scan_code_s1(code: u8) -> Option<Key> {
+    pub(crate) fn map_simple_scan_code_s1(code: u8) -> Option<Key> {
         macro_rules! create_pressed_key {
             ($scan_type:expr) => {{
                 Some(Key::new($scan_type, KeyState::Pressed))
@@ -302,24 +301,42 @@ impl Reader {
             0x51 => return create_pressed_keypad_key!(ScanType::Num3),
             0x52 => return create_pressed_keypad_key!(ScanType::Num0),
             0x53 => return create_pressed_keypad_key!(ScanType::SymbolPeriod),
-            // 0x54 ... 0x56
+            // SysRq, sent for print screen while Alt is held
+            0x54 => return create_pressed_key!(ScanType::PrintScreen),
+            // 0x55
+            0x56 => return create_pressed_key!(ScanType::Iso102nd),
             0x57 => return create_pressed_key!(ScanType::F11),
             0x58 => return create_pressed_key!(ScanType::F12),
 
+            // JIS keys
+            0x70 => return create_pressed_key!(ScanType::KatakanaHiragana),
+            0x73 => return create_pressed_key!(ScanType::Ro),
+            0x79 => return create_pressed_key!(ScanType::Henkan),
+            0x7b => return create_pressed_key!(ScanType::Muhenkan),
+            0x7d => return create_pressed_key!(ScanType::Yen),
+
             // Released keys
             0x81..=0xd3 => match Self::map_simple_scan_code_s1(code - 0x80) {
                 Some(n) => return Some(n.inverted_state()),
                 None => return None,
             },
 
+            0xd4 => Some(Key::new(ScanType::PrintScreen, KeyState::Released)),
+            0xd6 => Some(Key::new(ScanType::Iso102nd, KeyState::Released)),
             0xd7 => Some(Key::new(ScanType::F11, KeyState::Released)),
             0xd8 => Some(Key::new(ScanType::F12, KeyState::Released)),
 
+            // The Yen release shares its value with the "self test failed" response
+            0xf0 | 0xf3 | 0xf9 | 0xfb | 0xfd => match Self::map_simple_scan_code_s1(code - 0x80) {
+                Some(n) => return Some(n.inverted_state()),
+                None => return None,
+            },
+
             _ => return None,
         }
     }
 
-    fn map_media_scan_code_s1(code: u8) -> Option<Key> {
+    pub(crate) fn map_media_scan_code_s1(code: u8) -> Option<Key> {
         macro_rules! create_pressed_key {
             ($scan_type:expr) => {{
                 Some(Key::new($scan_type, KeyState::Pressed))
@@ -346,6 +363,7 @@ impl Reader {
             0x32 => return create_pressed_key!(ScanType::WWWHome),
             0x35 => return create_pressed_keypad_key!(ScanType::SymbolForwardSlash),
             0x38 => return create_pressed_key!(ScanType::RightAlt),
+            0x47 => return create_pressed_key!(ScanType::Home),
             0x48 => return create_pressed_key!(ScanType::CursorUp),
             0x49 => return create_pressed_key!(ScanType::PageUp),
             0x4b => return create_pressed_key!(ScanType::CursorLeft),
@@ -416,6 +434,39 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_simple_scan_jis() {
+            let mut reader = Reader::new(ReaderMode::Set1);
+
+            assert_eq!(
+                reader.input_scan_code(0x7d).unwrap().unwrap(),
+                Key::new(ScanType::Yen, KeyState::Pressed)
+            );
+            assert_eq!(
+                reader.input_scan_code(0xfd).unwrap().unwrap(),
+                Key::new(ScanType::Yen, KeyState::Released)
+            );
+            assert_eq!(
+                reader.input_scan_code(0xf0).unwrap().unwrap(),
+                Key::new(ScanType::KatakanaHiragana, KeyState::Released)
+            );
+            assert!(reader.input_scan_code(0xfa).unwrap().is_none());
+        }
+
+        #[test]
+        fn test_simple_scan_iso_102nd() {
+            let mut reader = Reader::new(ReaderMode::Set1);
+
+            assert_eq!(
+                reader.input_scan_code(0x56).unwrap().unwrap(),
+                Key::new(ScanType::Iso102nd, KeyState::Pressed)
+            );
+            assert_eq!(
+                reader.input_scan_code(0xd6).unwrap().unwrap(),
+                Key::new(ScanType::Iso102nd, KeyState::Released)
+            );
+        }
+
         #[test]
         fn test_simple_scan_4() {
             let mut reader = Reader::new(ReaderMode::Set1);
@@ -499,6 +550,17 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_media_scan_home() {
+            let mut reader = Reader::new(ReaderMode::Set1);
+
+            assert!(reader.input_scan_code(0xe0).unwrap().is_none());
+            assert_eq!(
+                reader.input_scan_code(0x47).unwrap().unwrap(),
+                Key::new(ScanType::Home, KeyState::Pressed)
+            );
+        }
+
         #[test]
         fn test_print_screen_pressed() {
             let mut reader = Reader::new(ReaderMode::Set1);
@@ -525,6 +587,20 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_sysrq() {
+            let mut reader = Reader::new(ReaderMode::Set1);
+
+            assert_eq!(
+                reader.input_scan_code(0x54).unwrap().unwrap(),
+                Key::new(ScanType::PrintScreen, KeyState::Pressed)
+            );
+            assert_eq!(
+                reader.input_scan_code(0xd4).unwrap().unwrap(),
+                Key::new(ScanType::PrintScreen, KeyState::Released)
+            );
+        }
+
         #[test]
         fn test_pause_pressed() {
             let mut reader = Reader::new(ReaderMode::Set1);
@@ -0,0 +1,222 @@
+use super::scan_code_set::{KeySet, KEY_SLOT_COUNT, SCAN_TYPE_COUNT};
+use super::{Key, ScanType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Rewrites the scan type of keys before modifiers and layouts see them.
+// The keypad flag and press/release state are preserved, keys can also be disabled.
+pub struct KeyRemap {
+    // None disables the key
+    targets: [Option<ScanType>; SCAN_TYPE_COUNT],
+}
+
+impl KeyRemap {
+    // A table that leaves every key alone
+    pub const fn identity() -> Self {
+        let mut targets = [None; SCAN_TYPE_COUNT];
+        let mut i = 0;
+
+        while i < SCAN_TYPE_COUNT {
+            targets[i] = Some(ScanType::ALL[i]);
+            i += 1;
+        }
+
+        return Self { targets };
+    }
+
+    pub const fn remap(mut self, from: ScanType, to: ScanType) -> Self {
+        if (from as usize) < SCAN_TYPE_COUNT {
+            self.targets[from as usize] = Some(to);
+        }
+
+        return self;
+    }
+
+    pub const fn swap(self, a: ScanType, b: ScanType) -> Self {
+        return self.remap(a, b).remap(b, a);
+    }
+
+    pub const fn disable(mut self, key: ScanType) -> Self {
+        if (key as usize) < SCAN_TYPE_COUNT {
+            self.targets[key as usize] = None;
+        }
+
+        return self;
+    }
+
+    pub fn set(&mut self, from: ScanType, to: Option<ScanType>) {
+        if let Some(slot) = self.targets.get_mut(from.as_u8() as usize) {
+            *slot = to;
+        }
+    }
+
+    // The scan type the key becomes, None if it is disabled
+    pub fn get(&self, from: ScanType) -> Option<ScanType> {
+        return match self.targets.get(from.as_u8() as usize) {
+            Some(target) => *target,
+            None => Some(from),
+        };
+    }
+
+    pub fn apply(&self, key: Key) -> Option<Key> {
+        return Some(key.with_scan_type(self.get(key.scan_type())?));
+    }
+}
+
+impl Default for KeyRemap {
+    fn default() -> Self {
+        return Self::identity();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+// Remembers what each physical key was delivered as when it was pressed, so the release
+// matches the press even if the mapping changed in between.
+pub(crate) struct PressedKeys {
+    held: KeySet,
+    targets: [Option<ScanType>; KEY_SLOT_COUNT],
+}
+
+impl PressedKeys {
+    pub(crate) const fn new() -> Self {
+        return Self {
+            held: KeySet::new(),
+            targets: [None; KEY_SLOT_COUNT],
+        };
+    }
+
+    // Records a press of the physical key, a repeated press keeps the original target
+    pub(crate) fn press(&mut self, physical: Key, target: Option<ScanType>) -> Option<ScanType> {
+        let index = match physical.slot() {
+            Some(index) => index,
+            None => return target,
+        };
+
+        if self.held.contains(&physical) {
+            return self.targets[index];
+        }
+
+        self.held.insert(&physical);
+        self.targets[index] = target;
+
+        return target;
+    }
+
+    // Returns what the press was delivered as, or None if the key was never seen pressed
+    pub(crate) fn release(&mut self, physical: Key) -> Option<Option<ScanType>> {
+        let index = physical.slot()?;
+
+        if !self.held.remove(&physical) {
+            return None;
+        }
+
+        return Some(self.targets[index].take());
+    }
+
+    // Maps a key through the table while keeping presses and releases paired
+    pub(crate) fn map(&mut self, remap: &KeyRemap, key: Key) -> Option<Key> {
+        let target = if key.is_pressed() {
+            self.press(key, remap.get(key.scan_type()))
+        } else {
+            match self.release(key) {
+                Some(target) => target,
+                None => remap.get(key.scan_type()),
+            }
+        };
+
+        return Some(key.with_scan_type(target?));
+    }
+}
+
+impl Default for PressedKeys {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::KeyState;
+    use super::*;
+
+    const CAPS_AS_CTRL: KeyRemap =
+        KeyRemap::identity().remap(ScanType::CapsLock, ScanType::LeftCtrl);
+
+    #[test]
+    fn test_identity() {
+        let remap = KeyRemap::identity();
+
+        for scan_type in ScanType::ALL.iter() {
+            assert_eq!(remap.get(*scan_type), Some(*scan_type));
+        }
+
+        assert_eq!(remap.get(ScanType::Unknown), Some(ScanType::Unknown));
+    }
+
+    #[test]
+    fn test_const_remap() {
+        assert_eq!(
+            CAPS_AS_CTRL.apply(Key::new(ScanType::CapsLock, KeyState::Released)),
+            Some(Key::new(ScanType::LeftCtrl, KeyState::Released))
+        );
+        assert_eq!(
+            CAPS_AS_CTRL.apply(Key::new_keypad(ScanType::Num1, KeyState::Pressed)),
+            Some(Key::new_keypad(ScanType::Num1, KeyState::Pressed))
+        );
+    }
+
+    #[test]
+    fn test_swap_and_disable() {
+        let remap = KeyRemap::identity()
+            .swap(ScanType::LeftAlt, ScanType::LeftGUI)
+            .disable(ScanType::Insert);
+
+        assert_eq!(remap.get(ScanType::LeftAlt), Some(ScanType::LeftGUI));
+        assert_eq!(remap.get(ScanType::LeftGUI), Some(ScanType::LeftAlt));
+        assert_eq!(remap.get(ScanType::Insert), None);
+    }
+
+    #[test]
+    fn test_release_matches_press() {
+        let mut pressed = PressedKeys::new();
+
+        assert_eq!(
+            pressed.map(
+                &CAPS_AS_CTRL,
+                Key::new(ScanType::CapsLock, KeyState::Pressed)
+            ),
+            Some(Key::new(ScanType::LeftCtrl, KeyState::Pressed))
+        );
+
+        // Swapping the table while the key is held does not change its release
+        assert_eq!(
+            pressed.map(
+                &KeyRemap::identity(),
+                Key::new(ScanType::CapsLock, KeyState::Released)
+            ),
+            Some(Key::new(ScanType::LeftCtrl, KeyState::Released))
+        );
+    }
+
+    #[test]
+    fn test_keypad_apart() {
+        let mut pressed = PressedKeys::new();
+        let swap = KeyRemap::identity().swap(ScanType::Num7, ScanType::Num8);
+
+        pressed.map(&swap, Key::new(ScanType::Num7, KeyState::Pressed));
+
+        // A keypad 7 doesn't take over the top-row 7's slot
+        let keypad = Key::new_keypad(ScanType::Num7, KeyState::Pressed);
+        pressed.map(&KeyRemap::identity(), keypad);
+        assert_eq!(
+            pressed.map(&KeyRemap::identity(), keypad.inverted_state()),
+            Some(keypad.inverted_state())
+        );
+        assert_eq!(
+            pressed.map(
+                &KeyRemap::identity(),
+                Key::new(ScanType::Num7, KeyState::Released)
+            ),
+            Some(Key::new(ScanType::Num8, KeyState::Released))
+        );
+    }
+}
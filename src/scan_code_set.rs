@@ -1,5 +1,6 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u8)]
+// The values are the scan type ids keymap files store, new scan types only ever go at the end
 pub enum ScanType {
     // Numbers
     Num0 = 0, // 0-9
@@ -134,10 +135,26 @@ pub enum ScanType {
     PrintScreen,
     Pause,
 
+    // The extra key next to left shift on ISO keyboards
+    Iso102nd,
+
+    // Japanese (JIS) keys, Zenkaku/Hankaku shares the backtick position
+    KatakanaHiragana,
+    Ro,
+    Henkan,
+    Muhenkan,
+    Yen,
+
     // Reserved key for any unknown key
     Unknown = 0xFF,
 }
 
+// The number of scan types excluding Unknown
+pub(crate) const SCAN_TYPE_COUNT: usize = ScanType::ALL.len();
+
+// Per-key tables have a slot for every scan type and another for it on the keypad
+pub(crate) const KEY_SLOT_COUNT: usize = SCAN_TYPE_COUNT * 2;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum KeyState {
     Pressed,
@@ -183,6 +200,13 @@ impl Key {
         return self.keypad;
     }
 
+    // The same key with a different scan type, keeping the state and keypad flag
+    #[inline]
+    pub fn with_scan_type(mut self, scan_type: ScanType) -> Self {
+        self.scan_type = scan_type;
+        return self;
+    }
+
     #[inline]
     pub fn inverted_state(mut self) -> Self {
         if self.state == KeyState::Pressed {
@@ -198,14 +222,158 @@ impl Key {
     pub fn is_pressed(&self) -> bool {
         return self.state == KeyState::Pressed;
     }
+
+    // The key's slot in per-key tables, None for Unknown
+    pub(crate) fn slot(&self) -> Option<usize> {
+        let index = self.scan_type.as_u8() as usize;
+
+        if index >= SCAN_TYPE_COUNT {
+            return None;
+        }
+
+        return Some(if self.keypad {
+            index + SCAN_TYPE_COUNT
+        } else {
+            index
+        });
+    }
 }
 
 impl ScanType {
+    // Every scan type other than `Unknown`, ordered by their u8 value.
+    pub const ALL: [ScanType; 117] = [
+        ScanType::Num0,
+        ScanType::Num1,
+        ScanType::Num2,
+        ScanType::Num3,
+        ScanType::Num4,
+        ScanType::Num5,
+        ScanType::Num6,
+        ScanType::Num7,
+        ScanType::Num8,
+        ScanType::Num9,
+        ScanType::CharA,
+        ScanType::CharB,
+        ScanType::CharC,
+        ScanType::CharD,
+        ScanType::CharE,
+        ScanType::CharF,
+        ScanType::CharG,
+        ScanType::CharH,
+        ScanType::CharI,
+        ScanType::CharJ,
+        ScanType::CharK,
+        ScanType::CharL,
+        ScanType::CharM,
+        ScanType::CharN,
+        ScanType::CharO,
+        ScanType::CharP,
+        ScanType::CharQ,
+        ScanType::CharR,
+        ScanType::CharS,
+        ScanType::CharT,
+        ScanType::CharU,
+        ScanType::CharV,
+        ScanType::CharW,
+        ScanType::CharX,
+        ScanType::CharY,
+        ScanType::CharZ,
+        ScanType::SymbolPlus,
+        ScanType::SymbolMinus,
+        ScanType::SymbolEquals,
+        ScanType::SymbolOpenSquareBracket,
+        ScanType::SymbolCloseSquareBracket,
+        ScanType::SymbolSemicolon,
+        ScanType::SymbolSingleQuote,
+        ScanType::SymbolBacktick,
+        ScanType::SymbolBackslash,
+        ScanType::SymbolComma,
+        ScanType::SymbolPeriod,
+        ScanType::SymbolForwardSlash,
+        ScanType::SymbolAsterisk,
+        ScanType::Escape,
+        ScanType::Backspace,
+        ScanType::Tab,
+        ScanType::Enter,
+        ScanType::LeftCtrl,
+        ScanType::RightCtrl,
+        ScanType::LeftShift,
+        ScanType::RightShift,
+        ScanType::LeftAlt,
+        ScanType::RightAlt,
+        ScanType::LeftGUI,
+        ScanType::RightGUI,
+        ScanType::Space,
+        ScanType::F1,
+        ScanType::F2,
+        ScanType::F3,
+        ScanType::F4,
+        ScanType::F5,
+        ScanType::F6,
+        ScanType::F7,
+        ScanType::F8,
+        ScanType::F9,
+        ScanType::F10,
+        ScanType::F11,
+        ScanType::F12,
+        ScanType::NumLock,
+        ScanType::ScrollLock,
+        ScanType::CapsLock,
+        ScanType::Home,
+        ScanType::PageUp,
+        ScanType::PageDown,
+        ScanType::CursorUp,
+        ScanType::CursorLeft,
+        ScanType::CursorRight,
+        ScanType::CursorDown,
+        ScanType::Insert,
+        ScanType::Delete,
+        ScanType::End,
+        ScanType::ACPIPower,
+        ScanType::ACPISleep,
+        ScanType::ACPIWake,
+        ScanType::PreviousTrack,
+        ScanType::NextTrack,
+        ScanType::Mute,
+        ScanType::Calculator,
+        ScanType::Stop,
+        ScanType::Play,
+        ScanType::WWWHome,
+        ScanType::VolumeUp,
+        ScanType::VolumeDown,
+        ScanType::Apps,
+        ScanType::WWWSearch,
+        ScanType::WWWFavorites,
+        ScanType::WWWRefresh,
+        ScanType::WWWStop,
+        ScanType::WWWForward,
+        ScanType::WWWBack,
+        ScanType::MyComputer,
+        ScanType::Email,
+        ScanType::MediaSelect,
+        ScanType::PrintScreen,
+        ScanType::Pause,
+        ScanType::Iso102nd,
+        ScanType::KatakanaHiragana,
+        ScanType::Ro,
+        ScanType::Henkan,
+        ScanType::Muhenkan,
+        ScanType::Yen,
+    ];
+
     #[inline]
     pub fn as_u8(self) -> u8 {
         return self as u8;
     }
 
+    pub fn from_u8(value: u8) -> Option<ScanType> {
+        if value == ScanType::Unknown.as_u8() {
+            return Some(ScanType::Unknown);
+        }
+
+        return ScanType::ALL.get(value as usize).copied();
+    }
+
     #[inline]
     pub fn is_letter(&self) -> bool {
         return *self >= ScanType::CharA && *self <= ScanType::CharZ;
@@ -216,3 +384,124 @@ impl ScanType {
         return *self >= ScanType::Num0 && *self <= ScanType::Num9;
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A set of scan types, used to remember keys across their press and release
+pub(crate) struct ScanTypeSet {
+    bits: [u32; 8],
+}
+
+impl ScanTypeSet {
+    pub(crate) const fn new() -> Self {
+        return Self { bits: [0u32; 8] };
+    }
+
+    pub(crate) fn insert(&mut self, scan_type: ScanType) {
+        let v = scan_type.as_u8() as usize;
+        self.bits[v / 32] |= 1 << (v % 32);
+    }
+
+    // Returns whether the scan type was in the set
+    pub(crate) fn remove(&mut self, scan_type: ScanType) -> bool {
+        let present = self.contains(scan_type);
+        let v = scan_type.as_u8() as usize;
+        self.bits[v / 32] &= !(1 << (v % 32));
+
+        return present;
+    }
+
+    pub(crate) fn contains(&self, scan_type: ScanType) -> bool {
+        let v = scan_type.as_u8() as usize;
+
+        return self.bits[v / 32] & (1 << (v % 32)) != 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A set of keys whatever their state, a keypad key is apart from the same scan type elsewhere
+pub(crate) struct KeySet {
+    keys: ScanTypeSet,
+    keypad: ScanTypeSet,
+}
+
+impl KeySet {
+    pub(crate) const fn new() -> Self {
+        return Self {
+            keys: ScanTypeSet::new(),
+            keypad: ScanTypeSet::new(),
+        };
+    }
+
+    fn set(&mut self, key: &Key) -> &mut ScanTypeSet {
+        return if key.keypad() {
+            &mut self.keypad
+        } else {
+            &mut self.keys
+        };
+    }
+
+    pub(crate) fn insert(&mut self, key: &Key) {
+        self.set(key).insert(key.scan_type());
+    }
+
+    // Returns whether the key was in the set
+    pub(crate) fn remove(&mut self, key: &Key) -> bool {
+        return self.set(key).remove(key.scan_type());
+    }
+
+    pub(crate) fn contains(&self, key: &Key) -> bool {
+        let set = if key.keypad() {
+            &self.keypad
+        } else {
+            &self.keys
+        };
+
+        return set.contains(key.scan_type());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_is_ordered() {
+        for (i, scan_type) in ScanType::ALL.iter().enumerate() {
+            assert_eq!(scan_type.as_u8() as usize, i);
+            assert_eq!(ScanType::from_u8(i as u8), Some(*scan_type));
+        }
+
+        assert_eq!(ScanType::from_u8(0xff), Some(ScanType::Unknown));
+        assert_eq!(ScanType::from_u8(ScanType::ALL.len() as u8), None);
+    }
+
+    #[test]
+    fn test_scan_type_set() {
+        let mut set = ScanTypeSet::new();
+
+        set.insert(ScanType::CharA);
+        set.insert(ScanType::Unknown);
+        assert!(set.contains(ScanType::CharA));
+        assert!(set.contains(ScanType::Unknown));
+        assert!(!set.contains(ScanType::CharB));
+
+        assert!(set.remove(ScanType::CharA));
+        assert!(!set.remove(ScanType::CharA));
+    }
+
+    #[test]
+    fn test_key_set() {
+        let mut set = KeySet::new();
+        let top_row = Key::new(ScanType::Num7, KeyState::Pressed);
+        let keypad = Key::new_keypad(ScanType::Num7, KeyState::Pressed);
+
+        set.insert(&keypad);
+        assert!(set.contains(&keypad.inverted_state()));
+        assert!(!set.contains(&top_row));
+        assert!(!set.remove(&top_row));
+        assert!(set.remove(&keypad));
+
+        assert_ne!(top_row.slot(), keypad.slot());
+        assert_eq!(Key::new(ScanType::Unknown, KeyState::Pressed).slot(), None);
+    }
+}
@@ -0,0 +1,539 @@
+use super::event::EventQueue;
+use super::hotkey::Hotkey;
+use super::layout::KeyModifierState;
+use super::scan_code_set::KeySet;
+use super::sticky_keys::is_modifier;
+use super::Key;
+
+// The longest sequence, e.g. Ctrl+X Ctrl+S is two steps
+pub const SEQUENCE_MAX_LEN: usize = 4;
+// The steps of every registered sequence share this many trie nodes
+pub const SEQUENCE_TABLE_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMatch {
+    // The key isn't part of a sequence and should be handled as usual
+    Unmatched,
+    // The key continued a sequence that isn't complete yet
+    Pending,
+    Matched(u16),
+    // The sequence broke off, its keys are waiting in next_replay
+    Failed,
+    // A release or repeat of a key that belonged to a matched sequence
+    Consumed,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SequenceNode {
+    step: Hotkey,
+    // None for the first step of a sequence
+    parent: Option<u8>,
+    id: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy)]
+// Recognises sequences of hotkeys like Ctrl+X Ctrl+S or a leader key followed by letters. Each
+// step has to follow the previous one within the timeout, measured in milliseconds on the
+// caller's clock. Modifier keys are never taken so the caller keeps track of them as usual.
+// Keys that were held back for a sequence that didn't complete, and a key that arrives after a
+// sequence ended, come back out of next_replay with the modifiers they were typed with.
+pub struct SequenceMatcher {
+    nodes: [Option<SequenceNode>; SEQUENCE_TABLE_CAPACITY],
+    timeout: u32,
+    // The node of the last matched step, None while no sequence is in progress
+    current: Option<u8>,
+    deadline: u64,
+    buffer: [Option<(Key, KeyModifierState)>; SEQUENCE_MAX_LEN * 2],
+    // Keys pressed for the sequence in progress that are still down
+    held: KeySet,
+    // Keys of a matched sequence that are still down
+    consumed: KeySet,
+    replay: EventQueue<(Key, KeyModifierState)>,
+}
+
+impl SequenceMatcher {
+    pub const fn new() -> Self {
+        return Self {
+            nodes: [None; SEQUENCE_TABLE_CAPACITY],
+            timeout: 1000,
+            current: None,
+            deadline: 0,
+            buffer: [None; SEQUENCE_MAX_LEN * 2],
+            held: KeySet::new(),
+            consumed: KeySet::new(),
+            replay: EventQueue::new(),
+        };
+    }
+
+    pub const fn with_timeout(mut self, timeout: u32) -> Self {
+        self.timeout = timeout;
+        return self;
+    }
+
+    pub fn timeout(&self) -> u32 {
+        return self.timeout;
+    }
+
+    pub fn set_timeout(&mut self, timeout: u32) {
+        self.timeout = timeout;
+    }
+
+    // Registering the same steps again replaces the id. A sequence may be the start of a longer
+    // one, it then matches when the timeout passes or another key follows.
+    pub fn register(&mut self, id: u16, steps: &[Hotkey]) -> Result<(), &'static str> {
+        if steps.is_empty() {
+            return Err("Sequence is empty.");
+        }
+
+        if steps.len() > SEQUENCE_MAX_LEN {
+            return Err("Sequence is too long.");
+        }
+
+        let mut parent = None;
+        let mut depth = 0;
+
+        while depth < steps.len() {
+            match self.child(parent, |step| step == steps[depth]) {
+                Some(index) => parent = Some(index),
+                None => break,
+            }
+
+            depth += 1;
+        }
+
+        let free = self.nodes.iter().filter(|node| node.is_none()).count();
+
+        if free < steps.len() - depth {
+            return Err("Sequence table full.");
+        }
+
+        for step in steps[depth..].iter() {
+            let index = self.nodes.iter().position(|node| node.is_none()).unwrap();
+
+            self.nodes[index] = Some(SequenceNode {
+                step: *step,
+                parent,
+                id: None,
+            });
+            parent = Some(index as u8);
+        }
+
+        if let Some(node) = self.nodes[parent.unwrap() as usize].as_mut() {
+            node.id = Some(id);
+        }
+
+        return Ok(());
+    }
+
+    // Removes every sequence with the id, returns whether any were removed. A sequence in
+    // progress is abandoned without replaying its keys.
+    pub fn unregister(&mut self, id: u16) -> bool {
+        let mut removed = false;
+
+        for node in self.nodes.iter_mut().flatten() {
+            if node.id == Some(id) {
+                node.id = None;
+                removed = true;
+            }
+        }
+
+        // Prune the steps that no longer lead to a sequence
+        let mut pruned = true;
+
+        while pruned {
+            pruned = false;
+
+            for index in 0..SEQUENCE_TABLE_CAPACITY {
+                let unused = match self.nodes[index] {
+                    Some(node) => node.id.is_none() && !self.has_children(index as u8),
+                    None => false,
+                };
+
+                if unused {
+                    self.nodes[index] = None;
+                    pruned = true;
+                }
+            }
+        }
+
+        if removed {
+            self.reset();
+        }
+
+        return removed;
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes = [None; SEQUENCE_TABLE_CAPACITY];
+        self.reset();
+    }
+
+    pub fn is_pending(&self) -> bool {
+        return self.current.is_some();
+    }
+
+    pub fn input(&mut self, key: Key, modifiers: &KeyModifierState, now: u64) -> SequenceMatch {
+        let scan_type = key.scan_type();
+
+        if is_modifier(scan_type) {
+            return SequenceMatch::Unmatched;
+        }
+
+        if let Some(result) = self.tick(now) {
+            self.replay_key(key, modifiers);
+            return result;
+        }
+
+        if self.consumed.contains(&key) {
+            if !key.is_pressed() {
+                self.consumed.remove(&key);
+            }
+
+            return SequenceMatch::Consumed;
+        }
+
+        let current = match self.current {
+            Some(current) => current,
+            None => {
+                if !key.is_pressed() {
+                    return SequenceMatch::Unmatched;
+                }
+
+                return match self.child(None, |step| step.matches(&key, modifiers)) {
+                    Some(index) => self.advance(index, key, modifiers, now),
+                    None => SequenceMatch::Unmatched,
+                };
+            }
+        };
+
+        if !key.is_pressed() {
+            if !self.held.remove(&key) {
+                return SequenceMatch::Unmatched;
+            }
+
+            self.hold_back(key, modifiers);
+            return SequenceMatch::Pending;
+        }
+
+        // Repeats of a key held for the sequence say nothing
+        if self.held.contains(&key) {
+            return SequenceMatch::Pending;
+        }
+
+        if let Some(index) = self.child(Some(current), |step| step.matches(&key, modifiers)) {
+            return self.advance(index, key, modifiers, now);
+        }
+
+        let result = self.end();
+        self.replay_key(key, modifiers);
+
+        return result;
+    }
+
+    // Ends a sequence whose timeout has passed, as a match when its steps so far form one
+    pub fn tick(&mut self, now: u64) -> Option<SequenceMatch> {
+        if self.current.is_some() && now >= self.deadline {
+            return Some(self.end());
+        }
+
+        return None;
+    }
+
+    pub fn next_replay(&mut self) -> Option<(Key, KeyModifierState)> {
+        return self.replay.pop();
+    }
+
+    fn child<F>(&self, parent: Option<u8>, mut matches: F) -> Option<u8>
+    where
+        F: FnMut(Hotkey) -> bool,
+    {
+        return self
+            .nodes
+            .iter()
+            .position(|node| match node {
+                Some(node) => node.parent == parent && matches(node.step),
+                None => false,
+            })
+            .map(|index| index as u8);
+    }
+
+    fn has_children(&self, index: u8) -> bool {
+        return self
+            .nodes
+            .iter()
+            .flatten()
+            .any(|node| node.parent == Some(index));
+    }
+
+    fn advance(
+        &mut self,
+        index: u8,
+        key: Key,
+        modifiers: &KeyModifierState,
+        now: u64,
+    ) -> SequenceMatch {
+        self.hold_back(key, modifiers);
+        self.held.insert(&key);
+        self.current = Some(index);
+        self.deadline = now + self.timeout as u64;
+
+        if self.has_children(index) {
+            return SequenceMatch::Pending;
+        }
+
+        return self.end();
+    }
+
+    fn end(&mut self) -> SequenceMatch {
+        let id = self
+            .current
+            .and_then(|index| self.nodes[index as usize])
+            .and_then(|node| node.id);
+
+        let result = match id {
+            Some(id) => {
+                for (key, _) in self.buffer.iter().flatten() {
+                    if self.held.contains(key) {
+                        self.consumed.insert(key);
+                    }
+                }
+
+                SequenceMatch::Matched(id)
+            }
+            None => {
+                for buffered in self.buffer.iter().flatten() {
+                    self.replay.push(*buffered);
+                }
+
+                SequenceMatch::Failed
+            }
+        };
+
+        self.current = None;
+        self.buffer = [None; SEQUENCE_MAX_LEN * 2];
+        self.held = KeySet::new();
+
+        return result;
+    }
+
+    fn reset(&mut self) {
+        self.current = None;
+        self.buffer = [None; SEQUENCE_MAX_LEN * 2];
+        self.held = KeySet::new();
+        self.consumed = KeySet::new();
+    }
+
+    // A press and release per step always fits
+    fn hold_back(&mut self, key: Key, modifiers: &KeyModifierState) {
+        if let Some(slot) = self.buffer.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some((key, *modifiers));
+        }
+    }
+
+    // The key that arrived as a sequence ended is handed back, unless it is the release of a key
+    // the match consumed
+    fn replay_key(&mut self, key: Key, modifiers: &KeyModifierState) {
+        if key.is_pressed() || !self.consumed.remove(&key) {
+            self.replay.push((key, *modifiers));
+        }
+    }
+}
+
+impl Default for SequenceMatcher {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{KeyState, ScanType};
+    use super::*;
+
+    fn pressed(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Pressed);
+    }
+
+    fn released(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Released);
+    }
+
+    fn ctrl() -> KeyModifierState {
+        let mut modifiers = KeyModifierState::new();
+        modifiers.left_ctrl = true;
+
+        return modifiers;
+    }
+
+    fn matcher() -> SequenceMatcher {
+        let mut matcher = SequenceMatcher::new().with_timeout(500);
+        let save = [
+            Hotkey::parse("Ctrl+X").unwrap(),
+            Hotkey::parse("Ctrl+S").unwrap(),
+        ];
+        let leader = [
+            Hotkey::parse("Backslash").unwrap(),
+            Hotkey::parse("W").unwrap(),
+        ];
+
+        matcher.register(1, &save).unwrap();
+        matcher.register(2, &leader).unwrap();
+        matcher.register(3, &leader[..1]).unwrap();
+
+        return matcher;
+    }
+
+    #[test]
+    fn test_match() {
+        let mut matcher = matcher();
+        let ctrl = ctrl();
+
+        assert_eq!(
+            matcher.input(pressed(ScanType::LeftCtrl), &ctrl, 0),
+            SequenceMatch::Unmatched
+        );
+        assert_eq!(
+            matcher.input(pressed(ScanType::CharX), &ctrl, 10),
+            SequenceMatch::Pending
+        );
+        assert_eq!(
+            matcher.input(pressed(ScanType::CharX), &ctrl, 40),
+            SequenceMatch::Pending
+        );
+        assert_eq!(
+            matcher.input(released(ScanType::CharX), &ctrl, 50),
+            SequenceMatch::Pending
+        );
+        assert_eq!(
+            matcher.input(pressed(ScanType::CharS), &ctrl, 100),
+            SequenceMatch::Matched(1)
+        );
+        assert!(!matcher.is_pending());
+        assert_eq!(
+            matcher.input(released(ScanType::CharS), &ctrl, 150),
+            SequenceMatch::Consumed
+        );
+        assert_eq!(
+            matcher.input(pressed(ScanType::CharS), &ctrl, 200),
+            SequenceMatch::Unmatched
+        );
+        assert_eq!(matcher.next_replay(), None);
+    }
+
+    #[test]
+    fn test_failure_replays_keys() {
+        let mut matcher = matcher();
+        let ctrl = ctrl();
+
+        let none = KeyModifierState::new();
+
+        matcher.input(pressed(ScanType::CharX), &ctrl, 0);
+        matcher.input(released(ScanType::CharX), &ctrl, 10);
+        assert_eq!(
+            matcher.input(pressed(ScanType::CharC), &none, 20),
+            SequenceMatch::Failed
+        );
+        assert!(!matcher.is_pending());
+
+        // Each key comes back with the modifiers it was typed with
+        let mut replay = core::iter::from_fn(|| matcher.next_replay());
+        assert!(replay.by_ref().eq([
+            (pressed(ScanType::CharX), ctrl),
+            (released(ScanType::CharX), ctrl),
+            (pressed(ScanType::CharC), none)
+        ]
+        .iter()
+        .copied()));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut matcher = matcher();
+        let modifiers = KeyModifierState::new();
+
+        matcher.input(pressed(ScanType::CharX), &ctrl(), 0);
+        assert_eq!(matcher.tick(499), None);
+        assert_eq!(matcher.tick(500), Some(SequenceMatch::Failed));
+        assert_eq!(
+            matcher.next_replay(),
+            Some((pressed(ScanType::CharX), ctrl()))
+        );
+
+        // The leader on its own matches once nothing follows it
+        assert_eq!(
+            matcher.input(pressed(ScanType::SymbolBackslash), &modifiers, 1000),
+            SequenceMatch::Pending
+        );
+        assert_eq!(
+            matcher.input(released(ScanType::CharX), &modifiers, 1600),
+            SequenceMatch::Matched(3)
+        );
+        assert_eq!(
+            matcher.next_replay(),
+            Some((released(ScanType::CharX), modifiers))
+        );
+
+        // Its release was consumed with it
+        assert_eq!(
+            matcher.input(released(ScanType::SymbolBackslash), &modifiers, 1700),
+            SequenceMatch::Consumed
+        );
+    }
+
+    #[test]
+    fn test_prefix_match_replays_next_key() {
+        let mut matcher = matcher();
+        let modifiers = KeyModifierState::new();
+
+        matcher.input(pressed(ScanType::SymbolBackslash), &modifiers, 0);
+        assert_eq!(
+            matcher.input(pressed(ScanType::CharQ), &modifiers, 10),
+            SequenceMatch::Matched(3)
+        );
+        assert_eq!(
+            matcher.next_replay(),
+            Some((pressed(ScanType::CharQ), modifiers))
+        );
+
+        matcher.input(released(ScanType::SymbolBackslash), &modifiers, 20);
+        matcher.input(pressed(ScanType::SymbolBackslash), &modifiers, 30);
+        assert_eq!(
+            matcher.input(pressed(ScanType::CharW), &modifiers, 40),
+            SequenceMatch::Matched(2)
+        );
+    }
+
+    #[test]
+    fn test_register() {
+        let mut matcher = matcher();
+        let f1 = Hotkey::parse("F1").unwrap();
+
+        assert_eq!(matcher.register(4, &[]), Err("Sequence is empty."));
+        assert_eq!(
+            matcher.register(4, &[f1; SEQUENCE_MAX_LEN + 1]),
+            Err("Sequence is too long.")
+        );
+
+        let used = SEQUENCE_TABLE_CAPACITY - 4;
+        for i in 0..used / SEQUENCE_MAX_LEN {
+            let mut steps = [f1; SEQUENCE_MAX_LEN];
+            steps[0] = Hotkey::new(Default::default(), ScanType::ALL[i]);
+            matcher.register(10 + i as u16, &steps).unwrap();
+        }
+
+        assert_eq!(matcher.register(4, &[f1]), Err("Sequence table full."));
+
+        // Unregistering frees the steps no other sequence uses
+        assert!(matcher.unregister(2));
+        assert!(!matcher.unregister(2));
+        matcher.register(4, &[f1]).unwrap();
+        assert_eq!(
+            matcher.input(
+                pressed(ScanType::SymbolBackslash),
+                &KeyModifierState::new(),
+                0
+            ),
+            SequenceMatch::Matched(3)
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use super::dead_key::DeadKey;
+use super::keymap::KeymapEntry;
+use super::layout::{KeyModifierState, Layout};
+use super::layouts::{level_symbol, KeySymbol};
+use super::scan_code_set::SCAN_TYPE_COUNT;
+use super::Key;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A layout built at compile time from rows of keys. Each scan type indexes straight to its row,
+// keypad keys use their keypad row when there is one and the main row otherwise. Keys without a
+// row produce nothing.
+pub struct StaticLayout<'a> {
+    keys: &'a [KeymapEntry],
+    keypad: &'a [KeymapEntry],
+    key_index: [u8; SCAN_TYPE_COUNT],
+    keypad_index: [u8; SCAN_TYPE_COUNT],
+    has_alt_gr: bool,
+}
+
+// Row number + 1 for every scan type, 0 where there is no row
+const fn build_index(rows: &[KeymapEntry]) -> [u8; SCAN_TYPE_COUNT] {
+    let mut index = [0u8; SCAN_TYPE_COUNT];
+    let mut i = 0;
+
+    if rows.len() >= u8::MAX as usize {
+        panic!("Too many rows in layout.");
+    }
+
+    while i < rows.len() {
+        let scan_type = rows[i].scan_type as usize;
+
+        if scan_type >= SCAN_TYPE_COUNT {
+            panic!("Layout row for unknown scan type.");
+        }
+
+        if index[scan_type] != 0 {
+            panic!("Scan type repeated in layout.");
+        }
+
+        index[scan_type] = i as u8 + 1;
+        i += 1;
+    }
+
+    return index;
+}
+
+impl<'a> StaticLayout<'a> {
+    pub const fn new(keys: &'a [KeymapEntry], keypad: &'a [KeymapEntry], has_alt_gr: bool) -> Self {
+        return Self {
+            keys,
+            keypad,
+            key_index: build_index(keys),
+            keypad_index: build_index(keypad),
+            has_alt_gr,
+        };
+    }
+
+    pub fn entry(&self, key: Key) -> Option<&'a KeymapEntry> {
+        let scan_type = key.scan_type() as usize;
+
+        if scan_type >= SCAN_TYPE_COUNT {
+            return None;
+        }
+
+        if key.keypad() && self.keypad_index[scan_type] != 0 {
+            return Some(&self.keypad[self.keypad_index[scan_type] as usize - 1]);
+        }
+
+        return match self.key_index[scan_type] {
+            0 => None,
+            row => Some(&self.keys[row as usize - 1]),
+        };
+    }
+
+    fn symbol(&self, modifiers: &KeyModifierState, key: Key) -> Option<KeySymbol> {
+        let entry = self.entry(key)?;
+
+        // Layouts without AltGr only ever see it when another layout falls back to them
+        if !self.has_alt_gr && modifiers.alt_gr {
+            let mut modifiers = *modifiers;
+            modifiers.alt_gr = false;
+
+            return Some(level_symbol(&entry.levels, entry.caps, &modifiers));
+        }
+
+        return Some(level_symbol(&entry.levels, entry.caps, modifiers));
+    }
+}
+
+impl<'a> Layout for StaticLayout<'a> {
+    fn key_into_char(&self, modifiers: &KeyModifierState, key: Key) -> Option<char> {
+        return match self.symbol(modifiers, key) {
+            Some(KeySymbol::Char(ch)) => Some(ch),
+            _ => None,
+        };
+    }
+
+    fn has_alt_gr(&self) -> bool {
+        return self.has_alt_gr;
+    }
+
+    fn dead_key(&self, modifiers: &KeyModifierState, key: Key) -> Option<DeadKey> {
+        return match self.symbol(modifiers, key) {
+            Some(KeySymbol::Dead(dead_key)) => Some(dead_key),
+            _ => None,
+        };
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __layout_symbol {
+    (_) => {
+        $crate::KeySymbol::Empty
+    };
+    ($ch:literal) => {
+        $crate::KeySymbol::Char($ch)
+    };
+    ($dead_key:ident) => {
+        $crate::KeySymbol::Dead($crate::DeadKey::$dead_key)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __layout_rows {
+    ($(
+        $scan_type:ident => ($base:tt, $shift:tt, $alt_gr:tt, $shift_alt_gr:tt, $caps:ident)
+    ),* $(,)?) => {
+        [$(
+            $crate::KeymapEntry::new(
+                $crate::ScanType::$scan_type,
+                $crate::CapsBehaviour::$caps,
+                [
+                    $crate::__layout_symbol!($base),
+                    $crate::__layout_symbol!($shift),
+                    $crate::__layout_symbol!($alt_gr),
+                    $crate::__layout_symbol!($shift_alt_gr),
+                ],
+            )
+        ),*]
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __layout_struct {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident;
+        $table:expr
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis struct $name;
+
+        impl $name {
+            pub const TABLE: $crate::StaticLayout<'static> = $table;
+        }
+
+        impl $crate::Layout for $name {
+            fn key_into_char(
+                &self,
+                modifiers: &$crate::KeyModifierState,
+                key: $crate::Key,
+            ) -> Option<char> {
+                return Self::TABLE.key_into_char(modifiers, key);
+            }
+
+            fn has_alt_gr(&self) -> bool {
+                return Self::TABLE.has_alt_gr();
+            }
+
+            fn dead_key(
+                &self,
+                modifiers: &$crate::KeyModifierState,
+                key: $crate::Key,
+            ) -> Option<$crate::DeadKey> {
+                return Self::TABLE.dead_key(modifiers, key);
+            }
+        }
+    };
+}
+
+// Declares a unit struct that implements Layout through a StaticLayout:
+//
+//   layout! {
+//       pub struct MyLayout;
+//       alt_gr: true;
+//       keys {
+//           CharE => ('e', 'E', '€', _, Auto),
+//           SymbolEquals => (Acute, Grave, _, _, Ignore),
+//       }
+//       keypad {
+//           Num1 => ('1', _, _, _, Ignore),
+//       }
+//   }
+//
+// Each row is `ScanType => (base, shift, altgr, shift_altgr, caps)`. A level is a character, `_`
+// for nothing or the name of a DeadKey, caps is a CapsBehaviour. The keypad rows are optional,
+// `keypad: ROWS;` shares an array of rows between layouts instead.
+#[macro_export]
+macro_rules! layout {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident;
+        alt_gr: $has_alt_gr:expr;
+        keys { $($keys:tt)* }
+        keypad: $keypad:expr;
+    ) => {
+        $crate::__layout_struct! {
+            $(#[$attr])*
+            $vis struct $name;
+            $crate::StaticLayout::new(&$crate::__layout_rows!($($keys)*), &$keypad, $has_alt_gr)
+        }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident;
+        alt_gr: $has_alt_gr:expr;
+        keys { $($keys:tt)* }
+        $(keypad { $($keypad:tt)* })?
+    ) => {
+        $crate::__layout_struct! {
+            $(#[$attr])*
+            $vis struct $name;
+            $crate::StaticLayout::new(
+                &$crate::__layout_rows!($($keys)*),
+                &$crate::__layout_rows!($($($keypad)*)?),
+                $has_alt_gr,
+            )
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::layouts::test_util::press;
+    use super::super::{KeyState, Keyboard, ReaderMode, ScanType, USStandardLayout};
+    use super::*;
+
+    crate::layout! {
+        struct TestLayout;
+        alt_gr: true;
+        keys {
+            CharE => ('e', 'E', '€', _, Auto),
+            CharY => ('z', 'Z', _, _, Auto),
+            Num5 => ('5', '%', _, _, Shift),
+            SymbolEquals => (Acute, Grave, _, _, Ignore),
+        }
+        keypad {
+            Num5 => ('5', _, _, _, Ignore),
+        }
+    }
+
+    #[test]
+    fn test_layout_macro() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, TestLayout);
+
+        press(&mut key_board, 0x45);
+        assert!(TestLayout.has_alt_gr());
+        assert_eq!(press(&mut key_board, 0x12), Some('e'));
+        assert_eq!(press(&mut key_board, 0x15), Some('z'));
+        assert_eq!(press(&mut key_board, 0x10), None);
+        assert_eq!(press(&mut key_board, 0x4c), Some('5'));
+
+        assert_eq!(press(&mut key_board, 0x0d), None);
+        assert_eq!(press(&mut key_board, 0x12), Some('é'));
+
+        key_board.input_byte(0xe0);
+        key_board.input_byte(0x38);
+        assert_eq!(press(&mut key_board, 0x12), Some('€'));
+        key_board.input_byte(0xe0);
+        key_board.input_byte(0xb8);
+
+        press(&mut key_board, 0x3a);
+        assert_eq!(press(&mut key_board, 0x12), Some('E'));
+        assert_eq!(press(&mut key_board, 0x06), Some('%'));
+        assert_eq!(press(&mut key_board, 0x4c), Some('5'));
+    }
+
+    #[test]
+    fn test_entry() {
+        let entry = TestLayout::TABLE
+            .entry(Key::new(ScanType::SymbolEquals, KeyState::Pressed))
+            .unwrap();
+
+        assert_eq!(entry.levels[0], KeySymbol::Dead(DeadKey::Acute));
+        assert_eq!(
+            TestLayout::TABLE.entry(Key::new(ScanType::CharQ, KeyState::Pressed)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_us_keypad() {
+        let mut key_board = Keyboard::new(ReaderMode::Set1, USStandardLayout);
+
+        press(&mut key_board, 0x45);
+        assert_eq!(press(&mut key_board, 0x4f), Some('1'));
+        assert_eq!(press(&mut key_board, 0x53), Some('.'));
+        assert_eq!(press(&mut key_board, 0x4e), Some('+'));
+
+        key_board.input_byte(0x2a);
+        assert_eq!(press(&mut key_board, 0x4f), None);
+        assert_eq!(press(&mut key_board, 0x02), Some('!'));
+        assert_eq!(press(&mut key_board, 0x0f), Some('\t'));
+    }
+}
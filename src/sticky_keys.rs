@@ -0,0 +1,237 @@
+use super::layout::KeyModifierState;
+use super::{Key, ScanType};
+
+const MODIFIER_KEYS: [ScanType; 8] = [
+    ScanType::LeftShift,
+    ScanType::RightShift,
+    ScanType::LeftCtrl,
+    ScanType::RightCtrl,
+    ScanType::LeftAlt,
+    ScanType::RightAlt,
+    ScanType::LeftGUI,
+    ScanType::RightGUI,
+];
+
+fn modifier_bit(scan_type: ScanType) -> Option<u8> {
+    let index = MODIFIER_KEYS.iter().position(|m| *m == scan_type)?;
+
+    return Some(1 << index);
+}
+
+pub(crate) fn is_modifier(scan_type: ScanType) -> bool {
+    return modifier_bit(scan_type).is_some();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+// A set of the eight modifier keys, e.g. the ones Sticky Keys has latched
+pub struct ModifierKeys {
+    bits: u8,
+}
+
+impl ModifierKeys {
+    pub const fn new() -> Self {
+        return Self { bits: 0 };
+    }
+
+    pub fn contains(&self, scan_type: ScanType) -> bool {
+        return match modifier_bit(scan_type) {
+            Some(bit) => self.bits & bit != 0,
+            None => false,
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.bits == 0;
+    }
+
+    pub fn shift(&self) -> bool {
+        return self.contains(ScanType::LeftShift) || self.contains(ScanType::RightShift);
+    }
+
+    pub fn ctrl(&self) -> bool {
+        return self.contains(ScanType::LeftCtrl) || self.contains(ScanType::RightCtrl);
+    }
+
+    pub fn alt(&self) -> bool {
+        return self.contains(ScanType::LeftAlt) || self.contains(ScanType::RightAlt);
+    }
+
+    pub fn gui(&self) -> bool {
+        return self.contains(ScanType::LeftGUI) || self.contains(ScanType::RightGUI);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ScanType> + '_ {
+        return MODIFIER_KEYS
+            .iter()
+            .copied()
+            .filter(move |m| self.contains(*m));
+    }
+
+    pub(crate) fn insert(&mut self, scan_type: ScanType) {
+        if let Some(bit) = modifier_bit(scan_type) {
+            self.bits |= bit;
+        }
+    }
+
+    // Returns whether the key was in the set
+    pub(crate) fn remove(&mut self, scan_type: ScanType) -> bool {
+        let present = self.contains(scan_type);
+
+        if let Some(bit) = modifier_bit(scan_type) {
+            self.bits &= !bit;
+        }
+
+        return present;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Sticky Keys: tapping a modifier latches it for the next key, tapping it again locks it and a
+// third tap unlocks it. A modifier that was held while another key went down was part of a chord
+// and isn't latched. The latched and locked keys live in KeyModifierState where they are folded
+// into the modifier fields, this keeps what is actually held.
+pub(crate) struct StickyKeys {
+    held: ModifierKeys,
+    chorded: ModifierKeys,
+}
+
+impl StickyKeys {
+    // Starts with the modifiers that are down right now
+    pub(crate) fn new(modifiers: &KeyModifierState) -> Self {
+        let mut held = ModifierKeys::new();
+
+        for scan_type in MODIFIER_KEYS.iter() {
+            if modifiers.modifier(*scan_type) {
+                held.insert(*scan_type);
+            }
+        }
+
+        return Self {
+            held,
+            chorded: ModifierKeys::new(),
+        };
+    }
+
+    // Called after KeyModifierState::apply_key for every key
+    pub(crate) fn apply_key(&mut self, modifiers: &mut KeyModifierState, key: &Key) {
+        let scan_type = key.scan_type();
+
+        if !is_modifier(scan_type) {
+            if key.is_pressed() {
+                self.chorded.bits |= self.held.bits;
+            }
+
+            return;
+        }
+
+        if key.is_pressed() {
+            self.held.remove(scan_type);
+            self.chorded.remove(scan_type);
+            self.chorded.bits |= self.held.bits;
+            self.held.insert(scan_type);
+        } else {
+            self.held.remove(scan_type);
+
+            if !self.chorded.remove(scan_type) {
+                Self::tap(modifiers, scan_type);
+            }
+        }
+
+        self.update(modifiers, scan_type);
+    }
+
+    fn tap(modifiers: &mut KeyModifierState, scan_type: ScanType) {
+        if modifiers.locked.remove(scan_type) {
+            return;
+        }
+
+        if modifiers.latched.remove(scan_type) {
+            modifiers.locked.insert(scan_type);
+        } else {
+            modifiers.latched.insert(scan_type);
+        }
+    }
+
+    fn update(&self, modifiers: &mut KeyModifierState, scan_type: ScanType) {
+        let down = self.held.contains(scan_type)
+            || modifiers.latched.contains(scan_type)
+            || modifiers.locked.contains(scan_type);
+
+        modifiers.set_modifier(scan_type, down);
+    }
+
+    // The latched modifiers only last for one key
+    pub(crate) fn release_latched(&self, modifiers: &mut KeyModifierState) {
+        let latched = modifiers.latched;
+        modifiers.latched = ModifierKeys::new();
+
+        for scan_type in latched.iter() {
+            self.update(modifiers, scan_type);
+        }
+    }
+
+    // Turning Sticky Keys off drops every latch and lock
+    pub(crate) fn clear(&self, modifiers: &mut KeyModifierState) {
+        let sticky = ModifierKeys {
+            bits: modifiers.latched.bits | modifiers.locked.bits,
+        };
+        modifiers.latched = ModifierKeys::new();
+        modifiers.locked = ModifierKeys::new();
+
+        for scan_type in sticky.iter() {
+            self.update(modifiers, scan_type);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::KeyState;
+    use super::*;
+
+    fn apply(sticky: &mut StickyKeys, modifiers: &mut KeyModifierState, key: Key) {
+        modifiers.apply_key(&key);
+        sticky.apply_key(modifiers, &key);
+    }
+
+    fn tap(sticky: &mut StickyKeys, modifiers: &mut KeyModifierState, scan_type: ScanType) {
+        apply(sticky, modifiers, Key::new(scan_type, KeyState::Pressed));
+        apply(sticky, modifiers, Key::new(scan_type, KeyState::Released));
+    }
+
+    #[test]
+    fn test_modifier_keys() {
+        let mut keys = ModifierKeys::new();
+
+        assert!(keys.is_empty());
+        keys.insert(ScanType::RightCtrl);
+        keys.insert(ScanType::CharA);
+        assert!(keys.ctrl());
+        assert!(!keys.shift());
+        assert!(keys.iter().eq([ScanType::RightCtrl].iter().copied()));
+        assert!(keys.remove(ScanType::RightCtrl));
+        assert!(!keys.remove(ScanType::RightCtrl));
+    }
+
+    #[test]
+    fn test_latch_lock_unlock() {
+        let mut sticky = StickyKeys::new(&KeyModifierState::new());
+        let mut modifiers = KeyModifierState::new();
+
+        tap(&mut sticky, &mut modifiers, ScanType::LeftShift);
+        assert!(modifiers.latched.contains(ScanType::LeftShift));
+        assert!(modifiers.left_shift);
+
+        tap(&mut sticky, &mut modifiers, ScanType::LeftShift);
+        assert!(modifiers.latched.is_empty());
+        assert!(modifiers.locked.contains(ScanType::LeftShift));
+        assert!(modifiers.left_shift);
+
+        sticky.release_latched(&mut modifiers);
+        assert!(modifiers.left_shift);
+
+        tap(&mut sticky, &mut modifiers, ScanType::LeftShift);
+        assert!(modifiers.locked.is_empty());
+        assert!(!modifiers.left_shift);
+    }
+}
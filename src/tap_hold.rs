@@ -0,0 +1,364 @@
+use super::event::EventQueue;
+use super::scan_code_set::ScanTypeSet;
+use super::{Key, KeyState, ScanType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// A key that types one key when tapped and acts as another while held, e.g. CapsLock as Escape
+// on tap and LeftCtrl on hold. Holding it longer than the term makes it a hold. With permissive
+// hold another key pressed and released while it is down makes it a hold straight away.
+pub struct DualRole {
+    pub key: ScanType,
+    pub tap: ScanType,
+    pub hold: ScanType,
+    // Milliseconds
+    pub term: u32,
+    pub permissive_hold: bool,
+}
+
+impl DualRole {
+    pub const fn new(key: ScanType, tap: ScanType, hold: ScanType) -> Self {
+        return Self {
+            key,
+            tap,
+            hold,
+            term: 200,
+            permissive_hold: true,
+        };
+    }
+
+    pub const fn with_term(mut self, term: u32) -> Self {
+        self.term = term;
+        return self;
+    }
+
+    pub const fn with_permissive_hold(mut self, permissive_hold: bool) -> Self {
+        self.permissive_hold = permissive_hold;
+        return self;
+    }
+}
+
+const TAP_HOLD_CAPACITY: usize = 16;
+// Keys pressed while a dual-role key is undecided, a full buffer decides it as a hold
+const TAP_HOLD_BUFFER: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    rule: DualRole,
+    pressed_at: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+// The dual-role keys and what they are doing right now. Keys go in with a timestamp from the
+// caller's monotonic clock and come out as the keys they stand for, the keys pressed while a
+// dual-role key is undecided are held back until it is.
+pub struct TapHold {
+    rules: [Option<DualRole>; TAP_HOLD_CAPACITY],
+    pending: Option<Pending>,
+    buffer: [Option<Key>; TAP_HOLD_BUFFER],
+    holding: ScanTypeSet,
+    output: EventQueue<Key>,
+}
+
+impl TapHold {
+    pub const fn new() -> Self {
+        return Self {
+            rules: [None; TAP_HOLD_CAPACITY],
+            pending: None,
+            buffer: [None; TAP_HOLD_BUFFER],
+            holding: ScanTypeSet::new(),
+            output: EventQueue::new(),
+        };
+    }
+
+    // A second rule for the same key replaces the first
+    pub fn register(&mut self, rule: DualRole) -> Result<(), &'static str> {
+        self.unregister(rule.key);
+
+        for slot in self.rules.iter_mut() {
+            if slot.is_none() {
+                *slot = Some(rule);
+                return Ok(());
+            }
+        }
+
+        return Err("Tap-hold table full.");
+    }
+
+    pub fn unregister(&mut self, key: ScanType) -> bool {
+        for slot in self.rules.iter_mut() {
+            if slot.map(|rule| rule.key) == Some(key) {
+                *slot = None;
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    pub fn rule(&self, key: ScanType) -> Option<DualRole> {
+        return self
+            .rules
+            .iter()
+            .flatten()
+            .find(|rule| rule.key == key)
+            .copied();
+    }
+
+    pub(crate) fn is_idle(&self) -> bool {
+        return self.rules.iter().all(|rule| rule.is_none()) && self.pending.is_none();
+    }
+
+    pub fn input(&mut self, key: Key, now: u64) {
+        self.tick(now);
+        self.process(key, now);
+    }
+
+    // Decides a dual-role key as a hold once its term has passed
+    pub fn tick(&mut self, now: u64) {
+        if let Some(pending) = self.pending {
+            if now.saturating_sub(pending.pressed_at) >= pending.rule.term as u64 {
+                self.decide_hold(now);
+            }
+        }
+    }
+
+    pub fn next_key(&mut self) -> Option<Key> {
+        return self.output.pop();
+    }
+
+    fn process(&mut self, key: Key, now: u64) {
+        let scan_type = key.scan_type();
+
+        if let Some(pending) = self.pending {
+            if scan_type == pending.rule.key {
+                // Repeats say nothing, a release before the term is a tap
+                if !key.is_pressed() {
+                    self.pending = None;
+                    self.output
+                        .push(Key::new(pending.rule.tap, KeyState::Pressed));
+                    self.output
+                        .push(Key::new(pending.rule.tap, KeyState::Released));
+                    self.flush(now);
+                }
+
+                return;
+            }
+
+            let pressed_during = self.buffer.iter().flatten().any(|buffered| {
+                return buffered.scan_type() == scan_type && buffered.is_pressed();
+            });
+
+            if !self.hold_back(key) {
+                self.decide_hold(now);
+                self.process(key, now);
+            } else if pending.rule.permissive_hold && !key.is_pressed() && pressed_during {
+                self.decide_hold(now);
+            }
+
+            return;
+        }
+
+        let rule = match self.rule(scan_type) {
+            Some(rule) => rule,
+            None => {
+                self.output.push(key);
+                return;
+            }
+        };
+
+        if key.is_pressed() {
+            if !self.holding.contains(scan_type) {
+                self.pending = Some(Pending {
+                    rule,
+                    pressed_at: now,
+                });
+            }
+        } else if self.holding.remove(scan_type) {
+            self.output.push(Key::new(rule.hold, KeyState::Released));
+        } else {
+            // Pressed before the rule was registered
+            self.output.push(key);
+        }
+    }
+
+    fn hold_back(&mut self, key: Key) -> bool {
+        for slot in self.buffer.iter_mut() {
+            if slot.is_none() {
+                *slot = Some(key);
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    fn decide_hold(&mut self, now: u64) {
+        if let Some(pending) = self.pending.take() {
+            self.holding.insert(pending.rule.key);
+            self.output
+                .push(Key::new(pending.rule.hold, KeyState::Pressed));
+            self.flush(now);
+        }
+    }
+
+    // The held back keys go through again now the dual-role key is decided, one of them may be
+    // another dual-role key
+    fn flush(&mut self, now: u64) {
+        let buffer = self.buffer;
+        self.buffer = [None; TAP_HOLD_BUFFER];
+
+        for key in buffer.iter().flatten() {
+            self.process(*key, now);
+        }
+    }
+}
+
+impl Default for TapHold {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAPS: DualRole = DualRole::new(ScanType::CapsLock, ScanType::Escape, ScanType::LeftCtrl);
+
+    fn press(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Pressed);
+    }
+
+    fn release(scan_type: ScanType) -> Key {
+        return Key::new(scan_type, KeyState::Released);
+    }
+
+    fn assert_output(tap_hold: &mut TapHold, keys: &[Key]) {
+        for key in keys {
+            assert_eq!(tap_hold.next_key(), Some(*key));
+        }
+
+        assert_eq!(tap_hold.next_key(), None);
+    }
+
+    fn engine(rule: DualRole) -> TapHold {
+        let mut tap_hold = TapHold::new();
+        tap_hold.register(rule).unwrap();
+
+        return tap_hold;
+    }
+
+    #[test]
+    fn test_tap() {
+        let mut tap_hold = engine(CAPS);
+
+        tap_hold.input(press(ScanType::CapsLock), 0);
+        tap_hold.input(press(ScanType::CapsLock), 50);
+        assert_output(&mut tap_hold, &[]);
+        tap_hold.input(release(ScanType::CapsLock), 100);
+        assert_output(
+            &mut tap_hold,
+            &[press(ScanType::Escape), release(ScanType::Escape)],
+        );
+
+        // Other keys pass straight through
+        tap_hold.input(press(ScanType::CharA), 200);
+        assert_output(&mut tap_hold, &[press(ScanType::CharA)]);
+    }
+
+    #[test]
+    fn test_hold_after_term() {
+        let mut tap_hold = engine(CAPS.with_term(150));
+
+        tap_hold.input(press(ScanType::CapsLock), 0);
+        tap_hold.tick(100);
+        assert_output(&mut tap_hold, &[]);
+        tap_hold.tick(150);
+        assert_output(&mut tap_hold, &[press(ScanType::LeftCtrl)]);
+
+        tap_hold.input(press(ScanType::CapsLock), 180);
+        tap_hold.input(press(ScanType::CharC), 200);
+        tap_hold.input(release(ScanType::CapsLock), 300);
+        assert_output(
+            &mut tap_hold,
+            &[press(ScanType::CharC), release(ScanType::LeftCtrl)],
+        );
+    }
+
+    #[test]
+    fn test_permissive_hold() {
+        let mut tap_hold = engine(CAPS);
+
+        tap_hold.input(press(ScanType::CapsLock), 0);
+        tap_hold.input(press(ScanType::CharC), 20);
+        assert_output(&mut tap_hold, &[]);
+        tap_hold.input(release(ScanType::CharC), 40);
+        assert_output(
+            &mut tap_hold,
+            &[
+                press(ScanType::LeftCtrl),
+                press(ScanType::CharC),
+                release(ScanType::CharC),
+            ],
+        );
+        tap_hold.input(release(ScanType::CapsLock), 60);
+        assert_output(&mut tap_hold, &[release(ScanType::LeftCtrl)]);
+    }
+
+    #[test]
+    fn test_rolling_over_is_a_tap() {
+        let mut tap_hold = engine(CAPS.with_permissive_hold(false));
+
+        // Without permissive hold the inner key doesn't decide it
+        tap_hold.input(press(ScanType::CapsLock), 0);
+        tap_hold.input(press(ScanType::CharC), 20);
+        tap_hold.input(release(ScanType::CharC), 40);
+        assert_output(&mut tap_hold, &[]);
+        tap_hold.input(release(ScanType::CapsLock), 60);
+        assert_output(
+            &mut tap_hold,
+            &[
+                press(ScanType::Escape),
+                release(ScanType::Escape),
+                press(ScanType::CharC),
+                release(ScanType::CharC),
+            ],
+        );
+
+        // A key pressed before and released during the dual-role key is a roll, not a hold
+        let mut tap_hold = engine(CAPS);
+        tap_hold.input(press(ScanType::CharA), 0);
+        tap_hold.input(press(ScanType::CapsLock), 10);
+        tap_hold.input(release(ScanType::CharA), 20);
+        tap_hold.input(release(ScanType::CapsLock), 30);
+        assert_output(
+            &mut tap_hold,
+            &[
+                press(ScanType::CharA),
+                press(ScanType::Escape),
+                release(ScanType::Escape),
+                release(ScanType::CharA),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let mut tap_hold = TapHold::new();
+
+        assert!(tap_hold.is_idle());
+        tap_hold.register(CAPS).unwrap();
+        tap_hold.register(CAPS.with_term(300)).unwrap();
+        assert_eq!(tap_hold.rule(ScanType::CapsLock).unwrap().term, 300);
+        assert!(tap_hold.unregister(ScanType::CapsLock));
+        assert!(!tap_hold.unregister(ScanType::CapsLock));
+
+        for scan_type in ScanType::ALL.iter().take(TAP_HOLD_CAPACITY) {
+            tap_hold
+                .register(DualRole::new(*scan_type, *scan_type, ScanType::LeftShift))
+                .unwrap();
+        }
+
+        assert!(tap_hold.register(CAPS).is_err());
+    }
+}
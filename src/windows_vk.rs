@@ -0,0 +1,346 @@
+// Translation between keys and Windows virtual-key (VK_*) codes.
+// Keypad keys map to the VK_NUMPAD* codes, the extended flag is set for any key whose
+// set 1 scan code is prefixed with 0xe0.
+
+use super::encoder::Encoder;
+use super::{Key, KeyState, ScanType};
+
+pub const VK_BACK: u8 = 0x08;
+pub const VK_TAB: u8 = 0x09;
+pub const VK_RETURN: u8 = 0x0d;
+pub const VK_SHIFT: u8 = 0x10;
+pub const VK_CONTROL: u8 = 0x11;
+pub const VK_MENU: u8 = 0x12;
+pub const VK_PAUSE: u8 = 0x13;
+pub const VK_CAPITAL: u8 = 0x14;
+pub const VK_KANA: u8 = 0x15;
+pub const VK_ESCAPE: u8 = 0x1b;
+pub const VK_CONVERT: u8 = 0x1c;
+pub const VK_NONCONVERT: u8 = 0x1d;
+pub const VK_SPACE: u8 = 0x20;
+pub const VK_PRIOR: u8 = 0x21;
+pub const VK_NEXT: u8 = 0x22;
+pub const VK_END: u8 = 0x23;
+pub const VK_HOME: u8 = 0x24;
+pub const VK_LEFT: u8 = 0x25;
+pub const VK_UP: u8 = 0x26;
+pub const VK_RIGHT: u8 = 0x27;
+pub const VK_DOWN: u8 = 0x28;
+pub const VK_SNAPSHOT: u8 = 0x2c;
+pub const VK_INSERT: u8 = 0x2d;
+pub const VK_DELETE: u8 = 0x2e;
+pub const VK_CLEAR: u8 = 0x0c;
+pub const VK_LWIN: u8 = 0x5b;
+pub const VK_RWIN: u8 = 0x5c;
+pub const VK_APPS: u8 = 0x5d;
+pub const VK_SLEEP: u8 = 0x5f;
+pub const VK_NUMPAD0: u8 = 0x60;
+pub const VK_MULTIPLY: u8 = 0x6a;
+pub const VK_ADD: u8 = 0x6b;
+pub const VK_SUBTRACT: u8 = 0x6d;
+pub const VK_DECIMAL: u8 = 0x6e;
+pub const VK_DIVIDE: u8 = 0x6f;
+pub const VK_F1: u8 = 0x70;
+pub const VK_NUMLOCK: u8 = 0x90;
+pub const VK_SCROLL: u8 = 0x91;
+pub const VK_LSHIFT: u8 = 0xa0;
+pub const VK_RSHIFT: u8 = 0xa1;
+pub const VK_LCONTROL: u8 = 0xa2;
+pub const VK_RCONTROL: u8 = 0xa3;
+pub const VK_LMENU: u8 = 0xa4;
+pub const VK_RMENU: u8 = 0xa5;
+pub const VK_BROWSER_BACK: u8 = 0xa6;
+pub const VK_BROWSER_FORWARD: u8 = 0xa7;
+pub const VK_BROWSER_REFRESH: u8 = 0xa8;
+pub const VK_BROWSER_STOP: u8 = 0xa9;
+pub const VK_BROWSER_SEARCH: u8 = 0xaa;
+pub const VK_BROWSER_FAVORITES: u8 = 0xab;
+pub const VK_BROWSER_HOME: u8 = 0xac;
+pub const VK_VOLUME_MUTE: u8 = 0xad;
+pub const VK_VOLUME_DOWN: u8 = 0xae;
+pub const VK_VOLUME_UP: u8 = 0xaf;
+pub const VK_MEDIA_NEXT_TRACK: u8 = 0xb0;
+pub const VK_MEDIA_PREV_TRACK: u8 = 0xb1;
+pub const VK_MEDIA_STOP: u8 = 0xb2;
+pub const VK_MEDIA_PLAY_PAUSE: u8 = 0xb3;
+pub const VK_LAUNCH_MAIL: u8 = 0xb4;
+pub const VK_LAUNCH_MEDIA_SELECT: u8 = 0xb5;
+pub const VK_LAUNCH_APP1: u8 = 0xb6;
+pub const VK_LAUNCH_APP2: u8 = 0xb7;
+pub const VK_OEM_1: u8 = 0xba;
+pub const VK_OEM_PLUS: u8 = 0xbb;
+pub const VK_OEM_COMMA: u8 = 0xbc;
+pub const VK_OEM_MINUS: u8 = 0xbd;
+pub const VK_OEM_PERIOD: u8 = 0xbe;
+pub const VK_OEM_2: u8 = 0xbf;
+pub const VK_OEM_3: u8 = 0xc0;
+pub const VK_OEM_4: u8 = 0xdb;
+pub const VK_OEM_5: u8 = 0xdc;
+pub const VK_OEM_6: u8 = 0xdd;
+pub const VK_OEM_7: u8 = 0xde;
+pub const VK_OEM_102: u8 = 0xe2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualKey {
+    pub code: u8,
+    pub extended: bool,
+}
+
+// With NumLock off Windows reports the keypad as non-extended navigation keys
+const KEYPAD_NAVIGATION: [(u8, ScanType); 11] = [
+    (VK_INSERT, ScanType::Num0),
+    (VK_END, ScanType::Num1),
+    (VK_DOWN, ScanType::Num2),
+    (VK_NEXT, ScanType::Num3),
+    (VK_LEFT, ScanType::Num4),
+    (VK_CLEAR, ScanType::Num5),
+    (VK_RIGHT, ScanType::Num6),
+    (VK_HOME, ScanType::Num7),
+    (VK_UP, ScanType::Num8),
+    (VK_PRIOR, ScanType::Num9),
+    (VK_DELETE, ScanType::SymbolPeriod),
+];
+
+fn virtual_key_code(scan_type: ScanType, keypad: bool) -> Option<u8> {
+    use ScanType::*;
+
+    if keypad {
+        return match scan_type {
+            Num0 | Num1 | Num2 | Num3 | Num4 | Num5 | Num6 | Num7 | Num8 | Num9 => {
+                Some(VK_NUMPAD0 + scan_type.as_u8() - Num0.as_u8())
+            }
+            SymbolAsterisk => Some(VK_MULTIPLY),
+            SymbolPlus => Some(VK_ADD),
+            SymbolMinus => Some(VK_SUBTRACT),
+            SymbolPeriod => Some(VK_DECIMAL),
+            SymbolForwardSlash => Some(VK_DIVIDE),
+            Enter => Some(VK_RETURN),
+            _ => None,
+        };
+    }
+
+    if scan_type.is_num() {
+        return Some(b'0' + scan_type.as_u8() - Num0.as_u8());
+    } else if scan_type.is_letter() {
+        return Some(b'A' + scan_type.as_u8() - CharA.as_u8());
+    } else if scan_type >= F1 && scan_type <= F12 {
+        return Some(VK_F1 + scan_type.as_u8() - F1.as_u8());
+    }
+
+    return match scan_type {
+        SymbolMinus => Some(VK_OEM_MINUS),
+        SymbolEquals => Some(VK_OEM_PLUS),
+        SymbolOpenSquareBracket => Some(VK_OEM_4),
+        SymbolCloseSquareBracket => Some(VK_OEM_6),
+        SymbolSemicolon => Some(VK_OEM_1),
+        SymbolSingleQuote => Some(VK_OEM_7),
+        SymbolBacktick => Some(VK_OEM_3),
+        SymbolBackslash => Some(VK_OEM_5),
+        Iso102nd => Some(VK_OEM_102),
+        SymbolComma => Some(VK_OEM_COMMA),
+        SymbolPeriod => Some(VK_OEM_PERIOD),
+        SymbolForwardSlash => Some(VK_OEM_2),
+        Escape => Some(VK_ESCAPE),
+        Backspace => Some(VK_BACK),
+        Tab => Some(VK_TAB),
+        Enter => Some(VK_RETURN),
+        LeftCtrl => Some(VK_LCONTROL),
+        RightCtrl => Some(VK_RCONTROL),
+        LeftShift => Some(VK_LSHIFT),
+        RightShift => Some(VK_RSHIFT),
+        LeftAlt => Some(VK_LMENU),
+        RightAlt => Some(VK_RMENU),
+        LeftGUI => Some(VK_LWIN),
+        RightGUI => Some(VK_RWIN),
+        Space => Some(VK_SPACE),
+        NumLock => Some(VK_NUMLOCK),
+        ScrollLock => Some(VK_SCROLL),
+        CapsLock => Some(VK_CAPITAL),
+        Home => Some(VK_HOME),
+        PageUp => Some(VK_PRIOR),
+        PageDown => Some(VK_NEXT),
+        CursorUp => Some(VK_UP),
+        CursorLeft => Some(VK_LEFT),
+        CursorRight => Some(VK_RIGHT),
+        CursorDown => Some(VK_DOWN),
+        Insert => Some(VK_INSERT),
+        Delete => Some(VK_DELETE),
+        End => Some(VK_END),
+        ACPISleep => Some(VK_SLEEP),
+        PreviousTrack => Some(VK_MEDIA_PREV_TRACK),
+        NextTrack => Some(VK_MEDIA_NEXT_TRACK),
+        Mute => Some(VK_VOLUME_MUTE),
+        Calculator => Some(VK_LAUNCH_APP2),
+        Stop => Some(VK_MEDIA_STOP),
+        Play => Some(VK_MEDIA_PLAY_PAUSE),
+        WWWHome => Some(VK_BROWSER_HOME),
+        VolumeUp => Some(VK_VOLUME_UP),
+        VolumeDown => Some(VK_VOLUME_DOWN),
+        Apps => Some(VK_APPS),
+        KatakanaHiragana => Some(VK_KANA),
+        Henkan => Some(VK_CONVERT),
+        Muhenkan => Some(VK_NONCONVERT),
+        WWWSearch => Some(VK_BROWSER_SEARCH),
+        WWWFavorites => Some(VK_BROWSER_FAVORITES),
+        WWWRefresh => Some(VK_BROWSER_REFRESH),
+        WWWStop => Some(VK_BROWSER_STOP),
+        WWWForward => Some(VK_BROWSER_FORWARD),
+        WWWBack => Some(VK_BROWSER_BACK),
+        MyComputer => Some(VK_LAUNCH_APP1),
+        Email => Some(VK_LAUNCH_MAIL),
+        MediaSelect => Some(VK_LAUNCH_MEDIA_SELECT),
+        PrintScreen => Some(VK_SNAPSHOT),
+        Pause => Some(VK_PAUSE),
+        _ => None,
+    };
+}
+
+pub fn to_virtual_key(key: &Key) -> Option<VirtualKey> {
+    let code = virtual_key_code(key.scan_type(), key.keypad())?;
+
+    return Some(VirtualKey {
+        code,
+        extended: Encoder::is_extended(key.scan_type(), key.keypad()),
+    });
+}
+
+pub fn from_virtual_key(vk: VirtualKey, state: KeyState) -> Option<Key> {
+    // The generic modifier codes use the extended flag to pick a side
+    let generic = match vk.code {
+        VK_SHIFT => Some(ScanType::LeftShift),
+        VK_CONTROL if vk.extended => Some(ScanType::RightCtrl),
+        VK_CONTROL => Some(ScanType::LeftCtrl),
+        VK_MENU if vk.extended => Some(ScanType::RightAlt),
+        VK_MENU => Some(ScanType::LeftAlt),
+        _ => None,
+    };
+
+    if let Some(scan_type) = generic {
+        return Some(Key::new(scan_type, state));
+    }
+
+    let mut loose_match = None;
+
+    for scan_type in ScanType::ALL.iter() {
+        for keypad in [false, true].iter() {
+            let key = if *keypad {
+                Key::new_keypad(*scan_type, state)
+            } else {
+                Key::new(*scan_type, state)
+            };
+
+            match to_virtual_key(&key) {
+                Some(v) if v == vk => return Some(key),
+                Some(v) if v.code == vk.code && loose_match.is_none() => loose_match = Some(key),
+                _ => (),
+            }
+        }
+    }
+
+    // A non-extended navigation code comes from the keypad with NumLock off
+    if !vk.extended {
+        for (code, scan_type) in KEYPAD_NAVIGATION.iter() {
+            if *code == vk.code {
+                return Some(Key::new_keypad(*scan_type, state));
+            }
+        }
+    }
+
+    return loose_match;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_letters_and_numbers() {
+        assert_eq!(
+            to_virtual_key(&Key::new(ScanType::CharQ, KeyState::Pressed)),
+            Some(VirtualKey {
+                code: b'Q',
+                extended: false
+            })
+        );
+        assert_eq!(
+            to_virtual_key(&Key::new(ScanType::Num4, KeyState::Pressed)),
+            Some(VirtualKey {
+                code: b'4',
+                extended: false
+            })
+        );
+        assert_eq!(
+            to_virtual_key(&Key::new_keypad(ScanType::Num4, KeyState::Pressed)),
+            Some(VirtualKey {
+                code: VK_NUMPAD0 + 4,
+                extended: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_extended_flag() {
+        assert_eq!(
+            to_virtual_key(&Key::new(ScanType::RightCtrl, KeyState::Pressed)),
+            Some(VirtualKey {
+                code: VK_RCONTROL,
+                extended: true
+            })
+        );
+        assert_eq!(
+            to_virtual_key(&Key::new_keypad(ScanType::Enter, KeyState::Pressed)),
+            Some(VirtualKey {
+                code: VK_RETURN,
+                extended: true
+            })
+        );
+        assert_eq!(
+            to_virtual_key(&Key::new(ScanType::Enter, KeyState::Pressed)),
+            Some(VirtualKey {
+                code: VK_RETURN,
+                extended: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_reverse_lookup() {
+        for scan_type in ScanType::ALL.iter() {
+            for keypad in [false, true].iter() {
+                let key = if *keypad {
+                    Key::new_keypad(*scan_type, KeyState::Released)
+                } else {
+                    Key::new(*scan_type, KeyState::Released)
+                };
+
+                if let Some(vk) = to_virtual_key(&key) {
+                    assert_eq!(from_virtual_key(vk, KeyState::Released), Some(key));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_reverse_lookup_generic() {
+        assert_eq!(
+            from_virtual_key(
+                VirtualKey {
+                    code: VK_CONTROL,
+                    extended: true
+                },
+                KeyState::Pressed
+            ),
+            Some(Key::new(ScanType::RightCtrl, KeyState::Pressed))
+        );
+        assert_eq!(
+            from_virtual_key(
+                VirtualKey {
+                    code: VK_HOME,
+                    extended: false
+                },
+                KeyState::Pressed
+            ),
+            Some(Key::new_keypad(ScanType::Num7, KeyState::Pressed))
+        );
+    }
+}
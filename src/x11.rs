@@ -0,0 +1,344 @@
+// Translation between keys and X11 keysyms.
+// Keys that produce a character go through the layout, everything else (and the whole
+// keypad) uses the fixed function keysyms.
+
+use super::layout::{KeyModifierState, Layout};
+use super::{Key, KeyState, ScanType};
+
+pub const XK_BACKSPACE: u32 = 0xff08;
+pub const XK_TAB: u32 = 0xff09;
+pub const XK_RETURN: u32 = 0xff0d;
+pub const XK_PAUSE: u32 = 0xff13;
+pub const XK_SCROLL_LOCK: u32 = 0xff14;
+pub const XK_ESCAPE: u32 = 0xff1b;
+pub const XK_HOME: u32 = 0xff50;
+pub const XK_LEFT: u32 = 0xff51;
+pub const XK_UP: u32 = 0xff52;
+pub const XK_RIGHT: u32 = 0xff53;
+pub const XK_DOWN: u32 = 0xff54;
+pub const XK_PAGE_UP: u32 = 0xff55;
+pub const XK_PAGE_DOWN: u32 = 0xff56;
+pub const XK_END: u32 = 0xff57;
+pub const XK_PRINT: u32 = 0xff61;
+pub const XK_INSERT: u32 = 0xff63;
+pub const XK_MENU: u32 = 0xff67;
+pub const XK_MUHENKAN: u32 = 0xff22;
+pub const XK_HENKAN: u32 = 0xff23;
+pub const XK_HIRAGANA_KATAKANA: u32 = 0xff27;
+pub const XK_NUM_LOCK: u32 = 0xff7f;
+pub const XK_KP_ENTER: u32 = 0xff8d;
+pub const XK_KP_HOME: u32 = 0xff95;
+pub const XK_KP_LEFT: u32 = 0xff96;
+pub const XK_KP_UP: u32 = 0xff97;
+pub const XK_KP_RIGHT: u32 = 0xff98;
+pub const XK_KP_DOWN: u32 = 0xff99;
+pub const XK_KP_PAGE_UP: u32 = 0xff9a;
+pub const XK_KP_PAGE_DOWN: u32 = 0xff9b;
+pub const XK_KP_END: u32 = 0xff9c;
+pub const XK_KP_BEGIN: u32 = 0xff9d;
+pub const XK_KP_INSERT: u32 = 0xff9e;
+pub const XK_KP_DELETE: u32 = 0xff9f;
+pub const XK_KP_MULTIPLY: u32 = 0xffaa;
+pub const XK_KP_ADD: u32 = 0xffab;
+pub const XK_KP_SUBTRACT: u32 = 0xffad;
+pub const XK_KP_DECIMAL: u32 = 0xffae;
+pub const XK_KP_DIVIDE: u32 = 0xffaf;
+pub const XK_KP_0: u32 = 0xffb0;
+pub const XK_F1: u32 = 0xffbe;
+pub const XK_SHIFT_L: u32 = 0xffe1;
+pub const XK_SHIFT_R: u32 = 0xffe2;
+pub const XK_CONTROL_L: u32 = 0xffe3;
+pub const XK_CONTROL_R: u32 = 0xffe4;
+pub const XK_CAPS_LOCK: u32 = 0xffe5;
+pub const XK_ALT_L: u32 = 0xffe9;
+pub const XK_ALT_R: u32 = 0xffea;
+pub const XK_SUPER_L: u32 = 0xffeb;
+pub const XK_SUPER_R: u32 = 0xffec;
+pub const XK_DELETE: u32 = 0xffff;
+
+pub const XF86XK_AUDIO_LOWER_VOLUME: u32 = 0x1008_ff11;
+pub const XF86XK_AUDIO_MUTE: u32 = 0x1008_ff12;
+pub const XF86XK_AUDIO_RAISE_VOLUME: u32 = 0x1008_ff13;
+pub const XF86XK_AUDIO_PLAY: u32 = 0x1008_ff14;
+pub const XF86XK_AUDIO_STOP: u32 = 0x1008_ff15;
+pub const XF86XK_AUDIO_PREV: u32 = 0x1008_ff16;
+pub const XF86XK_AUDIO_NEXT: u32 = 0x1008_ff17;
+pub const XF86XK_HOME_PAGE: u32 = 0x1008_ff18;
+pub const XF86XK_MAIL: u32 = 0x1008_ff19;
+pub const XF86XK_SEARCH: u32 = 0x1008_ff1b;
+pub const XF86XK_CALCULATOR: u32 = 0x1008_ff1d;
+pub const XF86XK_BACK: u32 = 0x1008_ff26;
+pub const XF86XK_FORWARD: u32 = 0x1008_ff27;
+pub const XF86XK_STOP: u32 = 0x1008_ff28;
+pub const XF86XK_REFRESH: u32 = 0x1008_ff29;
+pub const XF86XK_POWER_OFF: u32 = 0x1008_ff2a;
+pub const XF86XK_WAKE_UP: u32 = 0x1008_ff2b;
+pub const XF86XK_SLEEP: u32 = 0x1008_ff2f;
+pub const XF86XK_FAVORITES: u32 = 0x1008_ff30;
+pub const XF86XK_AUDIO_MEDIA: u32 = 0x1008_ff32;
+pub const XF86XK_MY_COMPUTER: u32 = 0x1008_ff33;
+
+// Keysyms for characters, Latin-1 maps directly and everything else uses the Unicode range
+pub fn char_to_keysym(ch: char) -> u32 {
+    let cp = ch as u32;
+
+    if (0x20..=0x7e).contains(&cp) || (0xa0..=0xff).contains(&cp) {
+        return cp;
+    }
+
+    return 0x0100_0000 | cp;
+}
+
+pub fn keysym_to_char(keysym: u32) -> Option<char> {
+    if (0x20..=0x7e).contains(&keysym) || (0xa0..=0xff).contains(&keysym) {
+        return core::char::from_u32(keysym);
+    } else if (0x0100_0100..=0x0110_ffff).contains(&keysym) {
+        return core::char::from_u32(keysym - 0x0100_0000);
+    }
+
+    return None;
+}
+
+fn keypad_keysym(modifiers: &KeyModifierState, scan_type: ScanType) -> Option<u32> {
+    use ScanType::*;
+
+    // Shift temporarily inverts NumLock
+    let num_lock = modifiers.num_lock != modifiers.shift_down();
+
+    return match scan_type {
+        Num0 | Num1 | Num2 | Num3 | Num4 | Num5 | Num6 | Num7 | Num8 | Num9 if num_lock => {
+            Some(XK_KP_0 + (scan_type.as_u8() - Num0.as_u8()) as u32)
+        }
+        SymbolPeriod if num_lock => Some(XK_KP_DECIMAL),
+        Num0 => Some(XK_KP_INSERT),
+        Num1 => Some(XK_KP_END),
+        Num2 => Some(XK_KP_DOWN),
+        Num3 => Some(XK_KP_PAGE_DOWN),
+        Num4 => Some(XK_KP_LEFT),
+        Num5 => Some(XK_KP_BEGIN),
+        Num6 => Some(XK_KP_RIGHT),
+        Num7 => Some(XK_KP_HOME),
+        Num8 => Some(XK_KP_UP),
+        Num9 => Some(XK_KP_PAGE_UP),
+        SymbolPeriod => Some(XK_KP_DELETE),
+        SymbolAsterisk => Some(XK_KP_MULTIPLY),
+        SymbolPlus => Some(XK_KP_ADD),
+        SymbolMinus => Some(XK_KP_SUBTRACT),
+        SymbolForwardSlash => Some(XK_KP_DIVIDE),
+        Enter => Some(XK_KP_ENTER),
+        _ => None,
+    };
+}
+
+fn function_keysym(scan_type: ScanType) -> Option<u32> {
+    use ScanType::*;
+
+    if scan_type >= F1 && scan_type <= F12 {
+        return Some(XK_F1 + (scan_type.as_u8() - F1.as_u8()) as u32);
+    }
+
+    return match scan_type {
+        Escape => Some(XK_ESCAPE),
+        Backspace => Some(XK_BACKSPACE),
+        Tab => Some(XK_TAB),
+        Enter => Some(XK_RETURN),
+        LeftCtrl => Some(XK_CONTROL_L),
+        RightCtrl => Some(XK_CONTROL_R),
+        LeftShift => Some(XK_SHIFT_L),
+        RightShift => Some(XK_SHIFT_R),
+        LeftAlt => Some(XK_ALT_L),
+        RightAlt => Some(XK_ALT_R),
+        LeftGUI => Some(XK_SUPER_L),
+        RightGUI => Some(XK_SUPER_R),
+        NumLock => Some(XK_NUM_LOCK),
+        ScrollLock => Some(XK_SCROLL_LOCK),
+        CapsLock => Some(XK_CAPS_LOCK),
+        Home => Some(XK_HOME),
+        PageUp => Some(XK_PAGE_UP),
+        PageDown => Some(XK_PAGE_DOWN),
+        CursorUp => Some(XK_UP),
+        CursorLeft => Some(XK_LEFT),
+        CursorRight => Some(XK_RIGHT),
+        CursorDown => Some(XK_DOWN),
+        Insert => Some(XK_INSERT),
+        Delete => Some(XK_DELETE),
+        End => Some(XK_END),
+        ACPIPower => Some(XF86XK_POWER_OFF),
+        ACPISleep => Some(XF86XK_SLEEP),
+        ACPIWake => Some(XF86XK_WAKE_UP),
+        PreviousTrack => Some(XF86XK_AUDIO_PREV),
+        NextTrack => Some(XF86XK_AUDIO_NEXT),
+        Mute => Some(XF86XK_AUDIO_MUTE),
+        Calculator => Some(XF86XK_CALCULATOR),
+        Stop => Some(XF86XK_AUDIO_STOP),
+        Play => Some(XF86XK_AUDIO_PLAY),
+        WWWHome => Some(XF86XK_HOME_PAGE),
+        VolumeUp => Some(XF86XK_AUDIO_RAISE_VOLUME),
+        VolumeDown => Some(XF86XK_AUDIO_LOWER_VOLUME),
+        Apps => Some(XK_MENU),
+        WWWSearch => Some(XF86XK_SEARCH),
+        WWWFavorites => Some(XF86XK_FAVORITES),
+        WWWRefresh => Some(XF86XK_REFRESH),
+        WWWStop => Some(XF86XK_STOP),
+        WWWForward => Some(XF86XK_FORWARD),
+        WWWBack => Some(XF86XK_BACK),
+        MyComputer => Some(XF86XK_MY_COMPUTER),
+        Email => Some(XF86XK_MAIL),
+        MediaSelect => Some(XF86XK_AUDIO_MEDIA),
+        PrintScreen => Some(XK_PRINT),
+        Pause => Some(XK_PAUSE),
+        KatakanaHiragana => Some(XK_HIRAGANA_KATAKANA),
+        Henkan => Some(XK_HENKAN),
+        Muhenkan => Some(XK_MUHENKAN),
+        _ => None,
+    };
+}
+
+pub fn key_to_keysym<T: Layout>(layout: &T, modifiers: &KeyModifierState, key: Key) -> Option<u32> {
+    if key.keypad() {
+        return keypad_keysym(modifiers, key.scan_type());
+    }
+
+    if let Some(keysym) = function_keysym(key.scan_type()) {
+        return Some(keysym);
+    }
+
+    return layout.key_into_char(modifiers, key).map(char_to_keysym);
+}
+
+// Finds a pressed key that produces the keysym together with the modifiers that must be held.
+pub fn keysym_to_key<T: Layout>(layout: &T, keysym: u32) -> Option<(Key, KeyModifierState)> {
+    let plain = KeyModifierState {
+        num_lock: true,
+        ..KeyModifierState::new()
+    };
+    let shifted = KeyModifierState {
+        left_shift: true,
+        ..plain
+    };
+    let no_num_lock = KeyModifierState::new();
+    let alt_gr = KeyModifierState {
+        right_alt: true,
+        alt_gr: true,
+        ..plain
+    };
+    let shifted_alt_gr = KeyModifierState {
+        left_shift: true,
+        ..alt_gr
+    };
+    let candidates = [plain, no_num_lock, shifted, alt_gr, shifted_alt_gr];
+    let count = if layout.has_alt_gr() { 5 } else { 3 };
+
+    for modifiers in candidates[..count].iter() {
+        for keypad in [false, true].iter() {
+            for scan_type in ScanType::ALL.iter() {
+                let key = if *keypad {
+                    Key::new_keypad(*scan_type, KeyState::Pressed)
+                } else {
+                    Key::new(*scan_type, KeyState::Pressed)
+                };
+
+                if key_to_keysym(layout, modifiers, key) == Some(keysym) {
+                    return Some((key, *modifiers));
+                }
+            }
+        }
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::layout::USStandardLayout;
+    use super::*;
+
+    #[test]
+    fn test_character_keysyms() {
+        let modifiers = KeyModifierState::new();
+
+        assert_eq!(
+            key_to_keysym(
+                &USStandardLayout,
+                &modifiers,
+                Key::new(ScanType::CharA, KeyState::Pressed)
+            ),
+            Some(0x61)
+        );
+
+        let shifted = KeyModifierState {
+            right_shift: true,
+            ..modifiers
+        };
+
+        assert_eq!(
+            key_to_keysym(
+                &USStandardLayout,
+                &shifted,
+                Key::new(ScanType::Num2, KeyState::Pressed)
+            ),
+            Some(0x40)
+        );
+    }
+
+    #[test]
+    fn test_function_keysyms() {
+        let modifiers = KeyModifierState::new();
+
+        assert_eq!(
+            key_to_keysym(
+                &USStandardLayout,
+                &modifiers,
+                Key::new(ScanType::Tab, KeyState::Pressed)
+            ),
+            Some(XK_TAB)
+        );
+        assert_eq!(
+            key_to_keysym(
+                &USStandardLayout,
+                &modifiers,
+                Key::new(ScanType::F5, KeyState::Pressed)
+            ),
+            Some(XK_F1 + 4)
+        );
+    }
+
+    #[test]
+    fn test_keypad_respects_num_lock() {
+        let mut modifiers = KeyModifierState::new();
+        let key = Key::new_keypad(ScanType::Num7, KeyState::Pressed);
+
+        assert_eq!(
+            key_to_keysym(&USStandardLayout, &modifiers, key),
+            Some(XK_KP_HOME)
+        );
+
+        modifiers.num_lock = true;
+        assert_eq!(
+            key_to_keysym(&USStandardLayout, &modifiers, key),
+            Some(XK_KP_0 + 7)
+        );
+    }
+
+    #[test]
+    fn test_unicode_keysyms() {
+        assert_eq!(char_to_keysym('é'), 0xe9);
+        assert_eq!(char_to_keysym('€'), 0x0100_20ac);
+        assert_eq!(keysym_to_char(0x0100_20ac), Some('€'));
+        assert_eq!(keysym_to_char(XK_ESCAPE), None);
+    }
+
+    #[test]
+    fn test_reverse_lookup() {
+        let (key, modifiers) = keysym_to_key(&USStandardLayout, 0x41).unwrap();
+        assert_eq!(key, Key::new(ScanType::CharA, KeyState::Pressed));
+        assert!(modifiers.shift_down());
+
+        let (key, modifiers) = keysym_to_key(&USStandardLayout, XK_KP_HOME).unwrap();
+        assert_eq!(key, Key::new_keypad(ScanType::Num7, KeyState::Pressed));
+        assert!(!modifiers.num_lock);
+
+        let (key, _) = keysym_to_key(&USStandardLayout, XK_KP_ENTER).unwrap();
+        assert_eq!(key, Key::new_keypad(ScanType::Enter, KeyState::Pressed));
+    }
+}